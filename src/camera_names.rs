@@ -0,0 +1,167 @@
+//! Camera make/model normalization
+//!
+//! Cameras write terse or inconsistent identifiers in EXIF ("SONY" +
+//! "ILCE-7RM4", "NIKON CORPORATION" + "NIKON Z 6_2"). This module maps
+//! them to the names photographers know ("Sony α7R IV", "Nikon Z6 II")
+//! using a built-in table that users can extend with their own TOML file.
+
+use crate::settings;
+use crate::PhotoBorderError;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Built-in table, embedded in the executable
+const BUILTIN_TABLE: &str = include_str!("../data/cameras.toml");
+
+/// File name of the user table in the configuration directory
+const USER_TABLE_FILE: &str = "cameras.toml";
+
+/// TOML layout of a camera name table
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CameraTable {
+    makes: HashMap<String, String>,
+    models: HashMap<String, String>,
+}
+
+/// Lookup table from raw EXIF make/model strings to marketing names
+///
+/// Keys are stored lowercased so that matching is case-insensitive.
+#[derive(Debug, Clone, Default)]
+pub struct CameraNames {
+    makes: HashMap<String, String>,
+    models: HashMap<String, String>,
+}
+
+impl CameraNames {
+    /// Returns the built-in table
+    pub fn builtin() -> Self {
+        let mut names = CameraNames::default();
+        names.merge_toml(BUILTIN_TABLE)
+            .expect("built-in camera table is valid TOML");
+        names
+    }
+
+    /// Loads the built-in table extended with the user's tables
+    ///
+    /// # Arguments
+    /// * `extra` - Optional table given on the command line
+    ///
+    /// # Returns
+    /// * `Ok(CameraNames)` with entries from later tables overriding
+    ///   earlier ones (built-in, then configuration directory, then `extra`)
+    /// * `Err(PhotoBorderError)` if `extra` cannot be read or parsed
+    ///
+    /// An invalid table in the configuration directory only prints a
+    /// warning, so that a typo does not prevent processing.
+    pub fn load(extra: Option<&Path>) -> Result<Self, PhotoBorderError> {
+        let mut names = Self::builtin();
+
+        if let Some(path) = settings::user_file(USER_TABLE_FILE) {
+            if let Err(e) = names.merge_file(&path) {
+                eprintln!("Warning: Ignoring camera table {}: {}", path.display(), e);
+            }
+        }
+
+        if let Some(path) = extra {
+            names.merge_file(path)?;
+        }
+
+        Ok(names)
+    }
+
+    /// Adds the entries of a TOML file, overriding existing ones
+    fn merge_file(&mut self, path: &Path) -> Result<(), PhotoBorderError> {
+        let content = fs::read_to_string(path)?;
+        self.merge_toml(&content)
+    }
+
+    /// Adds the entries of a TOML document, overriding existing ones
+    fn merge_toml(&mut self, content: &str) -> Result<(), PhotoBorderError> {
+        let table: CameraTable = toml::from_str(content)
+            .map_err(|e| PhotoBorderError::ConfigError(format!("Invalid camera table: {}", e)))?;
+
+        for (raw, name) in table.makes {
+            self.makes.insert(normalize_key(&raw), name);
+        }
+        for (raw, name) in table.models {
+            self.models.insert(normalize_key(&raw), name);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the brand name for a raw EXIF Make ("NIKON CORPORATION" -> "Nikon")
+    ///
+    /// Unknown makes are returned trimmed but otherwise unchanged.
+    pub fn brand(&self, make: &str) -> String {
+        self.makes
+            .get(&normalize_key(make))
+            .cloned()
+            .unwrap_or_else(|| make.trim().to_string())
+    }
+
+    /// Builds the display name of a camera from its raw EXIF Make and Model
+    ///
+    /// # Naming Logic
+    /// 1. The model is looked up in the table ("ILCE-7RM4" -> "α7R IV");
+    ///    an unknown model has the raw make or brand removed from its start
+    ///    ("NIKON D850" -> "D850")
+    /// 2. The brand is prepended unless the model already starts with it
+    ///    ("Canon EOS R5" stays "Canon EOS R5", not "Canon Canon EOS R5")
+    pub fn display_name(&self, make: Option<&str>, model: &str) -> String {
+        let model = model.trim();
+        let Some(make) = make.map(str::trim).filter(|m| !m.is_empty()) else {
+            return self.models.get(&normalize_key(model)).cloned().unwrap_or_else(|| model.to_string());
+        };
+        let brand = self.brand(make);
+
+        let model_name = match self.models.get(&normalize_key(model)) {
+            Some(name) => name.clone(),
+            None => {
+                // First word of the make, e.g. "NIKON" for "NIKON CORPORATION"
+                let make_word = make.split_whitespace().next().unwrap_or(make);
+                strip_prefix_ignore_case(model, &brand)
+                    .or_else(|| strip_prefix_ignore_case(model, make_word))
+                    .unwrap_or(model)
+                    .to_string()
+            }
+        };
+
+        if model_name.is_empty() {
+            brand
+        } else if starts_with_ignore_case(&model_name, &brand) {
+            model_name
+        } else {
+            format!("{} {}", brand, model_name)
+        }
+    }
+}
+
+/// Lowercases and trims a table key
+fn normalize_key(key: &str) -> String {
+    key.trim().to_lowercase()
+}
+
+fn starts_with_ignore_case(text: &str, prefix: &str) -> bool {
+    text.to_lowercase().starts_with(&prefix.to_lowercase())
+}
+
+/// Removes `prefix` and the following separators from the start of `text`
+///
+/// Only whole words are removed: "Leica" is stripped from "LEICA Q2" but
+/// not from "Leicaflex".
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    if prefix.is_empty() || !starts_with_ignore_case(text, prefix) {
+        return None;
+    }
+
+    let rest = text.get(prefix.len()..)?;
+    if !rest.is_empty() && !rest.starts_with([' ', '-', '_']) {
+        return None;
+    }
+
+    Some(rest.trim_start_matches([' ', '-', '_']))
+}
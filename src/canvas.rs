@@ -0,0 +1,190 @@
+//! Canvas of the bordered image
+//!
+//! The bordered image keeps the depth and transparency of the photo when
+//! the output format can store them: 16-bit PNG and TIFF masters give
+//! 16-bit output, and transparent PNGs keep their alpha channel. The
+//! canvas is an `ImageBuffer` of one of four pixel types (8 or 16-bit,
+//! RGB or RGBA); the frame is drawn by code generic over the pixel type.
+//!
+//! Text and logos are rendered as 8-bit RGBA layers and blended onto the
+//! canvas, whatever its depth.
+
+use crate::formats::OutputFormat;
+use image::{DynamicImage, ImageBuffer, Pixel, Primitive, Rgb, Rgba, RgbaImage};
+use std::str::FromStr;
+
+/// Handling of the alpha channel of transparent photos
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AlphaMode {
+    /// Transparent areas of the photo stay transparent, the border is
+    /// filled with the border colour
+    #[default]
+    Keep,
+    /// The border is transparent as well
+    Transparent,
+    /// The photo is composited onto the border colour, the output has
+    /// no alpha channel
+    Flatten,
+}
+
+impl FromStr for AlphaMode {
+    type Err = &'static str;
+
+    /// Parses an alpha mode ("keep", "transparent" or "flatten")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "keep" => Ok(AlphaMode::Keep),
+            "transparent" => Ok(AlphaMode::Transparent),
+            "flatten" => Ok(AlphaMode::Flatten),
+            _ => Err("Invalid alpha mode"),
+        }
+    }
+}
+
+/// Channel values of the canvas pixels (8 or 16 bits)
+pub trait Sample: Primitive {
+    /// Converts a value between 0 and 1
+    fn from_unit(value: f32) -> Self;
+    /// Value between 0 and 1
+    fn to_unit(self) -> f32;
+}
+
+impl Sample for u8 {
+    fn from_unit(value: f32) -> Self {
+        (value.clamp(0.0, 1.0) * 255.0).round() as u8
+    }
+
+    fn to_unit(self) -> f32 {
+        self as f32 / 255.0
+    }
+}
+
+impl Sample for u16 {
+    fn from_unit(value: f32) -> Self {
+        (value.clamp(0.0, 1.0) * 65535.0).round() as u16
+    }
+
+    fn to_unit(self) -> f32 {
+        self as f32 / 65535.0
+    }
+}
+
+/// Image with pixels of type `P`
+pub type Canvas<P> = ImageBuffer<P, Vec<<P as Pixel>::Subpixel>>;
+
+/// Converts the photo to the pixel type of the canvas
+///
+/// # Arguments
+/// * `img` - Decoded photo
+/// * `format` - Output format, which limits the depth and transparency
+/// * `mode` - Handling of the alpha channel
+/// * `background` - Border colour, onto which flattened photos are composited
+///
+/// # Returns
+/// An `ImageRgb8`, `ImageRgba8`, `ImageRgb16` or `ImageRgba16` image
+pub fn prepare(img: DynamicImage, format: OutputFormat, mode: AlphaMode, background: Rgb<u8>) -> DynamicImage {
+    let color_type = img.color();
+    let sixteen_bit = color_type.bytes_per_pixel() / color_type.channel_count() > 1 && format.supports_16_bit();
+    let alpha = color_type.has_alpha() && mode != AlphaMode::Flatten && format.supports_alpha();
+
+    match (sixteen_bit, alpha) {
+        (false, false) if color_type.has_alpha() => DynamicImage::ImageRgb8(flatten(&img, background)),
+        (true, false) if color_type.has_alpha() => DynamicImage::ImageRgb16(flatten(&img, background)),
+        (false, false) => DynamicImage::ImageRgb8(img.into_rgb8()),
+        (true, false) => DynamicImage::ImageRgb16(img.into_rgb16()),
+        (false, true) => DynamicImage::ImageRgba8(img.into_rgba8()),
+        (true, true) => DynamicImage::ImageRgba16(img.into_rgba16()),
+    }
+}
+
+/// Composites a transparent photo onto a solid colour
+fn flatten<P>(img: &DynamicImage, background: Rgb<u8>) -> Canvas<P>
+where
+    P: Pixel,
+    P::Subpixel: Sample,
+{
+    let photo = img.to_rgba32f();
+    ImageBuffer::from_fn(photo.width(), photo.height(), |x, y| {
+        let mut pixel = color::<P>(background, 1.0);
+        composite(&mut pixel, photo.get_pixel(x, y).0);
+        pixel
+    })
+}
+
+/// Canvas pixel of an sRGB colour
+///
+/// # Arguments
+/// * `color` - Colour
+/// * `alpha` - Opacity between 0 and 1, ignored by RGB pixels
+pub fn color<P>(color: Rgb<u8>, alpha: f32) -> P
+where
+    P: Pixel,
+    P::Subpixel: Sample,
+{
+    unit_color([color[0].to_unit(), color[1].to_unit(), color[2].to_unit(), alpha])
+}
+
+/// Canvas pixel of an sRGB colour with channels between 0 and 1 (red,
+/// green, blue and opacity), without rounding to 8 bits
+pub fn unit_color<P>(values: [f32; 4]) -> P
+where
+    P: Pixel,
+    P::Subpixel: Sample,
+{
+    let channels: Vec<P::Subpixel> = values[..P::CHANNEL_COUNT as usize]
+        .iter()
+        .map(|&value| Sample::from_unit(value))
+        .collect();
+    *P::from_slice(&channels)
+}
+
+/// Draws an 8-bit RGBA layer (text, logo) on the canvas, blending it with
+/// its alpha channel
+///
+/// # Arguments
+/// * `img` - Destination image (mutable)
+/// * `layer` - Layer to draw
+/// * `x`, `y` - Position of the top-left corner of the layer
+pub fn draw_layer<P>(img: &mut Canvas<P>, layer: &RgbaImage, x: u32, y: u32)
+where
+    P: Pixel,
+    P::Subpixel: Sample,
+{
+    for (lx, ly, pixel) in layer.enumerate_pixels() {
+        if pixel[3] == 0 {
+            continue;
+        }
+        let Some(target) = img.get_pixel_mut_checked(x + lx, y + ly) else {
+            continue;
+        };
+        let Rgba([r, g, b, a]) = *pixel;
+        composite(target, [r.to_unit(), g.to_unit(), b.to_unit(), a.to_unit()]);
+    }
+}
+
+/// Blends a colour over a pixel ("over" operator)
+///
+/// RGB pixels are opaque; on RGBA pixels the colour is weighted by the
+/// opacity of both, so that drawing on a transparent border does not mix
+/// in the hidden colour of the border.
+pub fn composite<P>(target: &mut P, source: [f32; 4])
+where
+    P: Pixel,
+    P::Subpixel: Sample,
+{
+    let channels = target.channels_mut();
+    let alpha = source[3];
+    let target_alpha = channels.get(3).map_or(1.0, |value| value.to_unit());
+    let result_alpha = alpha + target_alpha * (1.0 - alpha);
+    if result_alpha <= 0.0 {
+        return;
+    }
+
+    for channel in 0..3 {
+        let value = (source[channel] * alpha + channels[channel].to_unit() * target_alpha * (1.0 - alpha)) / result_alpha;
+        channels[channel] = Sample::from_unit(value);
+    }
+    if let Some(value) = channels.get_mut(3) {
+        *value = Sample::from_unit(result_alpha);
+    }
+}
@@ -0,0 +1,137 @@
+//! Carousel tiles
+//!
+//! Panoramas are posted as a swipeable carousel of equal tiles (4:5 or
+//! 1:1). The border is widened so that the bordered image is exactly a
+//! whole number of tiles, then the image is cut into them: the photo and
+//! the border continue from one tile to the next. The logo and caption
+//! are kept inside the first or the last tile.
+
+use crate::PhotoBorderError;
+use image::DynamicImage;
+use std::str::FromStr;
+
+/// Largest number of tiles of a carousel post
+pub const MAX_TILES: u32 = 20;
+
+/// Tile holding the logo and the caption
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CaptionTile {
+    First,
+    #[default]
+    Last,
+}
+
+impl FromStr for CaptionTile {
+    type Err = &'static str;
+
+    /// Parses a caption tile ("first" or "last")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "first" => Ok(CaptionTile::First),
+            "last" => Ok(CaptionTile::Last),
+            _ => Err("Invalid caption tile (expected first or last)"),
+        }
+    }
+}
+
+/// Settings of the carousel output
+#[derive(Debug, Clone)]
+pub struct CarouselOptions {
+    /// Aspect ratio of the tiles (width, height)
+    pub ratio: (u32, u32),
+    /// Number of tiles, `None` to follow the shape of the photo
+    pub tiles: Option<u32>,
+    /// Tile holding the logo and the caption
+    pub caption_tile: CaptionTile,
+}
+
+impl CarouselOptions {
+    /// Builds the carousel settings from the command-line options
+    ///
+    /// # Arguments
+    /// * `ratio` - Tile shape: "4:5" (or "portrait") or "1:1" (or "square")
+    /// * `tiles` - Number of tiles, from 1 to 20
+    /// * `caption_tile` - Tile of the caption ("first" or "last")
+    pub fn parse(ratio: &str, tiles: Option<&str>, caption_tile: &str) -> Result<Self, PhotoBorderError> {
+        let config_error = |e: &str| PhotoBorderError::ConfigError(e.to_string());
+
+        let ratio = match ratio.trim().to_lowercase().as_str() {
+            "4:5" | "portrait" => (4, 5),
+            "1:1" | "square" => (1, 1),
+            _ => return Err(config_error("Invalid carousel ratio (expected 4:5 or 1:1)")),
+        };
+        let tiles = tiles
+            .map(|tiles| {
+                tiles.trim()
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|tiles| (1..=MAX_TILES).contains(tiles))
+                    .ok_or_else(|| config_error("Number of tiles must be between 1 and 20"))
+            })
+            .transpose()?;
+
+        Ok(CarouselOptions {
+            ratio,
+            tiles,
+            caption_tile: CaptionTile::from_str(caption_tile).map_err(config_error)?,
+        })
+    }
+
+    /// Widens the borders so that the bordered image is a whole number of tiles
+    ///
+    /// # Arguments
+    /// * `photo` - Size of the photo
+    /// * `borders` - Borders of the border type (top, right, bottom, left)
+    ///
+    /// # Returns
+    /// The borders with the extra width or height shared between opposite sides
+    pub fn pad(&self, photo: (u32, u32), borders: (u32, u32, u32, u32)) -> (u32, u32, u32, u32) {
+        let (top, right, bottom, left) = borders;
+        let width = photo.0 + left + right;
+        let height = photo.1 + top + bottom;
+        let (ratio_width, ratio_height) = self.ratio;
+
+        // As many tiles as the shape of the bordered image calls for
+        let tiles = self.tiles.unwrap_or_else(|| {
+            let count = (width as f64 * ratio_height as f64) / (height as f64 * ratio_width as f64);
+            (count.round() as u32).clamp(1, MAX_TILES)
+        });
+
+        // Smallest tile size with the exact ratio containing the image
+        let unit = height.div_ceil(ratio_height).max(width.div_ceil(tiles * ratio_width));
+        let extra_width = tiles * unit * ratio_width - width;
+        let extra_height = unit * ratio_height - height;
+
+        (
+            top + extra_height / 2,
+            right + extra_width - extra_width / 2,
+            bottom + extra_height - extra_height / 2,
+            left + extra_width / 2,
+        )
+    }
+
+    /// Width of the tiles of an image padded by [`CarouselOptions::pad`]
+    pub fn tile_width(&self, height: u32) -> u32 {
+        height / self.ratio.1 * self.ratio.0
+    }
+
+    /// Horizontal span of the tile holding the logo and the caption
+    ///
+    /// # Returns
+    /// Position and width of the tile in the bordered image
+    pub fn caption_span(&self, width: u32, height: u32) -> (u32, u32) {
+        let tile_width = self.tile_width(height).max(1);
+        match self.caption_tile {
+            CaptionTile::First => (0, tile_width),
+            CaptionTile::Last => (width.saturating_sub(tile_width), tile_width),
+        }
+    }
+
+    /// Cuts a padded image into its tiles, from left to right
+    pub fn split(&self, img: &DynamicImage) -> Vec<DynamicImage> {
+        let tile_width = self.tile_width(img.height()).max(1);
+        (0..img.width() / tile_width)
+            .map(|index| img.crop_imm(index * tile_width, 0, tile_width, img.height()))
+            .collect()
+    }
+}
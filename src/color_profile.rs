@@ -0,0 +1,91 @@
+//! ICC colour profiles
+//!
+//! Decoding with `image` keeps the pixel values but drops the embedded
+//! ICC profile, so an Adobe RGB or Display P3 photo saved without it is
+//! displayed as sRGB and looks desaturated. The profile of the source is
+//! read separately (JPEG APP2, PNG iCCP, WebP ICCP, TIFF tag 34675, HEIF
+//! colr box) and either embedded in the output or used to convert the
+//! pixels to sRGB.
+
+use crate::formats;
+use crate::raw;
+use crate::PhotoBorderError;
+use image::DynamicImage;
+use img_parts::{Bytes, DynImage, ImageICC};
+use qcms::{DataType, Intent, Profile, Transform};
+use std::fs;
+use std::path::Path;
+
+/// Offset and value of the data colour space in an ICC profile header
+const COLOR_SPACE_OFFSET: usize = 16;
+const RGB_COLOR_SPACE: &[u8] = b"RGB ";
+
+/// Reads the ICC profile embedded in an image file
+///
+/// # Returns
+/// The profile, or `None` if the file has none, cannot be parsed, or the
+/// profile is not an RGB one (CMYK and grayscale photos are decoded to RGB
+/// pixels, which their profile does not describe)
+pub fn read_profile(path: &Path) -> Option<Vec<u8>> {
+    // The pixels of raw files come from their JPEG preview
+    let data = if raw::is_raw(path) { raw::extract_preview(path).ok()? } else { fs::read(path).ok()? };
+    let data = Bytes::from(data);
+    let profile = match DynImage::from_bytes(data.clone()) {
+        Ok(Some(image)) => image.icc_profile()?.to_vec(),
+        _ => tiff_profile(&data).or_else(|| formats::heif_icc_profile(path))?,
+    };
+
+    if is_rgb_profile(&profile) { Some(profile) } else { None }
+}
+
+/// Reads the ICC profile tag of a TIFF file
+fn tiff_profile(data: &[u8]) -> Option<Vec<u8>> {
+    let tiff = exif::Reader::new().read_raw(data.to_vec()).ok()?;
+    match &tiff.get_field(formats::TIFF_ICC_PROFILE, exif::In::PRIMARY)?.value {
+        exif::Value::Undefined(profile, _) | exif::Value::Byte(profile) => Some(profile.clone()),
+        _ => None,
+    }
+}
+
+/// Whether an ICC profile describes RGB data
+fn is_rgb_profile(profile: &[u8]) -> bool {
+    profile.get(COLOR_SPACE_OFFSET..COLOR_SPACE_OFFSET + 4) == Some(RGB_COLOR_SPACE)
+}
+
+/// Converts pixels from the colour space of `profile` to sRGB
+///
+/// # Arguments
+/// * `img` - Pixels to convert in place
+/// * `profile` - ICC profile of the pixels
+///
+/// # Returns
+/// * `Err(PhotoBorderError::ConfigError)` if the profile is not supported
+///
+/// Uses the perceptual intent, which compresses out-of-gamut colours
+/// smoothly instead of clipping them. qcms only transforms 8-bit pixels:
+/// 16-bit images are reduced to 8 bits.
+pub fn convert_to_srgb(img: &mut DynamicImage, profile: &[u8]) -> Result<(), PhotoBorderError> {
+    let unsupported = || PhotoBorderError::ConfigError("Unsupported ICC profile".to_string());
+
+    let input = Profile::new_from_slice(profile, false).ok_or_else(unsupported)?;
+    let output = Profile::new_sRGB();
+    let data_type = if img.color().has_alpha() { DataType::RGBA8 } else { DataType::RGB8 };
+    let transform = Transform::new(&input, &output, data_type, Intent::Perceptual)
+        .ok_or_else(unsupported)?;
+
+    match img {
+        DynamicImage::ImageRgb8(rgb) => transform.apply(rgb),
+        DynamicImage::ImageRgba8(rgba) => transform.apply(rgba),
+        _ if img.color().has_alpha() => {
+            let mut rgba = img.to_rgba8();
+            transform.apply(&mut rgba);
+            *img = DynamicImage::ImageRgba8(rgba);
+        }
+        _ => {
+            let mut rgb = img.to_rgb8();
+            transform.apply(&mut rgb);
+            *img = DynamicImage::ImageRgb8(rgb);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,261 @@
+//! Diptychs and triptychs
+//!
+//! Two or three photos are scaled to a common height (side by side) or
+//! width (stacked), without enlarging any of them, and assembled with
+//! uniform gutters into one image, which is then framed like a single
+//! photo. The caption is either one line combining the EXIF data of every
+//! photo or one caption below each photo.
+
+use crate::canvas::{self, Canvas, Sample};
+use crate::{PhotoBorderError, SizeUnit};
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, ImageBuffer, Pixel, Rgb};
+use std::str::FromStr;
+
+/// Separator of the caption parts ("camera | lens | settings")
+const PART_SEPARATOR: &str = " | ";
+
+/// Separator of the values of each photo in a combined caption
+const VALUE_SEPARATOR: &str = " / ";
+
+/// Arrangement of the photos
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Layout {
+    /// Side by side, scaled to the same height
+    Row,
+    /// Stacked, scaled to the same width
+    Column,
+}
+
+impl FromStr for Layout {
+    type Err = &'static str;
+
+    /// Parses a layout ("row"/"horizontal" or "column"/"vertical")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "row" | "horizontal" => Ok(Layout::Row),
+            "column" | "vertical" => Ok(Layout::Column),
+            _ => Err("Invalid layout (expected row or column)"),
+        }
+    }
+}
+
+/// Captions of a composition
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CaptionMode {
+    /// One line in the bottom border, values shared by every photo
+    /// written once
+    #[default]
+    Combined,
+    /// One caption below each photo
+    Each,
+}
+
+impl FromStr for CaptionMode {
+    type Err = &'static str;
+
+    /// Parses a caption mode ("combined" or "each")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "combined" => Ok(CaptionMode::Combined),
+            "each" => Ok(CaptionMode::Each),
+            _ => Err("Invalid caption mode (expected combined or each)"),
+        }
+    }
+}
+
+/// Settings of diptychs and triptychs
+#[derive(Debug, Clone)]
+pub struct ComposeOptions {
+    /// Arrangement of the photos
+    pub layout: Layout,
+    /// Space between the photos and its unit, `None` for the thickness
+    /// of the widest border
+    pub gutter: Option<(f32, SizeUnit)>,
+    /// Captions
+    pub caption_mode: CaptionMode,
+}
+
+impl ComposeOptions {
+    /// Builds the composition settings from the command-line options
+    ///
+    /// # Arguments
+    /// * `layout` - Arrangement ("row" or "column")
+    /// * `gutter` - Space between the photos, `None` for the default
+    /// * `unit` - Unit of the gutter ("px" or "%")
+    /// * `caption_mode` - Captions ("combined" or "each")
+    pub fn parse(layout: &str, gutter: Option<&str>, unit: &str, caption_mode: &str) -> Result<Self, PhotoBorderError> {
+        let config_error = |e: &str| PhotoBorderError::ConfigError(e.to_string());
+
+        let gutter = gutter
+            .map(|gutter| {
+                let value = gutter.trim()
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|value| *value >= 0.0 && value.is_finite())
+                    .ok_or_else(|| config_error("Gutter must be a positive number"))?;
+                Ok::<_, PhotoBorderError>((value, SizeUnit::from_str(unit).map_err(config_error)?))
+            })
+            .transpose()?;
+
+        Ok(ComposeOptions {
+            layout: Layout::from_str(layout).map_err(config_error)?,
+            gutter,
+            caption_mode: CaptionMode::from_str(caption_mode).map_err(config_error)?,
+        })
+    }
+
+    /// Space between the photos in pixels
+    ///
+    /// # Arguments
+    /// * `min_dimension` - Smallest dimension of the photos put together
+    ///   without gutters, for gutters in percent
+    /// * `borders` - Borders of the photos put together, giving the default
+    pub fn gutter_pixels(&self, min_dimension: u32, borders: (u32, u32, u32, u32)) -> u32 {
+        match self.gutter {
+            Some((value, unit)) => unit.to_pixels(value, min_dimension),
+            None => {
+                let (top, right, bottom, left) = borders;
+                top.max(right).max(bottom).max(left)
+            }
+        }
+    }
+}
+
+/// Scales the photos to a common height (row) or width (column), the
+/// smallest one so that no photo is enlarged
+pub fn normalize(photos: Vec<DynamicImage>, layout: Layout) -> Vec<DynamicImage> {
+    match layout {
+        Layout::Row => {
+            let height = photos.iter().map(|photo| photo.height()).min().unwrap_or(0);
+            photos.into_iter()
+                .map(|photo| {
+                    let width = (photo.width() as u64 * height as u64 / photo.height() as u64).max(1) as u32;
+                    if photo.height() == height { photo } else { photo.resize_exact(width, height, FilterType::Lanczos3) }
+                })
+                .collect()
+        }
+        Layout::Column => {
+            let width = photos.iter().map(|photo| photo.width()).min().unwrap_or(0);
+            photos.into_iter()
+                .map(|photo| {
+                    let height = (photo.height() as u64 * width as u64 / photo.width() as u64).max(1) as u32;
+                    if photo.width() == width { photo } else { photo.resize_exact(width, height, FilterType::Lanczos3) }
+                })
+                .collect()
+        }
+    }
+}
+
+/// Size of normalized photos put together with a gutter
+pub fn composed_size(photos: &[DynamicImage], layout: Layout, gutter: u32) -> (u32, u32) {
+    let gutters = gutter * (photos.len() as u32).saturating_sub(1);
+    match layout {
+        Layout::Row => (photos.iter().map(|photo| photo.width()).sum::<u32>() + gutters, photos[0].height()),
+        Layout::Column => (photos[0].width(), photos.iter().map(|photo| photo.height()).sum::<u32>() + gutters),
+    }
+}
+
+/// Assembles normalized photos into one image
+///
+/// # Arguments
+/// * `photos` - Photos, scaled by [`normalize`]
+/// * `layout` - Arrangement
+/// * `gutter` - Space between the photos in pixels
+/// * `background` - Colour of the gutters
+/// * `opacity` - Opacity of the gutters, if the image has an alpha channel
+///
+/// # Returns
+/// The composed image, 16-bit if any photo is and with an alpha channel
+/// if any photo has one, and the position and size of each photo in it
+pub fn compose(
+    photos: &[DynamicImage],
+    layout: Layout,
+    gutter: u32,
+    background: Rgb<u8>,
+    opacity: f32,
+) -> (DynamicImage, Vec<(u32, u32, u32, u32)>) {
+    let mut rects = Vec::with_capacity(photos.len());
+    let mut offset = 0;
+    for photo in photos {
+        let (width, height) = (photo.width(), photo.height());
+        match layout {
+            Layout::Row => {
+                rects.push((offset, 0, width, height));
+                offset += width + gutter;
+            }
+            Layout::Column => {
+                rects.push((0, offset, width, height));
+                offset += height + gutter;
+            }
+        }
+    }
+
+    let size = composed_size(photos, layout, gutter);
+    let sixteen_bit = photos.iter().any(|photo| {
+        let color_type = photo.color();
+        color_type.bytes_per_pixel() / color_type.channel_count() > 1
+    });
+    let alpha = photos.iter().any(|photo| photo.color().has_alpha());
+
+    let composed = match (sixteen_bit, alpha) {
+        (false, false) => DynamicImage::ImageRgb8(assemble(photos, &rects, size, background, opacity, DynamicImage::to_rgb8)),
+        (false, true) => DynamicImage::ImageRgba8(assemble(photos, &rects, size, background, opacity, DynamicImage::to_rgba8)),
+        (true, false) => DynamicImage::ImageRgb16(assemble(photos, &rects, size, background, opacity, DynamicImage::to_rgb16)),
+        (true, true) => DynamicImage::ImageRgba16(assemble(photos, &rects, size, background, opacity, DynamicImage::to_rgba16)),
+    };
+    (composed, rects)
+}
+
+/// Copies the photos on a canvas of pixel type `P` filled with the gutter colour
+fn assemble<P>(
+    photos: &[DynamicImage],
+    rects: &[(u32, u32, u32, u32)],
+    size: (u32, u32),
+    background: Rgb<u8>,
+    opacity: f32,
+    convert: fn(&DynamicImage) -> Canvas<P>,
+) -> Canvas<P>
+where
+    P: Pixel,
+    P::Subpixel: Sample,
+{
+    let mut composed = ImageBuffer::from_pixel(size.0, size.1, canvas::color(background, opacity));
+    for (photo, (x, y, _, _)) in photos.iter().zip(rects) {
+        imageops::replace(&mut composed, &convert(photo), *x as i64, *y as i64);
+    }
+    composed
+}
+
+/// Combines the captions of the photos into one line
+///
+/// Captions made of the same number of parts ("camera | lens | settings")
+/// are merged part by part: a part shared by every photo is written
+/// once, the others are listed in order ("35 mm • f/2.8 / 85 mm • f/1.8").
+/// Other captions are listed whole.
+pub fn combine_captions(captions: &[String]) -> String {
+    let captions: Vec<&String> = captions.iter().filter(|caption| !caption.is_empty()).collect();
+    let Some(first) = captions.first() else {
+        return String::new();
+    };
+
+    let parts: Vec<Vec<&str>> = captions.iter().map(|caption| caption.split(PART_SEPARATOR).collect()).collect();
+    if parts.iter().any(|caption_parts| caption_parts.len() != parts[0].len()) {
+        return captions.iter().map(|caption| caption.as_str()).collect::<Vec<_>>().join(VALUE_SEPARATOR);
+    }
+    if captions.len() == 1 {
+        return first.to_string();
+    }
+
+    (0..parts[0].len())
+        .map(|index| {
+            let values: Vec<&str> = parts.iter().map(|caption_parts| caption_parts[index]).collect();
+            if values.iter().all(|value| *value == values[0]) {
+                values[0].to_string()
+            } else {
+                values.join(VALUE_SEPARATOR)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(PART_SEPARATOR)
+}
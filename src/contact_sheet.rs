@@ -0,0 +1,506 @@
+//! Contact sheet subcommand
+//!
+//! `schnapsshot contact-sheet <files...>` lays out photos in a grid on one
+//! or more pages, each thumbnail with its file name and a short line of
+//! EXIF data below it, for client selection and archive indexing. The
+//! pages are saved as image files (`contact_sheet_01.jpg`, ...) or as the
+//! pages of a single PDF file.
+//!
+//! Thumbnails are converted to sRGB and scaled to fit their cell; the
+//! labels are drawn like the captions of bordered photos, with the same
+//! font, text colour, caption templates and EXIF options (name tables,
+//! `--lens`, `--set`, sidecar files).
+
+use crate::canvas::{self, AlphaMode};
+use crate::formats::{self, OutputFormat, OutputOptions};
+use crate::pdf::{self, PdfDocument};
+use crate::print::{self, PaperSize};
+use crate::{color_profile, fit_text, parse_color, BorderType, ExifData, ExifOptions, PhotoBorder, PhotoBorderError};
+use crate::{DEFAULT_BORDER_COLOR, DEFAULT_TEXT_COLOR};
+use clap::{Arg, ArgMatches, Command};
+use image::{imageops, DynamicImage, ImageBuffer, RgbImage};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Line height of the labels, relative to the font size
+const LINE_HEIGHT: f32 = 1.25;
+
+/// Layout of the contact sheets
+#[derive(Debug, Clone)]
+pub struct ContactSheetOptions {
+    /// Page size
+    pub paper: PaperSize,
+    /// Resolution of the pages in dots per inch
+    pub dpi: u32,
+    /// Number of thumbnails per row
+    pub columns: u32,
+    /// Number of rows per page
+    pub rows: u32,
+    /// Space between the cells, in millimetres
+    pub spacing: f32,
+    /// Space around the grid, in millimetres
+    pub margin: f32,
+    /// Font size of the labels, in points
+    pub label_size: f32,
+}
+
+/// Pixel sizes of the grid
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Grid {
+    page: (u32, u32),
+    margin: u32,
+    spacing: u32,
+    cell: (u32, u32),
+    /// Space left for the thumbnail above the labels
+    thumbnail: (u32, u32),
+    /// Font size of the labels
+    label_scale: f32,
+}
+
+impl ContactSheetOptions {
+    /// Builds the layout from the command-line options
+    ///
+    /// # Arguments
+    /// * `paper` - Page size ("A4", "13x18cm", "8x10in"); give the
+    ///   width first for landscape pages ("297x210mm")
+    /// * `dpi` - Resolution of the pages
+    /// * `columns`, `rows` - Size of the grid
+    /// * `spacing` - Space between the cells in millimetres
+    /// * `margin` - Space around the grid in millimetres
+    /// * `label_size` - Font size of the labels in points
+    ///
+    /// # Returns
+    /// * `Err(PhotoBorderError::ConfigError)` if a value is invalid or the
+    ///   grid leaves no room for the thumbnails
+    pub fn parse(
+        paper: &str,
+        dpi: &str,
+        columns: &str,
+        rows: &str,
+        spacing: &str,
+        margin: &str,
+        label_size: &str,
+    ) -> Result<Self, PhotoBorderError> {
+        let config_error = |e: &str| PhotoBorderError::ConfigError(e.to_string());
+        let positive_count = |value: &str, message: &'static str| {
+            value.parse::<u32>().ok().filter(|count| *count > 0).ok_or_else(|| config_error(message))
+        };
+        let length = |value: &str, message: &'static str| {
+            value.parse::<f32>().ok().filter(|length| *length >= 0.0 && length.is_finite()).ok_or_else(|| config_error(message))
+        };
+
+        let options = ContactSheetOptions {
+            paper: PaperSize::from_str(paper).map_err(config_error)?,
+            dpi: dpi.parse::<u32>()
+                .ok()
+                .filter(|dpi| (1..=u16::MAX as u32).contains(dpi))
+                .ok_or_else(|| config_error("Resolution must be a number of dots per inch"))?,
+            columns: positive_count(columns, "Number of columns must be a positive number")?,
+            rows: positive_count(rows, "Number of rows must be a positive number")?,
+            spacing: length(spacing, "Spacing must be a number of millimetres")?,
+            margin: length(margin, "Margin must be a number of millimetres")?,
+            label_size: label_size.parse::<f32>()
+                .ok()
+                .filter(|size| *size > 0.0 && size.is_finite())
+                .ok_or_else(|| config_error("Label size must be a positive number of points"))?,
+        };
+
+        let grid = options.grid();
+        if grid.thumbnail.0 == 0 || grid.thumbnail.1 == 0 {
+            return Err(config_error("Too many rows or columns for the page size, margin and label size"));
+        }
+        Ok(options)
+    }
+
+    /// Converts a length in millimetres to pixels at the page resolution
+    fn to_pixels(&self, mm: f32) -> u32 {
+        print::mm_to_pixels(mm, self.dpi)
+    }
+
+    /// Computes the pixel sizes of the grid
+    fn grid(&self) -> Grid {
+        let page = (self.to_pixels(self.paper.width), self.to_pixels(self.paper.height));
+        let margin = self.to_pixels(self.margin);
+        let spacing = self.to_pixels(self.spacing);
+
+        let cell_size = |available: u32, count: u32| {
+            available.saturating_sub(2 * margin).saturating_sub((count - 1) * spacing) / count
+        };
+        let cell = (cell_size(page.0, self.columns), cell_size(page.1, self.rows));
+
+        // File name and EXIF line below the thumbnail, half a line apart from it
+        let label_scale = print::points_to_pixels(self.label_size, self.dpi);
+        let labels = (2.5 * LINE_HEIGHT * label_scale).ceil() as u32;
+
+        Grid {
+            page,
+            margin,
+            spacing,
+            cell,
+            thumbnail: (cell.0, cell.1.saturating_sub(labels)),
+            label_scale,
+        }
+    }
+}
+
+/// Builds the `contact-sheet` subcommand definition
+pub fn command() -> Command {
+    Command::new("contact-sheet")
+        .about("Lay out photos in a grid with their file name and EXIF settings, on one or more pages")
+        .arg(
+            Arg::new("files")
+                .help("Input image filename(s)")
+                .required(true)
+                .num_args(1..),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .help("Output file: JPEG, PNG, TIFF, WebP or AVIF image (numbered _01, _02... for several pages) or PDF document")
+                .value_name("FILE")
+                .default_value("contact_sheet.jpg"),
+        )
+        .arg(
+            Arg::new("columns")
+                .long("columns")
+                .help("Number of thumbnails per row")
+                .value_name("COUNT")
+                .default_value("4"),
+        )
+        .arg(
+            Arg::new("rows")
+                .long("rows")
+                .help("Number of rows per page")
+                .value_name("COUNT")
+                .default_value("5"),
+        )
+        .arg(
+            Arg::new("spacing")
+                .long("spacing")
+                .help("Space between the thumbnails in millimetres")
+                .value_name("MM")
+                .default_value("5"),
+        )
+        .arg(
+            Arg::new("margin")
+                .long("margin")
+                .help("Space around the grid in millimetres")
+                .value_name("MM")
+                .default_value("10"),
+        )
+        .arg(
+            Arg::new("paper")
+                .long("paper")
+                .help("Page size: A3 to A6, letter, legal or e.g. 13x18cm (width first, 297x210mm for landscape A4)")
+                .value_name("PAPER")
+                .default_value("A4"),
+        )
+        .arg(
+            Arg::new("dpi")
+                .long("dpi")
+                .help("Resolution of the pages in dots per inch")
+                .value_name("DPI")
+                .default_value("300"),
+        )
+        .arg(
+            Arg::new("label_size")
+                .long("label-size")
+                .help("Font size of the file names and EXIF lines in points")
+                .value_name("PT")
+                .default_value("7"),
+        )
+        .arg(
+            Arg::new("caption")
+                .short('c')
+                .long("caption")
+                .help("Template of the EXIF line (see --caption of the main command), instead of the exposure settings")
+                .value_name("TEMPLATE"),
+        )
+        .args(ExifOptions::args())
+        .arg(
+            Arg::new("background")
+                .long("background")
+                .help("Page colour as hex (e.g. #ffffff)")
+                .value_name("COLOR"),
+        )
+        .arg(
+            Arg::new("text_color")
+                .long("text-color")
+                .help("Label colour as hex (e.g. #404040)")
+                .value_name("COLOR"),
+        )
+        .arg(
+            Arg::new("font")
+                .short('f')
+                .long("font")
+                .help("Font Typeface to use (TTF file path)")
+                .value_name("FONT_PATH"),
+        )
+}
+
+/// Runs the `contact-sheet` subcommand
+pub fn run(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let files: Vec<PathBuf> = matches.get_many::<String>("files")
+        .unwrap()
+        .map(PathBuf::from)
+        .collect();
+    let value = |name: &str| matches.get_one::<String>(name).unwrap().as_str();
+
+    let options = ContactSheetOptions::parse(
+        value("paper"),
+        value("dpi"),
+        value("columns"),
+        value("rows"),
+        value("spacing"),
+        value("margin"),
+        value("label_size"),
+    )?;
+
+    // Same EXIF options as the main command, for the same names as the captions
+    let exif_options = ExifOptions::from_matches(matches)?;
+    let background = matches.get_one::<String>("background")
+        .map(|c| parse_color(c))
+        .transpose()?
+        .unwrap_or(DEFAULT_BORDER_COLOR);
+    let text_color = matches.get_one::<String>("text_color")
+        .map(|c| parse_color(c))
+        .transpose()?
+        .unwrap_or(DEFAULT_TEXT_COLOR);
+
+    // The labels are drawn by the caption code of the main processor
+    let photo_border = PhotoBorder::new(
+        BorderType::Small,
+        true,
+        matches.get_one::<String>("font").map(|s| s.as_str()),
+    )?
+    .with_border_color(background)
+    .with_text_color(text_color)
+    .with_exif_options(exif_options)
+    .with_caption_template(matches.get_one::<String>("caption").map(|s| s.as_str()))?;
+
+    write_contact_sheets(&photo_border, &files, Path::new(value("output")), &options)?;
+    Ok(())
+}
+
+/// Lays out photos on contact sheets and saves them
+///
+/// # Arguments
+/// * `photo_border` - Processor giving the font, colours and caption template
+/// * `input_paths` - Photos, in grid order
+/// * `output_path` - Image file (numbered when there are several pages) or PDF file
+/// * `options` - Layout of the sheets
+///
+/// # Returns
+/// * `Ok(())` even if some images fail (they are left out)
+/// * `Err(PhotoBorderError)` if the output format is not supported, no
+///   image could be loaded or a file cannot be written
+pub fn write_contact_sheets(
+    photo_border: &PhotoBorder,
+    input_paths: &[PathBuf],
+    output_path: &Path,
+    options: &ContactSheetOptions,
+) -> Result<(), PhotoBorderError> {
+    // Check the output format before the long part
+    let extension = output_path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+    let format = if extension.eq_ignore_ascii_case("pdf") {
+        None
+    } else {
+        Some(OutputFormat::from_extension(extension).ok_or_else(|| {
+            PhotoBorderError::ConfigError("Contact sheets are saved as JPEG, PNG, TIFF, WebP, AVIF or PDF files".to_string())
+        })?)
+    };
+
+    println!("Laying out {} image(s)...", input_paths.len());
+
+    let grid = options.grid();
+    let per_page = (options.columns * options.rows) as usize;
+    let mut sheets = Vec::new();
+    let mut sheet = blank_sheet(&grid, photo_border);
+    let mut count = 0;
+    let mut error_count = 0;
+
+    for (index, input_path) in input_paths.iter().enumerate() {
+        println!("[{}/{}] Adding: {}", index + 1, input_paths.len(), input_path.display());
+
+        let thumbnail = match load_thumbnail(photo_border, input_path, grid.thumbnail) {
+            Ok(thumbnail) => thumbnail,
+            Err(e) => {
+                eprintln!("Error processing {}: {}", input_path.display(), e);
+                error_count += 1;
+                continue;
+            }
+        };
+
+        if count > 0 && count % per_page == 0 {
+            sheets.push(std::mem::replace(&mut sheet, blank_sheet(&grid, photo_border)));
+        }
+        let cell = (count % per_page) as u32;
+        draw_cell(photo_border, &mut sheet, &grid, (cell % options.columns, cell / options.columns), input_path, thumbnail)?;
+        count += 1;
+    }
+
+    if count == 0 {
+        return Err(PhotoBorderError::ConfigError("No image could be added to the contact sheet".to_string()));
+    }
+    sheets.push(sheet);
+
+    let page_count = sheets.len();
+    match format {
+        Some(format) => save_images(sheets, output_path, format, options.dpi)?,
+        None => save_pdf(&sheets, output_path, options.paper)?,
+    }
+
+    // Display final summary
+    println!("\nContact sheets complete:");
+    println!("  Page(s): {}", page_count);
+    println!("  Successfully added: {} image(s)", count);
+    if error_count > 0 {
+        println!("  Errors encountered: {} image(s)", error_count);
+    }
+
+    Ok(())
+}
+
+/// Empty page in the background colour
+fn blank_sheet(grid: &Grid, photo_border: &PhotoBorder) -> RgbImage {
+    ImageBuffer::from_pixel(grid.page.0, grid.page.1, photo_border.border_color)
+}
+
+/// Thumbnail with its EXIF data
+struct Thumbnail {
+    image: RgbImage,
+    exif_data: Option<ExifData>,
+}
+
+/// Loads a photo as an sRGB thumbnail fitting a box
+///
+/// # Arguments
+/// * `photo_border` - Processor giving the EXIF options and the background
+///   onto which transparent photos are composited
+/// * `input_path` - Photo
+/// * `size` - Largest thumbnail size
+fn load_thumbnail(photo_border: &PhotoBorder, input_path: &Path, size: (u32, u32)) -> Result<Thumbnail, PhotoBorderError> {
+    let mut img = formats::open_image(input_path)?;
+
+    // Convert to sRGB first, as the profile cannot be kept for a single cell
+    if let Some(profile) = color_profile::read_profile(input_path) {
+        if let Err(e) = color_profile::convert_to_srgb(&mut img, &profile) {
+            eprintln!("Warning: Could not convert to sRGB: {}", e);
+        }
+    }
+
+    let img = img.thumbnail(size.0, size.1);
+    let image = canvas::prepare(img, OutputFormat::Jpeg, AlphaMode::Flatten, photo_border.border_color).into_rgb8();
+
+    let exif_data = match ExifData::load(input_path, &photo_border.exif_options) {
+        Ok(exif_data) => Some(exif_data),
+        Err(e) => {
+            eprintln!("Warning: Could not read EXIF data: {}", e);
+            None
+        }
+    };
+
+    Ok(Thumbnail { image, exif_data })
+}
+
+/// Draws a thumbnail and its labels in a cell of the grid
+///
+/// The thumbnail is centred horizontally and sits on the labels, so that
+/// they line up across the row; the file name and the EXIF line are
+/// aligned with its left edge.
+fn draw_cell(
+    photo_border: &PhotoBorder,
+    sheet: &mut RgbImage,
+    grid: &Grid,
+    cell: (u32, u32),
+    input_path: &Path,
+    thumbnail: Thumbnail,
+) -> Result<(), PhotoBorderError> {
+    let (column, row) = cell;
+    let x = grid.margin + column * (grid.cell.0 + grid.spacing);
+    let y = grid.margin + row * (grid.cell.1 + grid.spacing);
+
+    let (width, height) = thumbnail.image.dimensions();
+    let thumbnail_x = x + (grid.thumbnail.0 - width) / 2;
+    let thumbnail_y = y + grid.thumbnail.1 - height;
+    imageops::replace(sheet, &thumbnail.image, thumbnail_x as i64, thumbnail_y as i64);
+
+    let Some(font) = photo_border.font()? else {
+        return Ok(());
+    };
+
+    // Labels half a line below the thumbnail
+    let line_height = (LINE_HEIGHT * grid.label_scale).round() as u32;
+    let label_y = y + grid.thumbnail.1 + line_height / 2;
+    let name = input_path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let name = fit_text(&font, &name, grid.label_scale, width);
+    photo_border.draw_text_line(sheet, &name, grid.label_scale, photo_border.text_color, thumbnail_x, label_y)?;
+
+    if let Some(exif_data) = &thumbnail.exif_data {
+        let line = match &photo_border.caption_template {
+            Some(template) => exif_data.render_template(template),
+            None => exposure_line(exif_data),
+        };
+        let line = fit_text(&font, &line, grid.label_scale, width);
+        photo_border.draw_text_line(sheet, &line, grid.label_scale, photo_border.text_color, thumbnail_x, label_y + line_height)?;
+    }
+
+    Ok(())
+}
+
+/// Short EXIF line: focal length, aperture, shutter speed and ISO
+fn exposure_line(exif_data: &ExifData) -> String {
+    [&exif_data.focal_length, &exif_data.aperture, &exif_data.shutter_speed, &exif_data.iso]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(" • ")
+}
+
+/// Saves the sheets as image files, numbered when there are several
+fn save_images(sheets: Vec<RgbImage>, output_path: &Path, format: OutputFormat, dpi: u32) -> Result<(), PhotoBorderError> {
+    let page_count = sheets.len();
+    for (index, sheet) in sheets.into_iter().enumerate() {
+        let path = if page_count == 1 {
+            output_path.to_path_buf()
+        } else {
+            let stem = output_path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("contact_sheet");
+            let extension = output_path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+            output_path.with_file_name(format!("{}_{:02}.{}", stem, index + 1, extension))
+        };
+
+        let encoded = formats::encode(
+            &DynamicImage::ImageRgb8(sheet),
+            format,
+            &OutputOptions::default(),
+            None,
+            None,
+            Some(dpi),
+        )?;
+        fs::write(&path, encoded)?;
+        println!("Saved contact sheet to: {}", path.display());
+    }
+    Ok(())
+}
+
+/// Saves the sheets as the pages of a PDF file
+fn save_pdf(sheets: &[RgbImage], output_path: &Path, paper: PaperSize) -> Result<(), PhotoBorderError> {
+    let title = output_path.file_stem().and_then(|stem| stem.to_str());
+    let mut document = PdfDocument::new(title);
+    let (width, height) = (paper.width * pdf::POINTS_PER_MM, paper.height * pdf::POINTS_PER_MM);
+
+    for sheet in sheets {
+        let image = document.add_photo(sheet, None)?;
+        let mut page = pdf::Page::new(width, height);
+        page.draw_image(image, 0.0, 0.0, width, height);
+        document.add_page(page);
+    }
+
+    fs::write(output_path, document.finish())?;
+    println!("Saved contact sheet to: {}", output_path.display());
+    Ok(())
+}
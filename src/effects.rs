@@ -0,0 +1,184 @@
+//! Drop shadow and keyline
+//!
+//! For a "floating print" look the photo can cast a soft drop shadow onto
+//! the border, and be outlined by a thin keyline of the border just
+//! around its edge. Both are drawn on the filled canvas before the photo
+//! is copied onto it, so they never cover the photo itself.
+
+use crate::canvas::{self, Canvas, Sample};
+use crate::{parse_color, PhotoBorderError, SizeUnit};
+use image::{Pixel, Rgb};
+use std::str::FromStr;
+
+/// Soft shadow cast by the photo
+#[derive(Debug, Clone, Copy)]
+pub struct ShadowOptions {
+    /// Horizontal and vertical offset, in percent of the smallest side of
+    /// the photo (positive to the right and down)
+    pub offset: (f32, f32),
+    /// Blur radius (standard deviation), in percent of the smallest side
+    pub blur: f32,
+    /// Opacity between 0 and 1
+    pub opacity: f32,
+    /// Colour
+    pub color: Rgb<u8>,
+}
+
+impl ShadowOptions {
+    /// Builds the shadow settings from the command-line options
+    ///
+    /// # Arguments
+    /// * `offset` - "x,y" offset in percent, or a single value for both
+    /// * `blur` - Blur radius in percent
+    /// * `opacity` - Opacity in percent
+    /// * `color` - Colour as hex
+    pub fn parse(offset: &str, blur: &str, opacity: &str, color: &str) -> Result<Self, PhotoBorderError> {
+        let config_error = |e: &str| PhotoBorderError::ConfigError(e.to_string());
+        let number = |value: &str| value.trim().trim_end_matches('%').parse::<f32>().ok().filter(|value| value.is_finite());
+
+        let values = offset.split(',')
+            .map(number)
+            .collect::<Option<Vec<f32>>>()
+            .ok_or_else(|| config_error("Invalid shadow offset (expected x,y in percent)"))?;
+        let offset = match values[..] {
+            [value] => (value, value),
+            [x, y] => (x, y),
+            _ => return Err(config_error("Invalid shadow offset (expected x,y in percent)")),
+        };
+
+        Ok(ShadowOptions {
+            offset,
+            blur: number(blur)
+                .filter(|blur| *blur >= 0.0)
+                .ok_or_else(|| config_error("Shadow blur must be a positive number"))?,
+            opacity: number(opacity)
+                .filter(|opacity| (0.0..=100.0).contains(opacity))
+                .ok_or_else(|| config_error("Shadow opacity must be between 0 and 100%"))? / 100.0,
+            color: parse_color(color)?,
+        })
+    }
+}
+
+/// Keyline around the photo edge
+#[derive(Debug, Clone, Copy)]
+pub struct StrokeOptions {
+    /// Thickness and its unit
+    pub width: (f32, SizeUnit),
+    /// Colour
+    pub color: Rgb<u8>,
+}
+
+impl StrokeOptions {
+    /// Builds the keyline settings from the command-line options
+    ///
+    /// # Arguments
+    /// * `width` - Thickness
+    /// * `unit` - Unit of the thickness ("px" or "%")
+    /// * `color` - Colour as hex
+    pub fn parse(width: &str, unit: &str, color: &str) -> Result<Self, PhotoBorderError> {
+        let config_error = |e: &str| PhotoBorderError::ConfigError(e.to_string());
+        let width = width.trim()
+            .parse::<f32>()
+            .ok()
+            .filter(|width| *width > 0.0 && width.is_finite())
+            .ok_or_else(|| config_error("Stroke width must be a positive number"))?;
+
+        Ok(StrokeOptions {
+            width: (width, SizeUnit::from_str(unit).map_err(config_error)?),
+            color: parse_color(color)?,
+        })
+    }
+}
+
+/// Draws the shadow of the photo on the canvas
+///
+/// The shadow of a rectangle blurred by a Gaussian is the product of two
+/// one-dimensional profiles, computed exactly for each row and column.
+///
+/// # Arguments
+/// * `img` - Filled canvas (mutable)
+/// * `rect` - Position and size of the photo (x, y, width, height)
+/// * `options` - Shadow settings
+pub fn draw_shadow<P>(img: &mut Canvas<P>, rect: (u32, u32, u32, u32), options: &ShadowOptions)
+where
+    P: Pixel,
+    P::Subpixel: Sample,
+{
+    let (x, y, width, height) = rect;
+    let min_dimension = width.min(height) as f32;
+    let sigma = options.blur / 100.0 * min_dimension;
+    let (offset_x, offset_y) = (options.offset.0 / 100.0 * min_dimension, options.offset.1 / 100.0 * min_dimension);
+
+    let columns = profile(img.width(), x as f32 + offset_x, width as f32, sigma);
+    let rows = profile(img.height(), y as f32 + offset_y, height as f32, sigma);
+    let Rgb([r, g, b]) = options.color;
+    let color = [r.to_unit(), g.to_unit(), b.to_unit()];
+
+    for (py, row) in rows.iter().enumerate().filter(|(_, row)| **row > 0.0) {
+        for (px, column) in columns.iter().enumerate().filter(|(_, column)| **column > 0.0) {
+            let alpha = row * column * options.opacity;
+            canvas::composite(img.get_pixel_mut(px as u32, py as u32), [color[0], color[1], color[2], alpha]);
+        }
+    }
+}
+
+/// Draws the keyline on the border, just around the photo
+///
+/// # Arguments
+/// * `img` - Filled canvas (mutable)
+/// * `rect` - Position and size of the photo (x, y, width, height)
+/// * `options` - Keyline settings
+pub fn draw_stroke<P>(img: &mut Canvas<P>, rect: (u32, u32, u32, u32), options: &StrokeOptions)
+where
+    P: Pixel,
+    P::Subpixel: Sample,
+{
+    let (x, y, width, height) = rect;
+    let (value, unit) = options.width;
+    // No wider than the canvas, which also keeps the sums below in range
+    let stroke = unit.to_pixels(value, width.min(height)).clamp(1, img.width().max(img.height()));
+    let pixel = canvas::color::<P>(options.color, 1.0);
+
+    let left = x.saturating_sub(stroke);
+    let top = y.saturating_sub(stroke);
+    let right = x.saturating_add(width).saturating_add(stroke).min(img.width());
+    let bottom = y.saturating_add(height).saturating_add(stroke).min(img.height());
+    for py in top..bottom {
+        for px in left..right {
+            let inside = px >= x && px < x + width && py >= y && py < y + height;
+            if !inside {
+                img.put_pixel(px, py, pixel);
+            }
+        }
+    }
+}
+
+/// Coverage of each pixel of a line by a segment blurred by a Gaussian
+///
+/// # Arguments
+/// * `length` - Number of pixels of the line
+/// * `start`, `size` - Segment
+/// * `sigma` - Standard deviation of the blur, 0 for a sharp segment
+fn profile(length: u32, start: f32, size: f32, sigma: f32) -> Vec<f32> {
+    let end = start + size;
+    (0..length)
+        .map(|position| {
+            let center = position as f32 + 0.5;
+            if sigma <= 0.0 {
+                return if center >= start && center < end { 1.0 } else { 0.0 };
+            }
+            let scale = sigma * std::f32::consts::SQRT_2;
+            let coverage = 0.5 * (erf((center - start) / scale) - erf((center - end) / scale));
+            // Ignore the far tails of the blur
+            if coverage < 1.0 / 1024.0 { 0.0 } else { coverage }
+        })
+        .collect()
+}
+
+/// Error function (Abramowitz and Stegun 7.1.26, error below 1.5e-7)
+fn erf(x: f32) -> f32 {
+    let t = 1.0 / (1.0 + 0.327_591_1 * x.abs());
+    let polynomial = t * (0.254_829_6 + t * (-0.284_496_74 + t * (1.421_413_7 + t * (-1.453_152_1 + t * 1.061_405_4))));
+    let value = 1.0 - polynomial * (-x * x).exp();
+    if x < 0.0 { -value } else { value }
+}
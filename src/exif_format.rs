@@ -0,0 +1,139 @@
+//! Human-friendly formatting of EXIF values
+//!
+//! Raw EXIF values are rationals (`4/1000`, `28/10`, `500/10`) that read
+//! poorly when displayed as-is. This module turns them into the notation
+//! photographers expect: reduced shutter fractions (`1/250 s`), seconds
+//! for long exposures (`2.5″`), and focal lengths / apertures without
+//! trailing zeros (`50 mm`, `f/2.8`).
+
+use exif::Rational;
+use std::str::FromStr;
+
+/// Exposures at least this long are shown in seconds rather than as a fraction
+const LONG_EXPOSURE_SECONDS: f64 = 1.0;
+
+/// Fractions below this value are always rounded to `1/x`
+const SHORT_EXPOSURE_SECONDS: f64 = 0.25;
+
+/// Notation used for units in formatted values
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ExifStyle {
+    /// Spaced units and photographic symbols: "50 mm", "1/250 s", "2.5″", "+0.7 EV"
+    #[default]
+    Standard,
+    /// No space before units: "50mm", "1/250s", "2.5s", "+0.7EV"
+    Compact,
+}
+
+impl FromStr for ExifStyle {
+    type Err = &'static str;
+
+    /// Parses a style name ("standard" or "compact")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "standard" => Ok(ExifStyle::Standard),
+            "compact" => Ok(ExifStyle::Compact),
+            _ => Err("Invalid EXIF style"),
+        }
+    }
+}
+
+impl ExifStyle {
+    /// Appends a unit to a value according to the style
+    fn with_unit(self, value: &str, unit: &str) -> String {
+        match self {
+            ExifStyle::Standard => format!("{} {}", value, unit),
+            ExifStyle::Compact => format!("{}{}", value, unit),
+        }
+    }
+
+    /// Formats an exposure time
+    ///
+    /// # Formatting Logic
+    /// - 1 second and longer: decimal seconds ("2.5″", "30″")
+    /// - Shorter: reduced fraction ("1/250 s"), with `4/1000` shown as `1/250`
+    /// - In-between values that are not a clean fraction ("0.3″") stay decimal
+    pub fn shutter_speed(self, time: Rational) -> String {
+        if time.denom == 0 {
+            return String::new();
+        }
+
+        let seconds = time.to_f64();
+        let seconds_unit = |value: String| match self {
+            ExifStyle::Standard => format!("{}″", value),
+            ExifStyle::Compact => format!("{}s", value),
+        };
+
+        if seconds >= LONG_EXPOSURE_SECONDS || seconds <= 0.0 {
+            return seconds_unit(trim_number(seconds, 1));
+        }
+
+        // Reduce the fraction, then express it as 1/x when possible
+        let divisor = gcd(time.num, time.denom);
+        let (num, denom) = (time.num / divisor, time.denom / divisor);
+        let reciprocal = 1.0 / seconds;
+
+        let fraction = if num == 1 {
+            Some(denom)
+        } else if seconds < SHORT_EXPOSURE_SECONDS
+            || (reciprocal - reciprocal.round()).abs() < 0.01 * reciprocal
+        {
+            Some(reciprocal.round() as u32)
+        } else {
+            None
+        };
+
+        match fraction {
+            Some(denom) => self.with_unit(&format!("1/{}", denom), "s"),
+            None => seconds_unit(trim_number(seconds, 1)),
+        }
+    }
+
+    /// Formats an f-number ("f/2.8", "f/8", "f/0.95")
+    pub fn aperture(self, f_number: f64) -> String {
+        let decimals = if f_number < 1.0 { 2 } else { 1 };
+        format!("f/{}", trim_number(f_number, decimals))
+    }
+
+    /// Formats a focal length in millimeters ("50 mm", "24.5 mm")
+    pub fn focal_length(self, millimeters: f64) -> String {
+        self.with_unit(&trim_number(millimeters, 1), "mm")
+    }
+
+    /// Formats an ISO sensitivity ("ISO 400")
+    pub fn iso(self, iso: u32) -> String {
+        format!("ISO {}", iso)
+    }
+
+    /// Formats an exposure compensation ("+0.7 EV", "-1 EV", "0 EV")
+    pub fn exposure_compensation(self, ev: f64) -> String {
+        let value = trim_number(ev, 1);
+        let value = if ev > 0.0 && value != "0" { format!("+{}", value) } else { value };
+        self.with_unit(&value, "EV")
+    }
+
+    /// Formats an altitude in meters ("35.5 m")
+    pub fn altitude(self, meters: f64) -> String {
+        self.with_unit(&trim_number(meters, 1), "m")
+    }
+}
+
+/// Formats a number with at most `decimals` decimals, dropping trailing zeros
+///
+/// `50.0` becomes `"50"`, `2.80` becomes `"2.8"`.
+pub fn trim_number(value: f64, decimals: usize) -> String {
+    let formatted = format!("{:.*}", decimals, value);
+    let trimmed = if formatted.contains('.') {
+        formatted.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &formatted
+    };
+
+    // Avoid printing "-0" for tiny negative values
+    if trimmed == "-0" { "0".to_string() } else { trimmed.to_string() }
+}
+
+/// Greatest common divisor, used to reduce exposure fractions
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 { a.max(1) } else { gcd(b, a % b) }
+}
@@ -0,0 +1,279 @@
+//! Border fills
+//!
+//! The border is a solid colour by default. It can instead be filled with
+//! a scaled-up, heavily blurred copy of the photo, optionally darkened, as
+//! used for 9:16 stories; a linear or radial gradient between two
+//! colours; a paper or canvas texture tiled from a file; or a background
+//! image scaled to cover the canvas. On every fill but the solid one, the
+//! EXIF text and the logo take a colour that stays readable on it.
+
+use crate::canvas::{self, Canvas, Sample};
+use crate::{formats, PhotoBorderError, DEFAULT_TEXT_COLOR};
+use image::imageops::{self, FilterType};
+use image::{ImageBuffer, Pixel, Rgb, RgbaImage};
+use std::path::Path;
+
+/// Longest side of the copy of the photo that is blurred, scaled up to
+/// the canvas afterwards: a heavy blur leaves no detail to lose
+const BLUR_WORK_SIZE: u32 = 512;
+
+/// Lowest contrast ratio at which the text keeps its configured colour
+/// (WCAG AA for normal text)
+const MIN_CONTRAST: f32 = 4.5;
+
+/// Content of the border area
+#[derive(Debug, Clone, Default)]
+pub enum BorderFill {
+    /// Border colour
+    #[default]
+    Solid,
+    /// Blurred copy of the photo scaled to cover the canvas
+    Blur {
+        /// Blur radius in percent of the smallest side of the canvas
+        radius: f32,
+        /// Darkening between 0 (none) and 1 (black)
+        darken: f32,
+    },
+    /// Linear gradient across the canvas
+    LinearGradient {
+        /// Colour at the start
+        from: Rgb<u8>,
+        /// Colour at the end
+        to: Rgb<u8>,
+        /// Direction in degrees, clockwise from left to right (90 for
+        /// top to bottom)
+        angle: f32,
+    },
+    /// Radial gradient from the centre to the corners
+    RadialGradient {
+        /// Colour at the centre
+        from: Rgb<u8>,
+        /// Colour at the corners
+        to: Rgb<u8>,
+    },
+    /// Texture tiled at its own size from the top-left corner
+    Texture(RgbaImage),
+    /// Background image scaled to cover the canvas and centred
+    Image(RgbaImage),
+}
+
+impl BorderFill {
+    /// Builds the border fill from the command-line options
+    ///
+    /// # Arguments
+    /// * `fill` - Fill type ("solid", "blur", "linear", "radial", "texture"
+    ///   or "image")
+    /// * `radius` - Blur radius in percent of the smallest side
+    /// * `darken` - Darkening of the blurred photo in percent
+    /// * `colors` - Start and end colours of the gradients
+    /// * `angle` - Direction of the linear gradient in degrees
+    /// * `file` - Texture or background image
+    ///
+    /// # Returns
+    /// * `Err(PhotoBorderError::ConfigError)` for an invalid value or a
+    ///   missing file
+    /// * `Err(PhotoBorderError)` if the texture or image cannot be loaded
+    pub fn parse(
+        fill: &str,
+        radius: &str,
+        darken: &str,
+        colors: (Rgb<u8>, Rgb<u8>),
+        angle: &str,
+        file: Option<&str>,
+    ) -> Result<Self, PhotoBorderError> {
+        let config_error = |e: &str| PhotoBorderError::ConfigError(e.to_string());
+        let percent = |value: &str, max: f32, error: &str| {
+            value.trim()
+                .trim_end_matches('%')
+                .parse::<f32>()
+                .ok()
+                .filter(|value| (0.0..=max).contains(value))
+                .ok_or_else(|| config_error(error))
+        };
+        let load = || {
+            let file = file.ok_or_else(|| config_error("Texture and image fills need a file (--fill-file)"))?;
+            Ok::<_, PhotoBorderError>(formats::open_image(Path::new(file))?.to_rgba8())
+        };
+        let (from, to) = colors;
+
+        match fill.to_lowercase().as_str() {
+            "solid" => Ok(BorderFill::Solid),
+            "blur" => Ok(BorderFill::Blur {
+                radius: percent(radius, 50.0, "Blur radius must be between 0 and 50%")?,
+                darken: percent(darken, 100.0, "Darkening must be between 0 and 100%")? / 100.0,
+            }),
+            "linear" => Ok(BorderFill::LinearGradient {
+                from,
+                to,
+                angle: angle.trim()
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|angle| angle.is_finite())
+                    .ok_or_else(|| config_error("Gradient angle must be a number of degrees"))?,
+            }),
+            "radial" => Ok(BorderFill::RadialGradient { from, to }),
+            "texture" => Ok(BorderFill::Texture(load()?)),
+            "image" => Ok(BorderFill::Image(load()?)),
+            _ => Err(config_error("Invalid border fill (expected solid, blur, linear, radial, texture or image)")),
+        }
+    }
+}
+
+/// Creates the canvas of the bordered image, filled before the photo is
+/// copied onto it
+///
+/// # Arguments
+/// * `photo` - Photo, with the pixel type of the output
+/// * `size` - Size of the bordered image
+/// * `fill` - Border fill
+/// * `color` - Border colour, for solid fills and under transparent
+///   textures and images
+/// * `opacity` - Opacity of the border, if the canvas has an alpha channel
+pub fn canvas<P>(photo: &Canvas<P>, size: (u32, u32), fill: &BorderFill, color: Rgb<u8>, opacity: f32) -> Canvas<P>
+where
+    P: Pixel + 'static,
+    P::Subpixel: Sample + 'static,
+{
+    let (width, height) = size;
+    match fill {
+        BorderFill::Solid => Canvas::from_pixel(width, height, canvas::color(color, opacity)),
+        BorderFill::Blur { radius, darken } => {
+            // Work on a small copy covering the canvas, cropped to its shape
+            let work_scale = (BLUR_WORK_SIZE as f32 / width.max(height) as f32).min(1.0);
+            let work_width = ((width as f32 * work_scale).round() as u32).max(1);
+            let work_height = ((height as f32 * work_scale).round() as u32).max(1);
+            let cover = cover(photo, work_width, work_height, FilterType::Triangle);
+
+            let sigma = radius / 100.0 * work_width.min(work_height) as f32;
+            let mut blurred = if sigma > 0.0 { imageops::blur(&cover, sigma) } else { cover };
+            for pixel in blurred.pixels_mut() {
+                let channels = pixel.channels_mut();
+                for value in channels.iter_mut().take(3) {
+                    *value = Sample::from_unit(value.to_unit() * (1.0 - darken));
+                }
+                if let Some(value) = channels.get_mut(3) {
+                    *value = Sample::from_unit(value.to_unit() * opacity);
+                }
+            }
+            imageops::resize(&blurred, width, height, FilterType::Triangle)
+        }
+        BorderFill::LinearGradient { from, to, angle } => {
+            // Position along the direction, from 0 at one edge to 1 at the
+            // opposite one
+            let (sin, cos) = angle.to_radians().sin_cos();
+            let extent = (width as f32 * cos).abs() + (height as f32 * sin).abs();
+            gradient(size, *from, *to, opacity, |x, y| {
+                let projection = (x - width as f32 / 2.0) * cos + (y - height as f32 / 2.0) * sin;
+                projection / extent.max(1.0) + 0.5
+            })
+        }
+        BorderFill::RadialGradient { from, to } => {
+            let (center_x, center_y) = (width as f32 / 2.0, height as f32 / 2.0);
+            let radius = center_x.hypot(center_y).max(1.0);
+            gradient(size, *from, *to, opacity, |x, y| (x - center_x).hypot(y - center_y) / radius)
+        }
+        BorderFill::Texture(texture) => {
+            let layer = RgbaImage::from_fn(width, height, |x, y| *texture.get_pixel(x % texture.width(), y % texture.height()));
+            layered(size, &layer, color, opacity)
+        }
+        BorderFill::Image(image) => layered(size, &cover(image, width, height, FilterType::Lanczos3), color, opacity),
+    }
+}
+
+/// Scales an image to cover a size, cropping the overflow on both sides
+fn cover<P>(img: &Canvas<P>, width: u32, height: u32, filter: FilterType) -> Canvas<P>
+where
+    P: Pixel + 'static,
+    P::Subpixel: 'static,
+{
+    let scale = (width as f32 / img.width() as f32).max(height as f32 / img.height() as f32);
+    let cover_width = ((img.width() as f32 * scale).ceil() as u32).max(width);
+    let cover_height = ((img.height() as f32 * scale).ceil() as u32).max(height);
+    let scaled = imageops::resize(img, cover_width, cover_height, filter);
+    imageops::crop_imm(&scaled, (cover_width - width) / 2, (cover_height - height) / 2, width, height).to_image()
+}
+
+/// Fills a canvas with a gradient between two colours
+///
+/// # Arguments
+/// * `size` - Size of the canvas
+/// * `from`, `to` - Colours at positions 0 and 1
+/// * `opacity` - Opacity of the canvas, if it has an alpha channel
+/// * `position` - Position of a pixel centre in the gradient, clamped
+///   between 0 and 1
+fn gradient<P>(size: (u32, u32), from: Rgb<u8>, to: Rgb<u8>, opacity: f32, position: impl Fn(f32, f32) -> f32) -> Canvas<P>
+where
+    P: Pixel,
+    P::Subpixel: Sample,
+{
+    ImageBuffer::from_fn(size.0, size.1, |x, y| {
+        let t = position(x as f32 + 0.5, y as f32 + 0.5).clamp(0.0, 1.0);
+        let [r, g, b] = [0, 1, 2].map(|channel| {
+            let (start, end) = (from[channel].to_unit(), to[channel].to_unit());
+            start + (end - start) * t
+        });
+        canvas::unit_color([r, g, b, opacity])
+    })
+}
+
+/// Fills a canvas with an 8-bit layer drawn over the border colour
+fn layered<P>(size: (u32, u32), layer: &RgbaImage, color: Rgb<u8>, opacity: f32) -> Canvas<P>
+where
+    P: Pixel,
+    P::Subpixel: Sample,
+{
+    let mut canvas = ImageBuffer::from_pixel(size.0, size.1, canvas::color(color, opacity));
+    if opacity > 0.0 {
+        canvas::draw_layer(&mut canvas, layer, 0, 0);
+    }
+    canvas
+}
+
+/// Colour of the text and logo drawn over a filled border
+///
+/// # Arguments
+/// * `img` - Filled canvas
+/// * `area` - Area of the text (x, y, width, height)
+/// * `preferred` - Configured text colour
+///
+/// # Returns
+/// The configured colour if it contrasts enough with the average colour
+/// of the area, otherwise white or dark gray, whichever contrasts most
+pub fn text_color<P>(img: &Canvas<P>, area: (u32, u32, u32, u32), preferred: Rgb<u8>) -> Rgb<u8>
+where
+    P: Pixel,
+    P::Subpixel: Sample,
+{
+    let (x, y, width, height) = area;
+    let view = imageops::crop_imm(img, x, y, width, height);
+    let (mut sum, mut count) = ([0.0f32; 3], 0u64);
+    for (_, _, pixel) in image::GenericImageView::pixels(&*view) {
+        for (total, value) in sum.iter_mut().zip(pixel.channels()) {
+            *total += value.to_unit();
+        }
+        count += 1;
+    }
+    if count == 0 {
+        return preferred;
+    }
+    let background = sum.map(|total| total / count as f32);
+    let background_luminance = luminance(background);
+
+    let contrast = |color: Rgb<u8>| {
+        let color_luminance = luminance(color.0.map(Sample::to_unit));
+        (color_luminance.max(background_luminance) + 0.05) / (color_luminance.min(background_luminance) + 0.05)
+    };
+    if contrast(preferred) >= MIN_CONTRAST {
+        return preferred;
+    }
+    let white = Rgb([255, 255, 255]);
+    if contrast(white) >= contrast(DEFAULT_TEXT_COLOR) { white } else { DEFAULT_TEXT_COLOR }
+}
+
+/// Relative luminance of an sRGB colour (channels between 0 and 1)
+fn luminance(color: [f32; 3]) -> f32 {
+    let [r, g, b] = color.map(|value| {
+        if value <= 0.04045 { value / 12.92 } else { ((value + 0.055) / 1.055).powf(2.4) }
+    });
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
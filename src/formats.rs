@@ -0,0 +1,748 @@
+//! Input and output file formats
+//!
+//! Photos are decoded with `image` (JPEG, PNG, 8/16-bit TIFF, WebP).
+//! HEIC/HEIF and AVIF files, as received from phones, are decoded with
+//! libheif when the application is built with the `heif` feature. Camera
+//! raw files are represented by their embedded JPEG preview (see [`crate::raw`]).
+//!
+//! The bordered image is written in the format of the original when it can
+//! be encoded (JPEG, PNG, TIFF, WebP, AVIF); HEIC and raw originals are
+//! saved as JPEG. `--format` selects another format, with `--quality` and
+//! `--lossless` for the web formats. The EXIF data and colour profile are
+//! embedded in every format; they are added to the AVIF container after
+//! encoding, as ravif does not write them.
+
+use crate::output_metadata;
+use crate::raw;
+use crate::PhotoBorderError;
+use exif::{Context, Exif, Field, In, Reader, Rational, Tag, Value};
+use image::codecs::jpeg::{JpegEncoder, PixelDensity};
+use image::DynamicImage;
+use img_parts::png::PngChunk;
+use img_parts::riff::RiffContent;
+use img_parts::webp::CHUNK_EXIF;
+use img_parts::{Bytes, DynImage, ImageEXIF, ImageICC};
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Extensions of the supported input files, for the file dialogs
+pub const INPUT_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "tif", "tiff", "webp", "heic", "heif", "avif",
+    "cr3", "nef", "arw", "raf", "dng",
+];
+
+/// Extensions of the files decoded with libheif
+const HEIF_EXTENSIONS: &[&str] = &["heic", "heif", "avif"];
+
+/// Default quality of lossy output formats
+const JPEG_QUALITY: u8 = 95;
+const WEBP_QUALITY: u8 = 90;
+const AVIF_QUALITY: u8 = 80;
+
+/// rav1e speed preset (1 = slowest, 10 = fastest); 6 keeps batch exports
+/// reasonably fast for a small loss in compression
+const AVIF_SPEED: u8 = 6;
+
+/// TIFF tag of the embedded ICC profile (not defined by the `exif` crate)
+pub const TIFF_ICC_PROFILE: Tag = Tag(Context::Tiff, 34675);
+
+/// TIFF tag describing the alpha channel
+const TIFF_EXTRA_SAMPLES: Tag = Tag(Context::Tiff, 338);
+
+/// Format of output files
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Jpeg,
+    Png,
+    Tiff,
+    Webp,
+    Avif,
+}
+
+impl FromStr for OutputFormat {
+    type Err = &'static str;
+
+    /// Parses a format name ("jpeg", "png", "tiff", "webp" or "avif")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_extension(s).ok_or("Invalid output format (expected jpeg, png, tiff, webp or avif)")
+    }
+}
+
+impl OutputFormat {
+    /// Format written for an extension, if the format can be written
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_lowercase().as_str() {
+            "jpg" | "jpeg" => Some(OutputFormat::Jpeg),
+            "png" => Some(OutputFormat::Png),
+            "tif" | "tiff" => Some(OutputFormat::Tiff),
+            "webp" => Some(OutputFormat::Webp),
+            "avif" => Some(OutputFormat::Avif),
+            _ => None,
+        }
+    }
+
+    /// Format of the output file of an original
+    ///
+    /// The format of the original when it can be written, JPEG otherwise
+    pub fn for_input(path: &Path) -> Self {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::from_extension)
+            .unwrap_or(OutputFormat::Jpeg)
+    }
+
+    /// Default file extension of the format
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Jpeg => "jpg",
+            OutputFormat::Png => "png",
+            OutputFormat::Tiff => "tif",
+            OutputFormat::Webp => "webp",
+            OutputFormat::Avif => "avif",
+        }
+    }
+
+    /// Whether the format has a lossless mode distinct from its lossy one
+    fn has_lossless_mode(self) -> bool {
+        matches!(self, OutputFormat::Webp | OutputFormat::Avif)
+    }
+
+    /// Whether 16-bit images can be written without reducing them to 8 bits
+    pub fn supports_16_bit(self) -> bool {
+        matches!(self, OutputFormat::Png | OutputFormat::Tiff)
+    }
+
+    /// Whether the format has an alpha channel
+    pub fn supports_alpha(self) -> bool {
+        self != OutputFormat::Jpeg
+    }
+}
+
+/// Output format settings
+#[derive(Debug, Clone, Default)]
+pub struct OutputOptions {
+    /// Output format, `None` for the format of each original
+    pub format: Option<OutputFormat>,
+    /// Quality of lossy formats (1-100), `None` for the format default
+    pub quality: Option<u8>,
+    /// Lossless WebP or AVIF encoding
+    pub lossless: bool,
+}
+
+impl OutputOptions {
+    /// Builds the output settings from the command-line options
+    ///
+    /// # Arguments
+    /// * `format` - "auto" (format of the original) or a format name
+    /// * `quality` - Quality from 1 to 100
+    /// * `lossless` - Lossless encoding, for WebP and AVIF only
+    pub fn parse(format: &str, quality: Option<&str>, lossless: bool) -> Result<Self, PhotoBorderError> {
+        let config_error = |e: &str| PhotoBorderError::ConfigError(e.to_string());
+
+        let format = match format.to_lowercase().as_str() {
+            "auto" => None,
+            name => Some(OutputFormat::from_str(name).map_err(config_error)?),
+        };
+        let quality = quality
+            .map(|quality| {
+                quality.parse::<u8>()
+                    .ok()
+                    .filter(|quality| (1..=100).contains(quality))
+                    .ok_or_else(|| config_error("Quality must be a number from 1 to 100"))
+            })
+            .transpose()?;
+        if lossless && !format.is_some_and(OutputFormat::has_lossless_mode) {
+            return Err(config_error("--lossless requires --format webp or avif"));
+        }
+
+        Ok(OutputOptions { format, quality, lossless })
+    }
+
+    /// Format of the output file of an original
+    pub fn format_for(&self, input_path: &Path) -> OutputFormat {
+        self.format.unwrap_or_else(|| OutputFormat::for_input(input_path))
+    }
+
+    /// Quality used for a format
+    fn quality(&self, format: OutputFormat) -> u8 {
+        self.quality.unwrap_or(match format {
+            OutputFormat::Webp => WEBP_QUALITY,
+            OutputFormat::Avif => AVIF_QUALITY,
+            _ => JPEG_QUALITY,
+        })
+    }
+}
+
+/// Whether a file is decoded with libheif rather than `image`
+fn is_heif(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| HEIF_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Decodes an image file
+///
+/// # Returns
+/// * `Ok(DynamicImage)` with the pixels of the primary image
+/// * `Err(PhotoBorderError)` if the file cannot be decoded, is a raw file
+///   without a usable preview, or is a HEIC/HEIF/AVIF file and the `heif`
+///   feature is disabled
+pub fn open_image(path: &Path) -> Result<DynamicImage, PhotoBorderError> {
+    if raw::is_raw(path) {
+        let preview = raw::extract_preview(path)?;
+        return Ok(image::load_from_memory_with_format(&preview, image::ImageFormat::Jpeg)?);
+    }
+    if is_heif(path) {
+        return decode_heif(path);
+    }
+    Ok(image::open(path)?)
+}
+
+/// Decodes the primary image of a HEIF container to 8-bit RGB(A)
+#[cfg(feature = "heif")]
+fn decode_heif(path: &Path) -> Result<DynamicImage, PhotoBorderError> {
+    use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+    let invalid = |e: String| PhotoBorderError::ConfigError(format!("Could not decode {}: {}", path.display(), e));
+    let file_name = path.to_str().ok_or_else(|| invalid("invalid path encoding".to_string()))?;
+
+    let lib_heif = LibHeif::new();
+    let context = HeifContext::read_from_file(file_name).map_err(|e| invalid(e.to_string()))?;
+    let handle = context.primary_image_handle().map_err(|e| invalid(e.to_string()))?;
+
+    let has_alpha = handle.has_alpha_channel();
+    let chroma = if has_alpha { RgbChroma::Rgba } else { RgbChroma::Rgb };
+    let image = lib_heif.decode(&handle, ColorSpace::Rgb(chroma), None)
+        .map_err(|e| invalid(e.to_string()))?;
+    let plane = image.planes().interleaved
+        .ok_or_else(|| invalid("no interleaved pixel plane".to_string()))?;
+
+    // Rows are padded to the stride
+    let channels = if has_alpha { 4 } else { 3 };
+    let row_length = plane.width as usize * channels;
+    let pixels: Vec<u8> = plane.data.chunks(plane.stride)
+        .take(plane.height as usize)
+        .flat_map(|row| &row[..row_length])
+        .copied()
+        .collect();
+
+    let image = if has_alpha {
+        image::RgbaImage::from_raw(plane.width, plane.height, pixels).map(DynamicImage::ImageRgba8)
+    } else {
+        image::RgbImage::from_raw(plane.width, plane.height, pixels).map(DynamicImage::ImageRgb8)
+    };
+    image.ok_or_else(|| invalid("unexpected pixel buffer size".to_string()))
+}
+
+#[cfg(not(feature = "heif"))]
+fn decode_heif(path: &Path) -> Result<DynamicImage, PhotoBorderError> {
+    Err(PhotoBorderError::ConfigError(format!(
+        "Cannot decode {}: HEIC/HEIF and AVIF support requires building with --features heif",
+        path.display()
+    )))
+}
+
+/// Reads the ICC profile of a HEIF container
+#[cfg(feature = "heif")]
+pub fn heif_icc_profile(path: &Path) -> Option<Vec<u8>> {
+    if !is_heif(path) {
+        return None;
+    }
+    let context = libheif_rs::HeifContext::read_from_file(path.to_str()?).ok()?;
+    let handle = context.primary_image_handle().ok()?;
+    handle.color_profile_raw().map(|profile| profile.data)
+}
+
+#[cfg(not(feature = "heif"))]
+pub fn heif_icc_profile(_path: &Path) -> Option<Vec<u8>> {
+    None
+}
+
+/// Reads the EXIF data of an image file, whatever its container
+///
+/// WebP files written by some tools (img-parts among them) keep the JPEG
+/// "Exif\0\0" prefix in their EXIF chunk, which the `exif` crate rejects;
+/// such blocks are read through img-parts, which strips the prefix.
+pub fn read_exif(path: &Path) -> Result<Exif, PhotoBorderError> {
+    if raw::is_raw(path) {
+        return Ok(Reader::new().read_raw(raw::read_exif_block(path)?)?);
+    }
+
+    let data = fs::read(path)?;
+    match Reader::new().read_from_container(&mut Cursor::new(&data)) {
+        Ok(exif) => Ok(exif),
+        Err(e) => {
+            let block = DynImage::from_bytes(Bytes::from(data))
+                .ok()
+                .flatten()
+                .and_then(|image| image.exif())
+                .ok_or(e)?;
+            Ok(Reader::new().read_raw(block.to_vec())?)
+        }
+    }
+}
+
+/// Encodes the bordered image with its metadata
+///
+/// # Arguments
+/// * `img` - Bordered image, converted to 8 bits and without alpha
+///   channel when the format cannot store them
+/// * `format` - Output format
+/// * `options` - Quality and lossless settings
+/// * `exif` - EXIF block to embed (TIFF structure, without the "Exif\0\0" prefix)
+/// * `icc_profile` - Colour profile to embed, `None` for sRGB
+/// * `dpi` - Print resolution to write in the JPEG (JFIF), PNG (pHYs) or
+///   TIFF header; other formats only carry it in the EXIF block
+///
+/// # Returns
+/// The content of the output file
+pub fn encode(
+    img: &DynamicImage,
+    format: OutputFormat,
+    options: &OutputOptions,
+    exif: Option<Vec<u8>>,
+    icc_profile: Option<Vec<u8>>,
+    dpi: Option<u32>,
+) -> Result<Vec<u8>, PhotoBorderError> {
+    let quality = options.quality(format);
+    let encoded = match format {
+        OutputFormat::Tiff => return encode_tiff(img, exif, icc_profile, dpi),
+        OutputFormat::Avif => return add_avif_metadata(encode_avif(img, quality, options.lossless)?, exif, icc_profile),
+        OutputFormat::Webp => {
+            let pixels = if img.color().has_alpha() { img.to_rgba8().into_raw() } else { img.to_rgb8().into_raw() };
+            let encoder = if img.color().has_alpha() {
+                webp::Encoder::from_rgba(&pixels, img.width(), img.height())
+            } else {
+                webp::Encoder::from_rgb(&pixels, img.width(), img.height())
+            };
+            let encoded = if options.lossless { encoder.encode_lossless() } else { encoder.encode(quality as f32) };
+            encoded.to_vec()
+        }
+        OutputFormat::Png => {
+            let mut buffer = Cursor::new(Vec::new());
+            img.write_to(&mut buffer, image::ImageOutputFormat::Png)?;
+            buffer.into_inner()
+        }
+        OutputFormat::Jpeg => {
+            let mut buffer = Cursor::new(Vec::new());
+            let mut encoder = JpegEncoder::new_with_quality(&mut buffer, quality);
+            if let Some(dpi) = dpi {
+                encoder.set_pixel_density(PixelDensity::dpi(dpi as u16));
+            }
+            encoder.encode_image(&img.to_rgb8())?;
+            buffer.into_inner()
+        }
+    };
+
+    // Metadata is added to the encoded file: APP1/APP2 segments for JPEG,
+    // eXIf/iCCP chunks for PNG, EXIF/ICCP chunks for WebP
+    let mut encoded = DynImage::from_bytes(Bytes::from(encoded))
+        .ok()
+        .flatten()
+        .ok_or_else(|| PhotoBorderError::IoError(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Could not parse encoded image",
+        )))?;
+    encoded.set_exif(exif.clone().map(Bytes::from));
+    encoded.set_icc_profile(icc_profile.map(Bytes::from));
+
+    // The PNG encoder of `image` does not write the pHYs chunk; it goes
+    // right after the header
+    if let (DynImage::Png(png), Some(dpi)) = (&mut encoded, dpi) {
+        let pixels_per_metre = (dpi as f32 / 0.0254).round() as u32;
+        let mut physical = Vec::with_capacity(9);
+        physical.extend_from_slice(&pixels_per_metre.to_be_bytes());
+        physical.extend_from_slice(&pixels_per_metre.to_be_bytes());
+        // 1 = metre
+        physical.push(1);
+        png.remove_chunks_by_type(*b"pHYs");
+        png.chunks_mut().insert(1, PngChunk::new(*b"pHYs", Bytes::from(physical)));
+    }
+
+    // img-parts keeps the JPEG "Exif\0\0" prefix in the WebP EXIF chunk,
+    // which the WebP specification and most readers do not expect
+    if let (DynImage::WebP(webp), Some(exif)) = (&mut encoded, exif) {
+        for chunk in webp.chunks_mut().iter_mut().filter(|chunk| chunk.id() == CHUNK_EXIF) {
+            *chunk.content_mut() = RiffContent::Data(Bytes::from(exif.clone()));
+        }
+    }
+
+    Ok(encoded.encoder().bytes().to_vec())
+}
+
+/// Encodes an 8-bit AVIF file, without metadata
+///
+/// Lossless encoding codes the RGB channels directly at 8 bits with the
+/// lowest quantizer, avoiding the rounding of the YCbCr conversion.
+fn encode_avif(img: &DynamicImage, quality: u8, lossless: bool) -> Result<Vec<u8>, PhotoBorderError> {
+    let encoder = ravif::Encoder::new().with_speed(AVIF_SPEED);
+    let encoder = if lossless {
+        encoder.with_quality(100.0)
+            .with_bit_depth(ravif::BitDepth::Eight)
+            .with_internal_color_model(ravif::ColorModel::RGB)
+    } else {
+        encoder.with_quality(quality as f32)
+    };
+
+    let (width, height) = (img.width() as usize, img.height() as usize);
+    let encoded = if img.color().has_alpha() {
+        let pixels: Vec<ravif::RGBA8> = img.to_rgba8().pixels()
+            .map(|pixel| ravif::RGBA8::new(pixel[0], pixel[1], pixel[2], pixel[3]))
+            .collect();
+        encoder.encode_rgba(ravif::Img::new(pixels.as_slice(), width, height))
+    } else {
+        let pixels: Vec<ravif::RGB8> = img.to_rgb8().pixels()
+            .map(|pixel| ravif::RGB8::new(pixel[0], pixel[1], pixel[2]))
+            .collect();
+        encoder.encode_rgb(ravif::Img::new(pixels.as_slice(), width, height))
+    };
+    let encoded = encoded.map_err(|e| PhotoBorderError::ConfigError(format!("Could not encode AVIF: {}", e)))?;
+    Ok(encoded.avif_file)
+}
+
+/// Adds the EXIF block and colour profile to an AVIF file written by ravif
+///
+/// ravif cannot embed metadata, so the boxes are added to the encoded file:
+/// the EXIF block becomes an `Exif` item describing the primary image,
+/// stored at the end of the `mdat` box, and the profile a `colr` property
+/// of type `prof` of the primary image. The data of the other items moves
+/// by the growth of the `meta` box.
+///
+/// # Arguments
+/// * `avif` - AVIF file (`ftyp`, `meta` and `mdat` boxes)
+/// * `exif` - EXIF block to embed (TIFF structure)
+/// * `icc_profile` - Colour profile to embed
+fn add_avif_metadata(
+    avif: Vec<u8>,
+    exif: Option<Vec<u8>>,
+    icc_profile: Option<Vec<u8>>,
+) -> Result<Vec<u8>, PhotoBorderError> {
+    if exif.is_none() && icc_profile.is_none() {
+        return Ok(avif);
+    }
+    let invalid = || PhotoBorderError::IoError(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        "Unexpected structure of the encoded AVIF file",
+    ));
+
+    let boxes = iso_boxes(&avif).ok_or_else(invalid)?;
+    let [ftyp, meta, mdat] = boxes.as_slice() else { return Err(invalid()) };
+    if (&ftyp.kind, &meta.kind, &mdat.kind) != (b"ftyp", b"meta", b"mdat") {
+        return Err(invalid());
+    }
+    let children = meta.content.get(4..).and_then(iso_boxes).ok_or_else(invalid)?;
+    let child = |kind: &[u8; 4]| children.iter().find(|child| &child.kind == kind);
+    let primary_id = child(b"pitm")
+        .and_then(|pitm| pitm.content.get(4..6))
+        .map(|id| u16::from_be_bytes([id[0], id[1]]))
+        .ok_or_else(invalid)?;
+    let items = child(b"iloc").and_then(|iloc| iloc_items(iloc.content)).ok_or_else(invalid)?;
+    let exif_id = items.iter().map(|(id, _)| *id).max().unwrap_or(0).checked_add(1).ok_or_else(invalid)?;
+    // "ExifDataBlock": offset of the TIFF header, then the EXIF block
+    let exif = exif.map(|block| [&[0; 4], block.as_slice()].concat());
+
+    // The `meta` box for data moved by `shift` bytes; its length does not
+    // depend on the shift
+    let build_meta = |shift: u32| -> Option<Vec<u8>> {
+        let mut content = meta.content[..4].to_vec();
+        for child in &children {
+            let data = match (&child.kind, &exif) {
+                (b"iloc", _) => {
+                    let mut items: Vec<IlocItem> = items.iter()
+                        .map(|(id, extents)| {
+                            let extents = extents.iter()
+                                .map(|&(offset, length)| Some((offset.checked_add(shift)?, length)))
+                                .collect::<Option<Vec<_>>>()?;
+                            Some((*id, extents))
+                        })
+                        .collect::<Option<_>>()?;
+                    if let Some(exif) = &exif {
+                        // Appended to `mdat`, the last box of the file
+                        let offset = u32::try_from(avif.len()).ok()?.checked_add(shift)?;
+                        items.push((exif_id, vec![(offset, u32::try_from(exif.len()).ok()?)]));
+                    }
+                    iloc_content(&items)?
+                }
+                (b"iinf", Some(_)) => {
+                    // Version 0: 16-bit item count
+                    let count = u16::from_be_bytes(child.content.get(4..6)?.try_into().ok()?);
+                    if child.content[0] != 0 {
+                        return None;
+                    }
+                    let mut data = child.content[..4].to_vec();
+                    data.extend_from_slice(&count.checked_add(1)?.to_be_bytes());
+                    data.extend_from_slice(&child.content[6..]);
+                    let mut infe = vec![2, 0, 0, 0];
+                    infe.extend_from_slice(&exif_id.to_be_bytes());
+                    infe.extend_from_slice(&[0, 0]);
+                    infe.extend_from_slice(b"Exif\0");
+                    write_box(&mut data, b"infe", &infe);
+                    data
+                }
+                (b"iref", Some(_)) => {
+                    // Version 0: 16-bit item IDs
+                    if child.content.first() != Some(&0) {
+                        return None;
+                    }
+                    let mut data = child.content.to_vec();
+                    write_box(&mut data, b"cdsc", &exif_reference(exif_id, primary_id));
+                    data
+                }
+                (b"iprp", _) if icc_profile.is_some() => {
+                    add_avif_profile(child.content, primary_id, icc_profile.as_deref()?)?
+                }
+                _ => child.content.to_vec(),
+            };
+            write_box(&mut content, &child.kind, &data);
+        }
+        if exif.is_some() && !children.iter().any(|child| &child.kind == b"iref") {
+            let mut data = vec![0; 4];
+            write_box(&mut data, b"cdsc", &exif_reference(exif_id, primary_id));
+            write_box(&mut content, b"iref", &data);
+        }
+
+        let mut meta = Vec::new();
+        write_box(&mut meta, b"meta", &content);
+        Some(meta)
+    };
+
+    let old_length = meta.content.len() + 8;
+    let shift = build_meta(0)
+        .and_then(|new_meta| u32::try_from(new_meta.len().checked_sub(old_length)?).ok())
+        .ok_or_else(invalid)?;
+    let new_meta = build_meta(shift).ok_or_else(invalid)?;
+
+    let mut output = Vec::with_capacity(avif.len() + shift as usize + exif.as_ref().map_or(0, Vec::len));
+    write_box(&mut output, b"ftyp", ftyp.content);
+    output.extend_from_slice(&new_meta);
+    write_box(&mut output, b"mdat", &[mdat.content, exif.as_deref().unwrap_or_default()].concat());
+    Ok(output)
+}
+
+/// Item of an `iloc` box: ID and extents (file offset, length)
+type IlocItem = (u16, Vec<(u32, u32)>);
+
+/// Box of an ISOBMFF file (HEIF, AVIF): type and content, without header
+struct IsoBox<'a> {
+    kind: [u8; 4],
+    content: &'a [u8],
+}
+
+/// Splits a sequence of ISOBMFF boxes
+///
+/// # Returns
+/// `None` if a box size is invalid or uses the 64-bit form
+fn iso_boxes(mut data: &[u8]) -> Option<Vec<IsoBox<'_>>> {
+    let mut boxes = Vec::new();
+    while !data.is_empty() {
+        let size = u32::from_be_bytes(data.get(0..4)?.try_into().ok()?) as usize;
+        if size < 8 || size > data.len() {
+            return None;
+        }
+        boxes.push(IsoBox { kind: data[4..8].try_into().ok()?, content: &data[8..size] });
+        data = &data[size..];
+    }
+    Some(boxes)
+}
+
+/// Appends an ISOBMFF box
+fn write_box(output: &mut Vec<u8>, kind: &[u8; 4], content: &[u8]) {
+    output.extend_from_slice(&(content.len() as u32 + 8).to_be_bytes());
+    output.extend_from_slice(kind);
+    output.extend_from_slice(content);
+}
+
+/// Items of an `iloc` box
+///
+/// Only the layout written by ravif is read: version 0, 32-bit offsets and
+/// lengths, no base offset.
+fn iloc_items(content: &[u8]) -> Option<Vec<IlocItem>> {
+    if content.get(..6)? != [0, 0, 0, 0, 0x44, 0] {
+        return None;
+    }
+    let u16_at = |position: usize| Some(u16::from_be_bytes(content.get(position..position + 2)?.try_into().ok()?));
+    let u32_at = |position: usize| Some(u32::from_be_bytes(content.get(position..position + 4)?.try_into().ok()?));
+
+    let mut position = 8;
+    let mut items = Vec::new();
+    for _ in 0..u16_at(6)? {
+        // ID, data reference index, extent count
+        let id = u16_at(position)?;
+        let extent_count = u16_at(position + 4)?;
+        position += 6;
+        let mut extents = Vec::new();
+        for _ in 0..extent_count {
+            extents.push((u32_at(position)?, u32_at(position + 4)?));
+            position += 8;
+        }
+        items.push((id, extents));
+    }
+    Some(items)
+}
+
+/// Content of an `iloc` box in the layout read by [`iloc_items`]
+fn iloc_content(items: &[IlocItem]) -> Option<Vec<u8>> {
+    let mut content = vec![0, 0, 0, 0, 0x44, 0];
+    content.extend_from_slice(&u16::try_from(items.len()).ok()?.to_be_bytes());
+    for (id, extents) in items {
+        content.extend_from_slice(&id.to_be_bytes());
+        content.extend_from_slice(&[0, 0]);
+        content.extend_from_slice(&u16::try_from(extents.len()).ok()?.to_be_bytes());
+        for (offset, length) in extents {
+            content.extend_from_slice(&offset.to_be_bytes());
+            content.extend_from_slice(&length.to_be_bytes());
+        }
+    }
+    Some(content)
+}
+
+/// Content of the `cdsc` reference of the EXIF item to the image it describes
+fn exif_reference(exif_id: u16, image_id: u16) -> Vec<u8> {
+    [exif_id.to_be_bytes(), 1u16.to_be_bytes(), image_id.to_be_bytes()].concat()
+}
+
+/// Adds a `colr` property with the colour profile to the primary image
+///
+/// # Arguments
+/// * `iprp` - Content of the `iprp` box (`ipco` and `ipma` boxes)
+/// * `primary_id` - ID of the primary image item
+/// * `icc_profile` - Colour profile
+///
+/// # Returns
+/// The new content of the `iprp` box, `None` if its layout is unexpected
+fn add_avif_profile(iprp: &[u8], primary_id: u16, icc_profile: &[u8]) -> Option<Vec<u8>> {
+    let children = iso_boxes(iprp)?;
+    let ipco = children.iter().find(|child| &child.kind == b"ipco")?;
+    // Property indices start at 1, and are 7-bit in the `ipma` layout of ravif
+    let index = u8::try_from(iso_boxes(ipco.content)?.len() + 1).ok().filter(|index| *index < 0x80)?;
+
+    let mut content = Vec::new();
+    for child in &children {
+        let data = match &child.kind {
+            b"ipco" => {
+                let mut data = child.content.to_vec();
+                write_box(&mut data, b"colr", &[b"prof", icc_profile].concat());
+                data
+            }
+            b"ipma" => {
+                // Version 0 and flags 0: 16-bit item IDs, 8-bit associations
+                let ipma = child.content;
+                if ipma.get(..4)? != [0, 0, 0, 0] {
+                    return None;
+                }
+                let mut data = ipma[..8].to_vec();
+                let mut position = 8;
+                for _ in 0..u32::from_be_bytes(ipma.get(4..8)?.try_into().ok()?) {
+                    let id = u16::from_be_bytes(ipma.get(position..position + 2)?.try_into().ok()?);
+                    let count = *ipma.get(position + 2)? as usize;
+                    let associations = ipma.get(position + 3..position + 3 + count)?;
+                    data.extend_from_slice(&id.to_be_bytes());
+                    if id == primary_id {
+                        data.push(u8::try_from(count + 1).ok()?);
+                        data.extend_from_slice(associations);
+                        data.push(index);
+                    } else {
+                        data.push(count as u8);
+                        data.extend_from_slice(associations);
+                    }
+                    position += 3 + count;
+                }
+                data
+            }
+            _ => child.content.to_vec(),
+        };
+        write_box(&mut content, &child.kind, &data);
+    }
+    Some(content)
+}
+
+/// Encodes an uncompressed RGB or RGBA TIFF, 8 or 16 bits per sample
+///
+/// TIFF stores EXIF data in the same structure as the pixels, so the file
+/// is built from the fields of the EXIF block plus the fields describing
+/// the pixel strip.
+fn encode_tiff(
+    img: &DynamicImage,
+    exif: Option<Vec<u8>>,
+    icc_profile: Option<Vec<u8>>,
+    dpi: Option<u32>,
+) -> Result<Vec<u8>, PhotoBorderError> {
+    let exif = exif.map(|block| Reader::new().read_raw(block)).transpose()?;
+    let field = |tag: Tag, value: Value| Field { tag, ifd_num: In::PRIMARY, value };
+    // The resolution of the EXIF block is used only if it is complete
+    let has_resolution = output_metadata::RESOLUTION_TAGS.iter()
+        .all(|&tag| exif.as_ref().is_some_and(|exif| exif.get_field(tag, In::PRIMARY).is_some()));
+    let little_endian = exif.as_ref().map(|exif| exif.little_endian()).unwrap_or(true);
+
+    // Samples in the byte order of the file
+    let sixteen_bit = |samples: &[u16]| -> Vec<u8> {
+        samples.iter()
+            .flat_map(|&sample| if little_endian { sample.to_le_bytes() } else { sample.to_be_bytes() })
+            .collect()
+    };
+    let (strip, bits, samples) = match img {
+        DynamicImage::ImageRgb16(rgb) => (sixteen_bit(rgb.as_raw()), 16, 3),
+        DynamicImage::ImageRgba16(rgba) => (sixteen_bit(rgba.as_raw()), 16, 4),
+        DynamicImage::ImageRgba8(rgba) => (rgba.as_raw().clone(), 8, 4),
+        _ => (img.to_rgb8().into_raw(), 8, 3),
+    };
+
+    let mut image_fields = vec![
+        field(Tag::ImageWidth, Value::Long(vec![img.width()])),
+        field(Tag::ImageLength, Value::Long(vec![img.height()])),
+        field(Tag::BitsPerSample, Value::Short(vec![bits; samples])),
+        // 1 = no compression
+        field(Tag::Compression, Value::Short(vec![1])),
+        // 2 = RGB
+        field(Tag::PhotometricInterpretation, Value::Short(vec![2])),
+        field(Tag::SamplesPerPixel, Value::Short(vec![samples as u16])),
+        field(Tag::RowsPerStrip, Value::Long(vec![img.height()])),
+        // 1 = interleaved samples
+        field(Tag::PlanarConfiguration, Value::Short(vec![1])),
+    ];
+    if samples == 4 {
+        // 2 = unassociated (not premultiplied) alpha
+        image_fields.push(field(TIFF_EXTRA_SAMPLES, Value::Short(vec![2])));
+    }
+    // Resolution is required by baseline TIFF readers; the print
+    // resolution is already in the EXIF block when there is one
+    if !has_resolution {
+        let dpi = Value::Rational(vec![Rational { num: dpi.unwrap_or(72), denom: 1 }]);
+        image_fields.push(field(Tag::XResolution, dpi.clone()));
+        image_fields.push(field(Tag::YResolution, dpi));
+        // 2 = inches
+        image_fields.push(field(Tag::ResolutionUnit, Value::Short(vec![2])));
+    }
+    if let Some(profile) = icc_profile {
+        image_fields.push(field(TIFF_ICC_PROFILE, Value::Undefined(profile, 0)));
+    }
+
+    let strips = [strip.as_slice()];
+    let mut writer = exif::experimental::Writer::new();
+    for field in &image_fields {
+        writer.push_field(field);
+    }
+    writer.set_strips(&strips, In::PRIMARY);
+
+    if let Some(exif) = &exif {
+        // The fields written above must not be duplicated
+        let written = |field: &Field| {
+            field.ifd_num == In::PRIMARY
+                && (output_metadata::IMAGE_STRUCTURE_TAGS.contains(&field.tag)
+                    || (!has_resolution && output_metadata::RESOLUTION_TAGS.contains(&field.tag)))
+        };
+        for field in exif.fields().filter(|field| !written(field)) {
+            writer.push_field(field);
+        }
+        if let Some(thumbnail) = output_metadata::thumbnail(exif) {
+            writer.set_jpeg(thumbnail, In::THUMBNAIL);
+        }
+    }
+
+    let mut buffer = Cursor::new(Vec::new());
+    writer.write(&mut buffer, little_endian)?;
+    Ok(buffer.into_inner())
+}
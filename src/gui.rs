@@ -1,34 +1,264 @@
-use crate::{PhotoBorder, BorderType, PhotoBorderError};
+use crate::{PhotoBorder, BorderType, PhotoBorderError, SizeUnit};
+use crate::{format_color, parse_color, ExifOptions, DEFAULT_BORDER_COLOR, DEFAULT_FILL_COLOR, DEFAULT_TEXT_COLOR};
+use crate::effects::{ShadowOptions, StrokeOptions};
+use crate::fill::BorderFill;
+use crate::formats;
+use crate::settings::Settings;
+use image::{Rgb, RgbImage};
+use slint::{ModelRc, SharedString, VecModel};
 use std::path::Path;
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
-use std::thread;
 
 slint::include_modules!();
 
+/// Largest side of the preview image, in pixels
+const PREVIEW_MAX_SIZE: u32 = 720;
+
 pub struct GuiApp {
     window: AppWindow,
     selected_files: Arc<Mutex<Vec<String>>>,
+    /// Downscaled copy of the first selected image, used by the eyedropper
+    preview: Arc<Mutex<Option<RgbImage>>>,
+    /// Settings persisted between sessions
+    settings: Arc<Mutex<Settings>>,
+}
+
+/// Processing settings read from the window
+struct ProcessOptions {
+    border_type: String,
+    size_unit: String,
+    custom_sizes: (f32, f32, f32, f32),
+    show_exif: bool,
+    border_color: Rgb<u8>,
+    text_color: Rgb<u8>,
+    border_fill: String,
+    fill_blur: f32,
+    fill_darken: f32,
+    fill_color: Rgb<u8>,
+    fill_angle: f32,
+    fill_file: Option<String>,
+    shadow: bool,
+    shadow_offset: f32,
+    shadow_blur: f32,
+    shadow_opacity: f32,
+    shadow_color: Rgb<u8>,
+    stroke: bool,
+    stroke_width: f32,
+    stroke_color: Rgb<u8>,
+    output_dir: Option<String>,
+    font_path: Option<String>,
+}
+
+impl ProcessOptions {
+    fn from_window(window: &AppWindow) -> Self {
+        let output_dir = window.get_output_directory().to_string();
+        let font_path = window.get_font_path().to_string();
+        let fill_file = window.get_fill_file().to_string();
+
+        ProcessOptions {
+            border_type: window.get_border_type().to_string(),
+            size_unit: window.get_size_unit().to_string(),
+            custom_sizes: (
+                window.get_custom_top(),
+                window.get_custom_right(),
+                window.get_custom_bottom(),
+                window.get_custom_left(),
+            ),
+            show_exif: window.get_show_exif(),
+            border_color: to_rgb(window.get_border_color()),
+            text_color: to_rgb(window.get_text_color()),
+            border_fill: window.get_border_fill().to_string(),
+            fill_blur: window.get_fill_blur(),
+            fill_darken: window.get_fill_darken(),
+            fill_color: to_rgb(window.get_fill_color()),
+            fill_angle: window.get_fill_angle(),
+            fill_file: if fill_file.is_empty() { None } else { Some(fill_file) },
+            shadow: window.get_shadow(),
+            shadow_offset: window.get_shadow_offset(),
+            shadow_blur: window.get_shadow_blur(),
+            shadow_opacity: window.get_shadow_opacity(),
+            shadow_color: to_rgb(window.get_shadow_color()),
+            stroke: window.get_stroke(),
+            stroke_width: window.get_stroke_width(),
+            stroke_color: to_rgb(window.get_stroke_color()),
+            output_dir: if output_dir.is_empty() { None } else { Some(output_dir) },
+            font_path: if font_path.is_empty() { None } else { Some(font_path) },
+        }
+    }
+
+    fn border_type(&self) -> Result<BorderType, PhotoBorderError> {
+        if self.border_type != "custom" {
+            return BorderType::from_str(&self.border_type)
+                .map_err(|e| PhotoBorderError::ConfigError(e.to_string()));
+        }
+
+        let (top, right, bottom, left) = self.custom_sizes;
+        let unit = SizeUnit::from_str(&self.size_unit)
+            .map_err(|e| PhotoBorderError::ConfigError(e.to_string()))?;
+        Ok(BorderType::Custom { top, right, bottom, left, unit })
+    }
+
+    fn border_fill(&self) -> Result<BorderFill, PhotoBorderError> {
+        BorderFill::parse(
+            &self.border_fill,
+            &self.fill_blur.to_string(),
+            &self.fill_darken.to_string(),
+            (self.border_color, self.fill_color),
+            &self.fill_angle.to_string(),
+            self.fill_file.as_deref(),
+        )
+    }
+
+    fn shadow(&self) -> Result<Option<ShadowOptions>, PhotoBorderError> {
+        self.shadow
+            .then(|| ShadowOptions::parse(
+                &self.shadow_offset.to_string(),
+                &self.shadow_blur.to_string(),
+                &self.shadow_opacity.to_string(),
+                &format_color(self.shadow_color),
+            ))
+            .transpose()
+    }
+
+    fn stroke(&self) -> Result<Option<StrokeOptions>, PhotoBorderError> {
+        (self.stroke && self.stroke_width > 0.0)
+            .then(|| StrokeOptions::parse(&self.stroke_width.to_string(), "px", &format_color(self.stroke_color)))
+            .transpose()
+    }
+}
+
+fn to_rgb(color: slint::Color) -> Rgb<u8> {
+    Rgb([color.red(), color.green(), color.blue()])
+}
+
+fn to_slint_color(color: Rgb<u8>) -> slint::Color {
+    slint::Color::from_rgb_u8(color[0], color[1], color[2])
+}
+
+fn to_string_model(values: &[String]) -> ModelRc<SharedString> {
+    let values: Vec<SharedString> = values.iter().map(SharedString::from).collect();
+    ModelRc::new(VecModel::from(values))
+}
+
+/// Restores saved settings into the window
+fn apply_settings(window: &AppWindow, settings: &Settings) {
+    let [top, right, bottom, left] = settings.custom_sizes;
+
+    window.set_border_type(settings.border_type.as_str().into());
+    window.set_show_exif(settings.show_exif);
+    window.set_output_directory(settings.output_directory.as_str().into());
+    window.set_font_path(settings.font_path.as_str().into());
+    window.set_size_unit(settings.size_unit.as_str().into());
+    window.set_custom_top(top);
+    window.set_custom_right(right);
+    window.set_custom_bottom(bottom);
+    window.set_custom_left(left);
+    window.set_border_color(to_slint_color(
+        parse_color(&settings.border_color).unwrap_or(DEFAULT_BORDER_COLOR),
+    ));
+    window.set_text_color(to_slint_color(
+        parse_color(&settings.text_color).unwrap_or(DEFAULT_TEXT_COLOR),
+    ));
+    window.set_border_fill(settings.border_fill.as_str().into());
+    window.set_fill_blur(settings.fill_blur);
+    window.set_fill_darken(settings.fill_darken);
+    window.set_fill_color(to_slint_color(
+        parse_color(&settings.fill_color).unwrap_or(DEFAULT_FILL_COLOR),
+    ));
+    window.set_fill_angle(settings.fill_angle);
+    window.set_fill_file(settings.fill_file.as_str().into());
+    window.set_shadow(settings.shadow);
+    window.set_shadow_offset(settings.shadow_offset);
+    window.set_shadow_blur(settings.shadow_blur);
+    window.set_shadow_opacity(settings.shadow_opacity);
+    window.set_shadow_color(to_slint_color(
+        parse_color(&settings.shadow_color).unwrap_or(Rgb([0, 0, 0])),
+    ));
+    window.set_stroke(settings.stroke);
+    window.set_stroke_width(settings.stroke_width);
+    window.set_stroke_color(to_slint_color(
+        parse_color(&settings.stroke_color).unwrap_or(Rgb([0, 0, 0])),
+    ));
+    window.set_recent_output_dirs(to_string_model(&settings.recent_output_dirs));
+    window.set_recent_fonts(to_string_model(&settings.recent_fonts));
+}
+
+/// Copies the current window state into the settings
+fn store_settings(window: &AppWindow, settings: &mut Settings) {
+    let options = ProcessOptions::from_window(window);
+    let (top, right, bottom, left) = options.custom_sizes;
+
+    settings.border_type = options.border_type;
+    settings.show_exif = options.show_exif;
+    settings.output_directory = options.output_dir.unwrap_or_default();
+    settings.font_path = options.font_path.unwrap_or_default();
+    settings.size_unit = options.size_unit;
+    settings.custom_sizes = [top, right, bottom, left];
+    settings.border_color = format_color(options.border_color);
+    settings.text_color = format_color(options.text_color);
+    settings.border_fill = options.border_fill;
+    settings.fill_blur = options.fill_blur;
+    settings.fill_darken = options.fill_darken;
+    settings.fill_color = format_color(options.fill_color);
+    settings.fill_angle = options.fill_angle;
+    settings.fill_file = options.fill_file.unwrap_or_default();
+    settings.shadow = options.shadow;
+    settings.shadow_offset = options.shadow_offset;
+    settings.shadow_blur = options.shadow_blur;
+    settings.shadow_opacity = options.shadow_opacity;
+    settings.shadow_color = format_color(options.shadow_color);
+    settings.stroke = options.stroke;
+    settings.stroke_width = options.stroke_width;
+    settings.stroke_color = format_color(options.stroke_color);
+
+    let output_directory = settings.output_directory.clone();
+    let font_path = settings.font_path.clone();
+    settings.add_recent_output_dir(&output_directory);
+    settings.add_recent_font(&font_path);
+}
+
+/// Saves the window state, reporting failures without interrupting the user
+fn save_settings(window: &AppWindow, settings: &Mutex<Settings>) {
+    let mut settings = settings.lock().unwrap();
+    store_settings(window, &mut settings);
+    window.set_recent_output_dirs(to_string_model(&settings.recent_output_dirs));
+    window.set_recent_fonts(to_string_model(&settings.recent_fonts));
+
+    if let Err(e) = settings.save() {
+        eprintln!("Warning: Could not save settings: {}", e);
+    }
 }
 
 impl GuiApp {
     pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
         let window = AppWindow::new()?;
         let selected_files = Arc::new(Mutex::new(Vec::new()));
+        let preview = Arc::new(Mutex::new(None));
+
+        // Restore the settings of the previous session
+        let settings = Settings::load();
+        apply_settings(&window, &settings);
 
         Ok(GuiApp {
             window,
             selected_files,
+            preview,
+            settings: Arc::new(Mutex::new(settings)),
         })
     }
 
     pub fn setup_callbacks(&self) -> Result<(), Box<dyn std::error::Error>> {
         let window_weak = self.window.as_weak();
         let files = self.selected_files.clone();
+        let preview = self.preview.clone();
+        let settings = self.settings.clone();
 
         // File selection callback
         self.window.on_select_files({
             let window_weak = window_weak.clone();
             let files = files.clone();
+            let preview = preview.clone();
             move || {
                 if let Some(window) = window_weak.upgrade() {
                     match Self::open_file_dialog(true) {
@@ -41,6 +271,20 @@ impl GuiApp {
                                     format!("{} files selected", selected.len())
                                 };
                                 window.set_selected_files(files_text.into());
+
+                                // Show the first image in the preview panel
+                                match Self::load_preview(&selected[0]) {
+                                    Ok(img) => {
+                                        window.set_preview_image(Self::to_slint_image(&img));
+                                        *preview.lock().unwrap() = Some(img);
+                                    }
+                                    Err(e) => {
+                                        window.set_preview_image(slint::Image::default());
+                                        *preview.lock().unwrap() = None;
+                                        let status = format!("Could not load preview: {}", e);
+                                        window.set_status_text(status.into());
+                                    }
+                                }
                             }
                         }
                         Err(e) => {
@@ -55,11 +299,13 @@ impl GuiApp {
         // Output directory selection callback
         self.window.on_select_output_dir({
             let window_weak = window_weak.clone();
+            let settings = settings.clone();
             move || {
                 if let Some(window) = window_weak.upgrade() {
                     match Self::open_folder_dialog() {
                         Ok(Some(dir)) => {
                             window.set_output_directory(dir.into());
+                            save_settings(&window, &settings);
                         }
                         Ok(None) => {
                             // User cancelled, do nothing
@@ -76,11 +322,13 @@ impl GuiApp {
         // Font selection callback
         self.window.on_select_font({
             let window_weak = window_weak.clone();
+            let settings = settings.clone();
             move || {
                 if let Some(window) = window_weak.upgrade() {
                     match Self::open_font_dialog() {
                         Ok(Some(font_path)) => {
                             window.set_font_path(font_path.into());
+                            save_settings(&window, &settings);
                         }
                         Ok(None) => {
                             // User cancelled, do nothing
@@ -94,10 +342,62 @@ impl GuiApp {
             }
         });
 
+        // Texture or background image selection callback
+        self.window.on_select_fill_file({
+            let window_weak = window_weak.clone();
+            move || {
+                if let Some(window) = window_weak.upgrade() {
+                    match Self::open_file_dialog(false) {
+                        Ok(selected) => {
+                            if let Some(file) = selected.into_iter().next() {
+                                window.set_fill_file(file.into());
+                            }
+                        }
+                        Err(e) => {
+                            let status = format!("Error selecting file: {}", e);
+                            window.set_status_text(status.into());
+                        }
+                    }
+                }
+            }
+        });
+
+        // Eyedropper callback: sample the preview at the clicked position
+        self.window.on_pick_color({
+            let window_weak = window_weak.clone();
+            let preview = preview.clone();
+            move |x, y, width, height| {
+                if let Some(window) = window_weak.upgrade() {
+                    let sampled = preview.lock().unwrap()
+                        .as_ref()
+                        .and_then(|img| Self::sample_preview(img, x, y, width, height));
+
+                    match sampled {
+                        Some(color) => {
+                            let color = to_slint_color(color);
+                            match window.get_eyedropper_target().as_str() {
+                                "border" => window.set_border_color(color),
+                                "text" => window.set_text_color(color),
+                                "fill" => window.set_fill_color(color),
+                                "shadow" => window.set_shadow_color(color),
+                                "stroke" => window.set_stroke_color(color),
+                                _ => {}
+                            }
+                            window.set_eyedropper_target("".into());
+                        }
+                        None => {
+                            window.set_status_text("Click inside the photo to pick a colour".into());
+                        }
+                    }
+                }
+            }
+        });
+
         // Process images callback
         self.window.on_process_images({
             let window_weak = window_weak.clone();
             let files = files.clone();
+            let settings = settings.clone();
             move || {
                 if let Some(window) = window_weak.upgrade() {
                     let files_to_process = files.lock().unwrap().clone();
@@ -106,11 +406,9 @@ impl GuiApp {
                         return;
                     }
 
-                    // Get settings from UI
-                    let border_type_str = window.get_border_type().to_string();
-                    let show_exif = window.get_show_exif();
-                    let output_dir = window.get_output_directory().to_string();
-                    let font_path = window.get_font_path().to_string();
+                    // Get settings from UI and remember them for next time
+                    let options = ProcessOptions::from_window(&window);
+                    save_settings(&window, &settings);
 
                     // Set processing state
                     window.set_processing(true);
@@ -118,13 +416,7 @@ impl GuiApp {
 
                     // Process in background thread
                     let window_weak_clone = window_weak.clone();
-                    let result = Self::process_images_background(
-                        files_to_process,
-                        &border_type_str,
-                        show_exif,
-                        if output_dir.is_empty() { None } else { Some(&output_dir) },
-                        if font_path.is_empty() { None } else { Some(&font_path) },
-                    );
+                    let result = Self::process_images_background(files_to_process, &options);
 
                     // Update UI with result
                     if let Some(window) = window_weak_clone.upgrade() {
@@ -148,17 +440,20 @@ impl GuiApp {
 
     fn process_images_background(
         files: Vec<String>,
-        border_type_str: &str,
-        show_exif: bool,
-        output_dir: Option<&str>,
-        font_path: Option<&str>,
+        options: &ProcessOptions,
     ) -> Result<String, PhotoBorderError> {
         // Parse border type
-        let border_type = BorderType::from_str(border_type_str)
-            .map_err(|e| PhotoBorderError::FontError(e.to_string()))?;
+        let border_type = options.border_type()?;
+        let output_dir = options.output_dir.as_deref();
 
         // Create PhotoBorder instance
-        let photo_border = PhotoBorder::new(border_type, show_exif, font_path)?;
+        let photo_border = PhotoBorder::new(border_type, options.show_exif, options.font_path.as_deref())?
+            .with_border_color(options.border_color)
+            .with_border_fill(options.border_fill()?)
+            .with_shadow(options.shadow()?)
+            .with_stroke(options.stroke()?)
+            .with_text_color(options.text_color)
+            .with_exif_options(ExifOptions::load(None, None)?);
 
         // Process images
         let mut success_count = 0;
@@ -168,7 +463,7 @@ impl GuiApp {
         for file_path in &files {
             match photo_border.process_image(
                 file_path,
-                output_dir.map(|s| Path::new(s))
+                output_dir.map(Path::new)
             ) {
                 Ok(()) => success_count += 1,
                 Err(e) => {
@@ -196,6 +491,43 @@ impl GuiApp {
         Ok(status)
     }
 
+    /// Loads a downscaled copy of an image for the preview panel
+    fn load_preview(path: &str) -> Result<RgbImage, PhotoBorderError> {
+        let img = formats::open_image(Path::new(path))?;
+        Ok(img.thumbnail(PREVIEW_MAX_SIZE, PREVIEW_MAX_SIZE).to_rgb8())
+    }
+
+    fn to_slint_image(img: &RgbImage) -> slint::Image {
+        let buffer = slint::SharedPixelBuffer::<slint::Rgb8Pixel>::clone_from_slice(
+            img.as_raw(),
+            img.width(),
+            img.height(),
+        );
+        slint::Image::from_rgb8(buffer)
+    }
+
+    /// Returns the preview pixel under a click, given the size of the
+    /// preview area (the image is displayed with `image-fit: contain`)
+    fn sample_preview(img: &RgbImage, x: f32, y: f32, width: f32, height: f32) -> Option<Rgb<u8>> {
+        let (img_width, img_height) = (img.width() as f32, img.height() as f32);
+        let scale = (width / img_width).min(height / img_height);
+        if scale <= 0.0 {
+            return None;
+        }
+
+        // Offsets of the letterboxed image inside the preview area
+        let offset_x = (width - img_width * scale) / 2.0;
+        let offset_y = (height - img_height * scale) / 2.0;
+        let px = (x - offset_x) / scale;
+        let py = (y - offset_y) / scale;
+
+        if px < 0.0 || py < 0.0 || px >= img_width || py >= img_height {
+            return None;
+        }
+
+        Some(*img.get_pixel(px as u32, py as u32))
+    }
+
     fn open_file_dialog(multiple: bool) -> Result<Vec<String>, Box<dyn std::error::Error>> {
         use std::process::Command;
 
@@ -204,19 +536,23 @@ impl GuiApp {
         #[cfg(target_os = "windows")]
         {
             let _used_for_other_os = multiple;
+            let patterns = formats::INPUT_EXTENSIONS.iter()
+                .map(|ext| format!("*.{}", ext))
+                .collect::<Vec<_>>()
+                .join(";");
             // Windows PowerShell command for file dialog
             let output = Command::new("powershell")
                 .arg("-Command")
-                .arg(r#"
+                .arg(format!(r#"
                 Add-Type -AssemblyName System.Windows.Forms
                 $openFileDialog = New-Object System.Windows.Forms.OpenFileDialog
-                $openFileDialog.Filter = 'Image files (*.jpg;*.jpeg;*.png)|*.jpg;*.jpeg;*.png|All files (*.*)|*.*'
+                $openFileDialog.Filter = 'Image files ({patterns})|{patterns}|All files (*.*)|*.*'
                 $openFileDialog.Multiselect = $true
                 $openFileDialog.Title = 'Select Images'
-                if ($openFileDialog.ShowDialog() -eq [System.Windows.Forms.DialogResult]::OK) {
+                if ($openFileDialog.ShowDialog() -eq [System.Windows.Forms.DialogResult]::OK) {{
                     $openFileDialog.FileNames -join ';'
-                }
-                "#)
+                }}
+                "#))
                 .output()?;
 
             let result_string = String::from_utf8_lossy(&output.stdout);
@@ -231,10 +567,14 @@ impl GuiApp {
         #[cfg(target_os = "macos")]
         {
             // macOS osascript command for file dialog
+            let types = formats::INPUT_EXTENSIONS.iter()
+                .map(|ext| format!("\"{}\"", ext))
+                .collect::<Vec<_>>()
+                .join(", ");
             let script = if multiple {
-                r#"tell application "System Events" to return POSIX path of (choose file with prompt "Select Images" of type {"jpg", "jpeg", "png"} with multiple selections allowed)"#
+                format!(r#"tell application "System Events" to return POSIX path of (choose file with prompt "Select Images" of type {{{}}} with multiple selections allowed)"#, types)
             } else {
-                r#"tell application "System Events" to return POSIX path of (choose file with prompt "Select Images" of type {"jpg", "jpeg", "png"})"#
+                format!(r#"tell application "System Events" to return POSIX path of (choose file with prompt "Select Images" of type {{{}}})"#, types)
             };
 
             let output = Command::new("osascript")
@@ -256,10 +596,14 @@ impl GuiApp {
         #[cfg(target_os = "linux")]
         {
             // Linux zenity command for file dialog
+            let patterns = formats::INPUT_EXTENSIONS.iter()
+                .map(|ext| format!("*.{}", ext))
+                .collect::<Vec<_>>()
+                .join(" ");
             let mut cmd = Command::new("zenity");
             cmd.arg("--file-selection")
                 .arg("--title=Select Images")
-                .arg("--file-filter=Image files | *.jpg *.jpeg *.png");
+                .arg(format!("--file-filter=Image files | {}", patterns));
 
             if multiple {
                 cmd.arg("--multiple");
@@ -396,6 +740,10 @@ impl GuiApp {
     }
 
     pub fn run(&self) -> Result<(), slint::PlatformError> {
-        self.window.run()
+        self.window.run()?;
+
+        // Save the last used settings on exit
+        save_settings(&self.window, &self.settings);
+        Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,161 @@
+//! EXIF inspection subcommand
+//!
+//! `schnapsshot exif <files...>` prints the metadata extracted by
+//! [`ExifData::load`] (EXIF data completed by sidecar files), optionally
+//! followed by every raw EXIF tag, either as a human-readable table or as
+//! JSON for scripting. It takes the EXIF options of the main command
+//! ([`ExifOptions::args`]), so that the fields are those a caption would
+//! show. Files that cannot be read are reported on stderr and make the
+//! command fail.
+
+use crate::formats;
+use crate::{ExifData, ExifOptions, PhotoBorderError};
+use clap::{Arg, ArgMatches, Command};
+use serde::Serialize;
+use std::error::Error;
+use std::path::Path;
+
+/// A single EXIF tag as stored in the file
+#[derive(Debug, Serialize)]
+struct RawTag {
+    /// IFD containing the tag ("primary" or "thumbnail")
+    ifd: String,
+    /// Tag name as known by the `exif` crate
+    tag: String,
+    /// Value with its unit, as displayed by the `exif` crate
+    value: String,
+}
+
+/// Inspection result for one file
+#[derive(Debug, Serialize)]
+struct Report {
+    file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exif: Option<ExifData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    raw_tags: Option<Vec<RawTag>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Builds the `exif` subcommand definition
+pub fn command() -> Command {
+    Command::new("exif")
+        .about("Print the EXIF data extracted from one or more photos")
+        .arg(
+            Arg::new("files")
+                .help("Input image filename(s)")
+                .required(true)
+                .num_args(1..),
+        )
+        .arg(
+            Arg::new("all")
+                .short('a')
+                .long("all")
+                .help("Also print every raw EXIF tag found in the file")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .args(ExifOptions::args())
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .help("Print the result as JSON instead of a table")
+                .action(clap::ArgAction::SetTrue),
+        )
+}
+
+/// Runs the `exif` subcommand
+///
+/// # Returns
+/// * `Ok(())` if every file could be read
+/// * `Err` after printing the reports if an option is invalid or a file
+///   could not be read
+pub fn run(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let show_all = matches.get_flag("all");
+    // Same options as the main command, for the same fields as the captions
+    let options = ExifOptions::from_matches(matches)?;
+    let reports: Vec<Report> = matches.get_many::<String>("files")
+        .unwrap()
+        .map(|file| inspect_file(file, show_all, &options))
+        .collect();
+
+    if matches.get_flag("json") {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    } else {
+        for report in &reports {
+            print_table(report);
+        }
+    }
+
+    let failed = reports.iter().filter(|report| report.error.is_some()).count();
+    if failed > 0 {
+        return Err(Box::new(PhotoBorderError::IoError(std::io::Error::other(
+            format!("{} of {} file(s) could not be read", failed, reports.len()),
+        ))));
+    }
+    Ok(())
+}
+
+/// Extracts parsed fields (and raw tags if requested) from a file
+fn inspect_file(file: &str, show_all: bool, options: &ExifOptions) -> Report {
+    let mut report = Report {
+        file: file.to_string(),
+        exif: None,
+        raw_tags: None,
+        error: None,
+    };
+
+    match ExifData::load(file, options) {
+        Ok(exif) => report.exif = Some(exif),
+        Err(e) => {
+            report.error = Some(e.to_string());
+            return report;
+        }
+    }
+
+    if show_all {
+        match read_raw_tags(Path::new(file)) {
+            Ok(tags) => report.raw_tags = Some(tags),
+            Err(e) => report.error = Some(e.to_string()),
+        }
+    }
+
+    report
+}
+
+/// Reads every tag of the EXIF container, in file order
+fn read_raw_tags(path: &Path) -> Result<Vec<RawTag>, PhotoBorderError> {
+    let exif = formats::read_exif(path)?;
+
+    Ok(exif.fields()
+        .map(|field| RawTag {
+            ifd: field.ifd_num.to_string(),
+            tag: field.tag.to_string(),
+            value: field.display_value().with_unit(&exif).to_string(),
+        })
+        .collect())
+}
+
+/// Prints a report as an aligned two-column table
+fn print_table(report: &Report) {
+    println!("{}", report.file);
+
+    if let Some(exif) = &report.exif {
+        for (name, value) in exif.fields() {
+            println!("  {:<22} {}", name, value.unwrap_or("-"));
+        }
+    }
+
+    if let Some(tags) = &report.raw_tags {
+        println!("  Raw tags:");
+        for tag in tags {
+            println!("  {:<10} {:<30} {}", tag.ifd, tag.tag, tag.value);
+        }
+    }
+
+    if let Some(error) = &report.error {
+        eprintln!("{}: {}", report.file, error);
+    }
+
+    println!();
+}
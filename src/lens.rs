@@ -0,0 +1,348 @@
+//! Lens name inference
+//!
+//! Many bodies and adapted lenses leave the EXIF `LensModel` tag empty,
+//! which drops the lens from the caption. When it is missing, the lens
+//! name is inferred from, in order:
+//!
+//! 1. The maker note: lens name (Canon) or lens ID looked up in the
+//!    lens table (Canon, Nikon up to the D2X and D70, Sony A-mount);
+//!    other Nikon and Fujifilm maker notes only give the focal range and
+//!    aperture used in step 2
+//! 2. The lens table entries keyed by focal range and maximum aperture,
+//!    matched against `LensSpecification`, the maker note, or for manual
+//!    lenses without either the `FocalLength` / `MaxApertureValue` set on
+//!    the body
+//! 3. `LensMake` and `LensSpecification` ("Sigma 24-70mm f/2.8")
+
+use crate::exif_format::trim_number;
+use crate::makernote::{self, LensIdBrand};
+use crate::settings;
+use crate::PhotoBorderError;
+use exif::{Exif, In, Tag, Value};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Built-in table, embedded in the executable
+const BUILTIN_TABLE: &str = include_str!("../data/lenses.toml");
+
+/// File name of the user table in the configuration directory
+const USER_TABLE_FILE: &str = "lenses.toml";
+
+/// Focal lengths closer than this (in mm) are considered equal
+const FOCAL_TOLERANCE: f64 = 0.5;
+
+/// Apertures closer than this (in f-stops) are considered equal
+const APERTURE_TOLERANCE: f64 = 0.1;
+
+/// Focal range and maximum aperture of a lens
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LensSpec {
+    /// Shortest focal length in mm
+    pub min_focal: f64,
+    /// Longest focal length in mm (equal to `min_focal` for primes)
+    pub max_focal: f64,
+    /// Maximum aperture at the shortest focal length
+    pub aperture: Option<f64>,
+    /// Maximum aperture at the longest focal length
+    pub aperture_tele: Option<f64>,
+}
+
+impl LensSpec {
+    /// Builds a spec, returning `None` if the focal range is unknown
+    ///
+    /// Zero or non-finite values are treated as unknown.
+    pub fn new(min_focal: f64, max_focal: f64, aperture: Option<f64>, aperture_tele: Option<f64>) -> Option<Self> {
+        let known = |v: f64| v.is_finite() && v > 0.0;
+        if !known(min_focal) {
+            return None;
+        }
+
+        Some(LensSpec {
+            min_focal,
+            max_focal: if known(max_focal) { max_focal.max(min_focal) } else { min_focal },
+            aperture: aperture.filter(|v| known(*v)),
+            aperture_tele: aperture_tele.filter(|v| known(*v)),
+        })
+    }
+
+    /// Builds a spec from the 4 values of `LensSpecification` (min focal,
+    /// max focal, min f-number at min focal, min f-number at max focal)
+    pub fn from_values(values: &[f64]) -> Option<Self> {
+        match values {
+            [min, max, aperture, aperture_tele, ..] => Self::new(*min, *max, Some(*aperture), Some(*aperture_tele)),
+            _ => None,
+        }
+    }
+
+    /// Parses a lens table key such as "35 f/1.4", "24-70mm f/2.8"
+    /// or "18-55mm F3.5-5.6"
+    pub fn parse(key: &str) -> Option<Self> {
+        let mut parts = key.split_whitespace();
+        let focal = parts.next()?.to_lowercase();
+        let focal = focal.trim_end_matches("mm");
+        let (min, max) = match focal.split_once('-') {
+            Some((min, max)) => (min.parse().ok()?, max.parse().ok()?),
+            None => {
+                let focal = focal.parse().ok()?;
+                (focal, focal)
+            }
+        };
+
+        let (aperture, aperture_tele) = match parts.next() {
+            Some(aperture) => {
+                let aperture = aperture.to_lowercase();
+                let aperture = aperture.trim_start_matches("f/").trim_start_matches('f');
+                match aperture.split_once('-') {
+                    Some((wide, tele)) => (Some(wide.parse().ok()?), Some(tele.parse().ok()?)),
+                    None => (Some(aperture.parse().ok()?), None),
+                }
+            }
+            None => (None, None),
+        };
+
+        Self::new(min, max, aperture, aperture_tele)
+    }
+
+    /// Whether a lens table key matches the spec read from a photo
+    ///
+    /// Apertures are only compared when the key specifies one.
+    fn matches(&self, observed: &LensSpec) -> bool {
+        let close = |a: f64, b: f64, tolerance: f64| (a - b).abs() <= tolerance;
+
+        close(self.min_focal, observed.min_focal, FOCAL_TOLERANCE)
+            && close(self.max_focal, observed.max_focal, FOCAL_TOLERANCE)
+            && match (self.aperture, observed.aperture) {
+                (Some(key), Some(observed)) => close(key, observed, APERTURE_TOLERANCE),
+                (Some(_), None) => false,
+                (None, _) => true,
+            }
+    }
+
+    fn is_prime(&self) -> bool {
+        (self.max_focal - self.min_focal).abs() < f64::EPSILON
+    }
+}
+
+/// Formats the spec as a lens name: "35mm f/1.4", "18-55mm f/3.5-5.6"
+impl fmt::Display for LensSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_prime() {
+            write!(f, "{}mm", trim_number(self.min_focal, 1))?;
+        } else {
+            write!(f, "{}-{}mm", trim_number(self.min_focal, 1), trim_number(self.max_focal, 1))?;
+        }
+
+        if let Some(aperture) = self.aperture {
+            write!(f, " f/{}", trim_number(aperture, 1))?;
+            if let Some(tele) = self.aperture_tele.filter(|t| (t - aperture).abs() > APERTURE_TOLERANCE) {
+                write!(f, "-{}", trim_number(tele, 1))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// TOML layout of a lens table
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct LensTableFile {
+    canon: HashMap<String, String>,
+    nikon: HashMap<String, String>,
+    sony: HashMap<String, String>,
+    specs: HashMap<String, String>,
+}
+
+/// Lookup table of lens names by maker note ID or by focal range/aperture
+#[derive(Debug, Clone, Default)]
+pub struct LensTable {
+    canon: HashMap<u64, String>,
+    nikon: HashMap<u64, String>,
+    sony: HashMap<u64, String>,
+    /// Entries in load order; later entries take precedence
+    specs: Vec<(LensSpec, String)>,
+}
+
+impl LensTable {
+    /// Returns the built-in table
+    pub fn builtin() -> Self {
+        let mut table = LensTable::default();
+        table.merge_toml(BUILTIN_TABLE)
+            .expect("built-in lens table is valid TOML");
+        table
+    }
+
+    /// Loads the built-in table extended with the user's tables
+    ///
+    /// # Arguments
+    /// * `extra` - Optional table given on the command line
+    ///
+    /// # Returns
+    /// * `Ok(LensTable)` with entries from later tables overriding
+    ///   earlier ones (built-in, then configuration directory, then `extra`)
+    /// * `Err(PhotoBorderError)` if `extra` cannot be read or parsed
+    pub fn load(extra: Option<&Path>) -> Result<Self, PhotoBorderError> {
+        let mut table = Self::builtin();
+
+        if let Some(path) = settings::user_file(USER_TABLE_FILE) {
+            if let Err(e) = table.merge_file(&path) {
+                eprintln!("Warning: Ignoring lens table {}: {}", path.display(), e);
+            }
+        }
+
+        if let Some(path) = extra {
+            table.merge_file(path)?;
+        }
+
+        Ok(table)
+    }
+
+    fn merge_file(&mut self, path: &Path) -> Result<(), PhotoBorderError> {
+        let content = fs::read_to_string(path)?;
+        self.merge_toml(&content)
+    }
+
+    fn merge_toml(&mut self, content: &str) -> Result<(), PhotoBorderError> {
+        let file: LensTableFile = toml::from_str(content)
+            .map_err(|e| PhotoBorderError::ConfigError(format!("Invalid lens table: {}", e)))?;
+
+        let invalid_id = |id: &str| PhotoBorderError::ConfigError(format!("Invalid lens ID '{}'", id));
+        let parse_id = |id: &str| id.trim().parse::<u32>().map(u64::from).map_err(|_| invalid_id(id));
+        // Nikon IDs are written as 8 hexadecimal bytes, as by ExifTool
+        let parse_nikon_id = |id: &str| {
+            let bytes = id.split_whitespace()
+                .map(|byte| u8::from_str_radix(byte, 16))
+                .collect::<Result<Vec<u8>, _>>()
+                .ok()
+                .filter(|bytes| bytes.len() == 8)
+                .ok_or_else(|| invalid_id(id))?;
+            Ok::<_, PhotoBorderError>(bytes.iter().fold(0, |id, &byte| id << 8 | byte as u64))
+        };
+
+        for (id, name) in file.canon {
+            self.canon.insert(parse_id(&id)?, name);
+        }
+        for (id, name) in file.nikon {
+            self.nikon.insert(parse_nikon_id(&id)?, name);
+        }
+        for (id, name) in file.sony {
+            self.sony.insert(parse_id(&id)?, name);
+        }
+        for (key, name) in file.specs {
+            let spec = LensSpec::parse(&key)
+                .ok_or_else(|| PhotoBorderError::ConfigError(format!("Invalid lens key '{}'", key)))?;
+            self.specs.push((spec, name));
+        }
+
+        Ok(())
+    }
+
+    fn by_id(&self, brand: LensIdBrand, id: u64) -> Option<&str> {
+        let table = match brand {
+            LensIdBrand::Canon => &self.canon,
+            LensIdBrand::Nikon => &self.nikon,
+            LensIdBrand::Sony => &self.sony,
+        };
+        table.get(&id).map(String::as_str)
+    }
+
+    fn by_spec(&self, observed: &LensSpec) -> Option<&str> {
+        self.specs.iter()
+            .rev()
+            .find(|(key, _)| key.matches(observed))
+            .map(|(_, name)| name.as_str())
+    }
+}
+
+/// Infers the lens name of a photo whose `LensModel` tag is missing
+///
+/// # Arguments
+/// * `exif` - Parsed EXIF container
+/// * `make` - Raw camera Make, used to decode the maker note
+/// * `table` - Lens table for ID and focal range lookups
+///
+/// # Returns
+/// The inferred name, or `None` if nothing identifies the lens
+pub fn infer_lens(exif: &Exif, make: Option<&str>, table: &LensTable) -> Option<String> {
+    let maker_note = makernote::read_lens(exif, make);
+
+    // 1. Name or ID from the maker note
+    if let Some(name) = maker_note.name {
+        return Some(name);
+    }
+    if let Some(name) = maker_note.id.and_then(|(brand, id)| table.by_id(brand, id)) {
+        return Some(name.to_string());
+    }
+
+    // 2. User entries keyed by focal range and aperture; the focal
+    // length set on the body only stands for the lens when nothing else
+    // describes it (a zoom would match a prime at its current focal length)
+    let spec = lens_specification(exif).or(maker_note.spec);
+    let observed = spec.or_else(|| manual_lens_spec(exif));
+    if let Some(name) = observed.and_then(|observed| table.by_spec(&observed)) {
+        return Some(name.to_string());
+    }
+
+    // 3. Lens maker and specification
+    let lens_make = text(exif, Tag::LensMake);
+    match (lens_make, spec) {
+        (Some(lens_make), Some(spec)) => Some(format!("{} {}", lens_make, spec)),
+        (None, Some(spec)) => Some(spec.to_string()),
+        (Some(lens_make), None) => Some(lens_make),
+        (None, None) => None,
+    }
+}
+
+/// Reads the standard `LensSpecification` tag
+fn lens_specification(exif: &Exif) -> Option<LensSpec> {
+    match exif.get_field(Tag::LensSpecification, In::PRIMARY)?.value {
+        Value::Rational(ref values) => {
+            let values: Vec<f64> = values.iter()
+                .map(|v| if v.denom == 0 { 0.0 } else { v.to_f64() })
+                .collect();
+            LensSpec::from_values(&values)
+        }
+        _ => None,
+    }
+}
+
+/// Spec of a manual lens from the focal length and maximum aperture
+/// entered on the body (e.g. Nikon non-CPU lens data)
+///
+/// The spec is a prime at the current focal length, so it only matches
+/// prime entries of the table. Only used for table lookups, when neither
+/// `LensSpecification` nor the maker note describe the lens.
+fn manual_lens_spec(exif: &Exif) -> Option<LensSpec> {
+    let focal = match exif.get_field(Tag::FocalLength, In::PRIMARY)?.value {
+        Value::Rational(ref values) => values.first()?.to_f64(),
+        _ => return None,
+    };
+
+    // MaxApertureValue is in APEX units: f-number = sqrt(2)^value
+    let aperture = exif.get_field(Tag::MaxApertureValue, In::PRIMARY)
+        .and_then(|field| match field.value {
+            Value::Rational(ref values) => values.first().map(|v| 2f64.sqrt().powf(v.to_f64())),
+            _ => None,
+        })
+        // Round to the usual one-decimal marking (f/1.4, f/2.8)
+        .map(|f| (f * 10.0).round() / 10.0);
+
+    LensSpec::new(focal, focal, aperture, None)
+}
+
+/// Reads a non-empty ASCII tag
+fn text(exif: &Exif, tag: Tag) -> Option<String> {
+    let field = exif.get_field(tag, In::PRIMARY)?;
+    let Value::Ascii(ref values) = field.value else {
+        return None;
+    };
+    let text = values.iter()
+        .map(|v| String::from_utf8_lossy(v).to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    if text.is_empty() { None } else { Some(text.to_string()) }
+}
@@ -0,0 +1,251 @@
+//! Camera brand logos
+//!
+//! Draws the camera brand next to the caption, as on "Leica-style"
+//! frames. The logo of a photo is chosen from its normalized make
+//! (`ExifData::make`, e.g. "Fujifilm"), in order:
+//!
+//! 1. The logo file given on the command line (`--logo-file`)
+//! 2. `<brand>.svg` or `<brand>.png` in the logo directory (`--logo-dir`),
+//!    then in `logos/` of the configuration directory; the file name is
+//!    the lowercased brand with spaces replaced by dashes ("phase-one.svg")
+//! 3. The embedded wordmark: the brand name set in DejaVu Sans Bold
+//!
+//! Manufacturer artwork is not shipped with the application: users who
+//! want the real logos drop them in the logo directory.
+
+use crate::settings;
+use crate::PhotoBorderError;
+use image::{imageops, Rgb, Rgba, RgbaImage};
+use resvg::{tiny_skia, usvg};
+use rusttype::{point, Font, Scale};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Font of the embedded wordmarks
+const WORDMARK_FONT: &[u8] = include_bytes!("../fonts/DejaVuSans-Bold.ttf");
+
+/// Name of the logo directory inside the configuration directory
+const USER_LOGO_DIR: &str = "logos";
+
+/// Supported logo file extensions, in lookup order
+const LOGO_EXTENSIONS: [&str; 2] = ["svg", "png"];
+
+/// Position of the logo in the bottom border
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LogoPosition {
+    /// Before the caption, which is moved right to make room
+    #[default]
+    Left,
+    /// Centred horizontally on the image
+    Center,
+    /// Aligned with the right edge of the photo
+    Right,
+}
+
+impl FromStr for LogoPosition {
+    type Err = &'static str;
+
+    /// Parses a logo position ("left", "center"/"centre" or "right")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "left" => Ok(LogoPosition::Left),
+            "center" | "centre" => Ok(LogoPosition::Center),
+            "right" => Ok(LogoPosition::Right),
+            _ => Err("Invalid logo position"),
+        }
+    }
+}
+
+/// Settings of the brand logo
+#[derive(Debug, Clone)]
+pub struct LogoOptions {
+    /// Where the logo is drawn relative to the caption
+    pub position: LogoPosition,
+    /// Logo height as a fraction of the bottom border height
+    pub size: f32,
+    /// Whether logo files are tinted with the text colour (wordmarks
+    /// always use it)
+    pub tint: bool,
+    /// Logo used for every photo, whatever the camera
+    pub file: Option<PathBuf>,
+    /// Directory searched for `<brand>.svg` / `<brand>.png` before the
+    /// configuration directory
+    pub dir: Option<PathBuf>,
+}
+
+impl Default for LogoOptions {
+    fn default() -> Self {
+        LogoOptions {
+            position: LogoPosition::default(),
+            size: 0.5,
+            tint: true,
+            file: None,
+            dir: None,
+        }
+    }
+}
+
+/// Loads the logo of a brand, scaled to the given height
+///
+/// # Arguments
+/// * `brand` - Normalized camera make, if known
+/// * `height` - Logo height in pixels
+/// * `color` - Text colour, used for wordmarks and tinting
+/// * `options` - Logo settings
+///
+/// # Returns
+/// * `Ok(Some(RgbaImage))` with the logo
+/// * `Ok(None)` if there is neither a logo file nor a brand
+/// * `Err(PhotoBorderError)` if a logo file cannot be read
+pub fn load_logo(
+    brand: Option<&str>,
+    height: u32,
+    color: Rgb<u8>,
+    options: &LogoOptions,
+) -> Result<Option<RgbaImage>, PhotoBorderError> {
+    let brand = brand.map(str::trim).filter(|b| !b.is_empty());
+    let file = options.file.clone()
+        .or_else(|| brand.and_then(|brand| find_logo_file(brand, options.dir.as_deref())));
+
+    let logo = match (file, brand) {
+        (Some(file), _) => {
+            let logo = render_file(&file, height)?;
+            if options.tint { tint(&logo, color) } else { logo }
+        }
+        (None, Some(brand)) => render_wordmark(brand, height, color),
+        (None, None) => return Ok(None),
+    };
+
+    Ok(Some(logo))
+}
+
+/// Looks for `<brand>.svg` or `<brand>.png` in the logo directories
+fn find_logo_file(brand: &str, dir: Option<&Path>) -> Option<PathBuf> {
+    let name = brand.to_lowercase().split_whitespace().collect::<Vec<_>>().join("-");
+    let user_dir = settings::config_dir().map(|config| config.join(USER_LOGO_DIR));
+
+    dir.map(Path::to_path_buf)
+        .into_iter()
+        .chain(user_dir)
+        .flat_map(|dir| LOGO_EXTENSIONS.map(|ext| dir.join(format!("{}.{}", name, ext))))
+        .find(|path| path.is_file())
+}
+
+/// Renders a PNG (or any format supported by `image`) or SVG logo file
+fn render_file(path: &Path, height: u32) -> Result<RgbaImage, PhotoBorderError> {
+    let is_svg = path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("svg"))
+        .unwrap_or(false);
+
+    if is_svg {
+        return render_svg(path, height);
+    }
+
+    let logo = image::open(path)?.to_rgba8();
+    let width = scaled_width(logo.width(), logo.height(), height);
+    Ok(imageops::resize(&logo, width, height, imageops::FilterType::Lanczos3))
+}
+
+/// Rasterizes an SVG logo at the given height
+fn render_svg(path: &Path, height: u32) -> Result<RgbaImage, PhotoBorderError> {
+    let invalid = |e: String| PhotoBorderError::ConfigError(format!("Invalid SVG logo {}: {}", path.display(), e));
+
+    let data = fs::read(path)?;
+    let tree = usvg::Tree::from_data(&data, &usvg::Options::default())
+        .map_err(|e| invalid(e.to_string()))?;
+
+    let size = tree.size();
+    let scale = height as f32 / size.height();
+    let width = (size.width() * scale).ceil().max(1.0) as u32;
+    let mut pixmap = tiny_skia::Pixmap::new(width, height)
+        .ok_or_else(|| invalid("empty image".to_string()))?;
+    resvg::render(&tree, tiny_skia::Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+
+    // The pixmap stores premultiplied alpha
+    let pixels = pixmap.pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+
+    RgbaImage::from_raw(width, height, pixels)
+        .ok_or_else(|| invalid("unexpected pixel buffer size".to_string()))
+}
+
+/// Renders the brand name in capitals, with capitals `height` pixels high
+fn render_wordmark(brand: &str, height: u32, color: Rgb<u8>) -> RgbaImage {
+    let font = Font::try_from_bytes(WORDMARK_FONT).expect("embedded wordmark font is valid");
+    let text = brand.to_uppercase();
+
+    // Measure the capital height at a reference size to find the scale
+    // giving capitals of the requested height
+    let reference = 100.0;
+    let cap_height = font.glyph('H')
+        .scaled(Scale::uniform(reference))
+        .exact_bounding_box()
+        .map(|bounds| bounds.height())
+        .filter(|h| *h > 0.0)
+        .unwrap_or(reference * 0.73);
+    let scale = Scale::uniform(height as f32 * reference / cap_height);
+    let ascent = font.v_metrics(scale).ascent;
+
+    let glyphs: Vec<_> = font.layout(&text, scale, point(0.0, ascent)).collect();
+    let Some((min_x, min_y, max_x, max_y)) = glyphs.iter()
+        .filter_map(|glyph| glyph.pixel_bounding_box())
+        .map(|b| (b.min.x, b.min.y, b.max.x, b.max.y))
+        .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+    else {
+        return RgbaImage::new(1, height.max(1));
+    };
+
+    // Glyph coverage becomes the alpha channel of the text colour
+    let mut logo = RgbaImage::from_pixel(
+        (max_x - min_x) as u32,
+        (max_y - min_y) as u32,
+        Rgba([color[0], color[1], color[2], 0]),
+    );
+    for glyph in &glyphs {
+        let Some(bounds) = glyph.pixel_bounding_box() else {
+            continue;
+        };
+        glyph.draw(|x, y, coverage| {
+            let x = (bounds.min.x - min_x) as u32 + x;
+            let y = (bounds.min.y - min_y) as u32 + y;
+            if let Some(pixel) = logo.get_pixel_mut_checked(x, y) {
+                let alpha = (coverage * 255.0).round() as u8;
+                pixel[3] = pixel[3].max(alpha);
+            }
+        });
+    }
+
+    logo
+}
+
+/// Turns a logo into a single-colour silhouette
+///
+/// Logos with transparency keep their shape (alpha channel); fully
+/// opaque logos are assumed to be dark artwork on a light background,
+/// so darkness is used as coverage.
+fn tint(logo: &RgbaImage, color: Rgb<u8>) -> RgbaImage {
+    let opaque = logo.pixels().all(|pixel| pixel[3] == 255);
+
+    RgbaImage::from_fn(logo.width(), logo.height(), |x, y| {
+        let pixel = logo.get_pixel(x, y);
+        let coverage = if opaque {
+            let luma = 0.299 * pixel[0] as f32 + 0.587 * pixel[1] as f32 + 0.114 * pixel[2] as f32;
+            255 - luma.round() as u8
+        } else {
+            pixel[3]
+        };
+        Rgba([color[0], color[1], color[2], coverage])
+    })
+}
+
+/// Width keeping the aspect ratio when scaling to `height`
+fn scaled_width(width: u32, original_height: u32, height: u32) -> u32 {
+    ((width as f64 * height as f64 / original_height.max(1) as f64).round() as u32).max(1)
+}
@@ -7,8 +7,20 @@
  * Key Features:
  * - Add borders of different sizes (small, medium, large)
  * - Extract and display EXIF data (camera, lens, settings)
+ * - Camera brand logo next to the EXIF text
  * - Batch processing of multiple images
- * - Support for JPEG and PNG formats
+ * - Support for JPEG, PNG, TIFF and WebP formats (HEIC and AVIF with the `heif` feature)
+ * - Camera raw files (CR3, NEF, ARW, RAF, DNG) through their embedded preview
+ * - ICC colour profile preservation or conversion to sRGB
+ * - 16-bit and transparent images kept as such in PNG and TIFF output
+ * - Print mode with paper sizes and print resolution
+ * - Multi-page PDF output with an optional cover page
+ * - Diptychs and triptychs in a shared border
+ * - Panorama carousels split into seamless 4:5 or 1:1 tiles
+ * - Border fills: blurred photo, gradients, textures and background images,
+ *   with adaptive text colour
+ * - Soft drop shadow and keyline around the photo for a floating print look
+ * - Contact sheets for client selection and archive indexing
  * - GUI and CLI interfaces
  *
  * Author: Nicolas M.
@@ -18,20 +30,55 @@
 // Hide console window in GUI mode on Windows
 #![cfg_attr(target_os = "windows", windows_subsystem = "windows")]
 
-use clap::{Arg, Command};
-use image::{ImageBuffer, Rgb, RgbImage};
+use clap::{Arg, ArgMatches, Command};
+use image::{DynamicImage, Rgb, Rgba, RgbaImage};
 use imageproc::drawing::{draw_text_mut};
-use rusttype::{Font, Scale};
+use rusttype::{point, Font, Scale};
 use std::fs;
-use std::path::Path;
-use exif::{In, Tag, Reader};
+use std::path::{Path, PathBuf};
+use exif::{In, Tag};
 use std::error::Error;
 use std::fmt;
 use std::io;
-use img_parts::{jpeg::Jpeg, Bytes, ImageEXIF};
-
+use std::str::FromStr;
+use serde::Serialize;
+
+mod camera_names;
+mod canvas;
+mod carousel;
+mod color_profile;
+mod compose;
+mod contact_sheet;
+mod effects;
+mod exif_format;
+mod fill;
+mod formats;
 mod gui;
+mod inspect;
+mod lens;
+mod logo;
+mod makernote;
+mod output_metadata;
+mod overrides;
+mod pdf;
+mod print;
+mod raw;
+mod settings;
+mod xmp;
+use camera_names::CameraNames;
+use canvas::{AlphaMode, Canvas, Sample};
+use carousel::CarouselOptions;
+use effects::{ShadowOptions, StrokeOptions};
+use compose::{CaptionMode, ComposeOptions, Layout};
+use exif_format::ExifStyle;
+use fill::BorderFill;
+use formats::{OutputFormat, OutputOptions};
 use gui::GuiApp;
+use lens::LensTable;
+use logo::{LogoOptions, LogoPosition};
+use output_metadata::MetadataPolicy;
+use pdf::{FontStyle, PdfDocument, PdfOptions};
+use print::PrintOptions;
 
 // ============================================================================
 // ERROR HANDLING
@@ -51,6 +98,8 @@ pub enum PhotoBorderError {
     ExifError(exif::Error),
     /// Font-related errors
     FontError(String),
+    /// Invalid user settings (border type, sizes, colours, etc.)
+    ConfigError(String),
 }
 
 /// Implementation of formatted error display
@@ -64,6 +113,7 @@ impl fmt::Display for PhotoBorderError {
             PhotoBorderError::IoError(e) => write!(f, "Input/output error: {}", e),
             PhotoBorderError::ExifError(e) => write!(f, "EXIF reading error: {}", e),
             PhotoBorderError::FontError(e) => write!(f, "Font error: {}", e),
+            PhotoBorderError::ConfigError(e) => write!(f, "Configuration error: {}", e),
         }
     }
 }
@@ -94,6 +144,38 @@ impl From<exif::Error> for PhotoBorderError {
 // BORDER TYPES
 // ============================================================================
 
+/// Unit in which custom border sizes are expressed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SizeUnit {
+    /// Absolute thickness in pixels
+    Pixels,
+    /// Percentage of the smallest image dimension
+    Percent,
+}
+
+impl FromStr for SizeUnit {
+    type Err = &'static str;
+
+    /// Parses a size unit ("px"/"pixels" or "%"/"percent")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "px" | "pixels" => Ok(SizeUnit::Pixels),
+            "%" | "percent" => Ok(SizeUnit::Percent),
+            _ => Err("Invalid size unit"),
+        }
+    }
+}
+
+impl SizeUnit {
+    /// Converts a size expressed in this unit to pixels
+    fn to_pixels(self, value: f32, min_dimension: u32) -> u32 {
+        match self {
+            SizeUnit::Pixels => value.max(0.0).round() as u32,
+            SizeUnit::Percent => (min_dimension as f32 * value.max(0.0) / 100.0).round() as u32,
+        }
+    }
+}
+
 /// Enumeration of different available border types
 ///
 /// Each type corresponds to a different border size, calculated
@@ -106,9 +188,19 @@ pub enum BorderType {
     Medium,
     /// Large border - for a pronounced artistic effect
     Large,
+    /// User-defined thickness for each side
+    Custom {
+        top: f32,
+        right: f32,
+        bottom: f32,
+        left: f32,
+        unit: SizeUnit,
+    },
 }
 
-impl BorderType {
+impl FromStr for BorderType {
+    type Err = &'static str;
+
     /// Parses a string to determine the border type
     ///
     /// # Arguments
@@ -117,7 +209,7 @@ impl BorderType {
     /// # Returns
     /// * `Ok(BorderType)` if the string is recognized
     /// * `Err(&'static str)` if the string is not valid
-    pub fn from_str(s: &str) -> Result<Self, &'static str> {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
             "s" | "small" => Ok(BorderType::Small),
             "m" | "medium" => Ok(BorderType::Medium),
@@ -125,6 +217,23 @@ impl BorderType {
             _ => Err("Invalid border type"),
         }
     }
+}
+
+impl BorderType {
+    /// Builds a custom border from a CSS-like size list
+    ///
+    /// # Arguments
+    /// * `spec` - Comma-separated sizes: "all", "vertical,horizontal"
+    ///   or "top,right,bottom,left"
+    /// * `unit` - Unit in which the sizes are expressed
+    ///
+    /// # Returns
+    /// * `Ok(BorderType::Custom)` if the list is valid
+    /// * `Err(&'static str)` if a value is not a positive number
+    pub fn parse_custom(spec: &str, unit: SizeUnit) -> Result<Self, &'static str> {
+        let (top, right, bottom, left) = parse_sides(spec)?;
+        Ok(BorderType::Custom { top, right, bottom, left, unit })
+    }
 
     /// Calculates border dimensions based on type and image size
     ///
@@ -139,6 +248,7 @@ impl BorderType {
     /// - Small: Thin border only at bottom (polaroid style)
     /// - Medium: Uniform border representing 1/15 of the smallest dimension
     /// - Large: Uniform border representing 1/10 of the smallest dimension
+    /// - Custom: Each side converted from its unit (pixels or percentage)
     fn get_border_size(&self, img_width: u32, img_height: u32) -> (u32, u32, u32, u32) {
         // Uses the smallest dimension to maintain harmonious proportions
         let min_dimension = img_width.min(img_height);
@@ -160,10 +270,93 @@ impl BorderType {
                 let border = min_dimension / 10;
                 (border, border, border, border)
             },
+            BorderType::Custom { top, right, bottom, left, unit } => (
+                unit.to_pixels(*top, min_dimension),
+                unit.to_pixels(*right, min_dimension),
+                unit.to_pixels(*bottom, min_dimension),
+                unit.to_pixels(*left, min_dimension),
+            ),
         }
     }
 }
 
+/// Parses a CSS-like list of side sizes
+///
+/// # Arguments
+/// * `spec` - Comma-separated sizes: "all", "vertical,horizontal"
+///   or "top,right,bottom,left"
+///
+/// # Returns
+/// * `Ok((top, right, bottom, left))` if the list is valid
+/// * `Err(&'static str)` if a value is not a positive number
+pub fn parse_sides(spec: &str) -> Result<(f32, f32, f32, f32), &'static str> {
+    let values = spec
+        .split(',')
+        .map(|v| v.trim().parse::<f32>())
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|_| "Invalid border size")?;
+
+    if values.iter().any(|v| *v < 0.0 || !v.is_finite()) {
+        return Err("Border sizes must be positive numbers");
+    }
+
+    match values.as_slice() {
+        [all] => Ok((*all, *all, *all, *all)),
+        [vertical, horizontal] => Ok((*vertical, *horizontal, *vertical, *horizontal)),
+        [top, right, bottom, left] => Ok((*top, *right, *bottom, *left)),
+        _ => Err("Border size expects 1, 2 or 4 values"),
+    }
+}
+
+// ============================================================================
+// COLORS
+// ============================================================================
+
+/// Default border colour: white gives a professional and timeless appearance
+pub const DEFAULT_BORDER_COLOR: Rgb<u8> = Rgb([255, 255, 255]);
+
+/// Default text colour: dark gray for good readability on a white border
+pub const DEFAULT_TEXT_COLOR: Rgb<u8> = Rgb([64, 64, 64]);
+
+/// Default end colour of the gradient fills: light gray, for a subtle
+/// gradient from the white border
+pub const DEFAULT_FILL_COLOR: Rgb<u8> = Rgb([192, 192, 192]);
+
+/// Parses a hexadecimal colour
+///
+/// # Arguments
+/// * `s` - Colour in "#rrggbb", "rrggbb", "#rgb" or "rgb" notation
+///
+/// # Returns
+/// * `Ok(Rgb<u8>)` if the colour is valid
+/// * `Err(PhotoBorderError::ConfigError)` otherwise
+pub fn parse_color(s: &str) -> Result<Rgb<u8>, PhotoBorderError> {
+    let hex = s.trim().trim_start_matches('#');
+    let invalid = || PhotoBorderError::ConfigError(format!("Invalid colour '{}'", s));
+
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+
+    let channel = |digits: &str| u8::from_str_radix(digits, 16).map_err(|_| invalid());
+
+    match hex.len() {
+        6 => Ok(Rgb([channel(&hex[0..2])?, channel(&hex[2..4])?, channel(&hex[4..6])?])),
+        // Short notation: each digit is doubled ("#abc" -> "#aabbcc")
+        3 => Ok(Rgb([
+            channel(&hex[0..1])? * 17,
+            channel(&hex[1..2])? * 17,
+            channel(&hex[2..3])? * 17,
+        ])),
+        _ => Err(invalid()),
+    }
+}
+
+/// Formats a colour as "#rrggbb", the inverse of [`parse_color`]
+pub fn format_color(color: Rgb<u8>) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
 // ============================================================================
 // EXIF DATA
 // ============================================================================
@@ -172,10 +365,14 @@ impl BorderType {
 ///
 /// This structure stores all important technical information
 /// from a photograph that can be displayed on the border.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize)]
 pub struct ExifData {
-    /// Camera model used
+    /// Camera display name (brand and marketing model name)
     pub camera: Option<String>,
+    /// Camera brand, normalized ("Nikon" for "NIKON CORPORATION")
+    pub make: Option<String>,
+    /// Camera model as written in the file
+    pub model: Option<String>,
     /// Lens model used
     pub lens: Option<String>,
     /// Focal length in millimeters
@@ -188,6 +385,147 @@ pub struct ExifData {
     pub iso: Option<String>,
     /// Date taken
     pub date_taken: Option<String>,
+    /// Exposure compensation in EV
+    pub exposure_compensation: Option<String>,
+    /// 35mm-equivalent focal length
+    pub focal_length_35mm: Option<String>,
+    /// Exposure program (manual, aperture priority, etc.)
+    pub exposure_program: Option<String>,
+    /// Exposure mode (auto, manual, bracketing)
+    pub exposure_mode: Option<String>,
+    /// Metering mode
+    pub metering_mode: Option<String>,
+    /// Flash status
+    pub flash: Option<String>,
+    /// White balance mode
+    pub white_balance: Option<String>,
+    /// Lens manufacturer
+    pub lens_make: Option<String>,
+    /// Camera body serial number
+    pub body_serial: Option<String>,
+    /// Photographer name
+    pub artist: Option<String>,
+    /// Copyright notice
+    pub copyright: Option<String>,
+    /// Image description
+    pub image_description: Option<String>,
+    /// Picture title (XMP/IPTC)
+    pub title: Option<String>,
+    /// Caption written in the photo manager (XMP/IPTC)
+    pub caption: Option<String>,
+    /// Keywords separated by commas (XMP/IPTC)
+    pub keywords: Option<String>,
+    /// Star rating as stars, e.g. "★★★" (XMP)
+    pub rating: Option<String>,
+    /// Location, city, state and country separated by commas (XMP/IPTC)
+    pub location: Option<String>,
+    /// GPS latitude in decimal degrees (negative for south)
+    pub gps_latitude: Option<String>,
+    /// GPS longitude in decimal degrees (negative for west)
+    pub gps_longitude: Option<String>,
+    /// GPS altitude in meters (negative below sea level)
+    pub gps_altitude: Option<String>,
+}
+
+/// Settings used when extracting EXIF data
+#[derive(Debug, Clone)]
+pub struct ExifOptions {
+    /// Notation used for EXIF values
+    pub style: ExifStyle,
+    /// Table used to normalize camera names
+    pub camera_names: CameraNames,
+    /// Table used to infer the lens when `LensModel` is missing
+    pub lenses: LensTable,
+    /// Lens name replacing whatever is found in the file
+    pub lens_override: Option<String>,
+    /// Field values replacing the EXIF and sidecar values (`--set`)
+    pub overrides: Vec<(String, String)>,
+    /// Whether sidecar files (`photo.jpg.json`) are read
+    pub sidecars: bool,
+}
+
+impl Default for ExifOptions {
+    fn default() -> Self {
+        ExifOptions {
+            style: ExifStyle::default(),
+            camera_names: CameraNames::builtin(),
+            lenses: LensTable::builtin(),
+            lens_override: None,
+            overrides: Vec::new(),
+            sidecars: true,
+        }
+    }
+}
+
+impl ExifOptions {
+    /// Options with the built-in tables extended by the user's tables
+    ///
+    /// # Arguments
+    /// * `camera_names` - Optional extra camera name table
+    /// * `lenses` - Optional extra lens table
+    pub fn load(camera_names: Option<&Path>, lenses: Option<&Path>) -> Result<Self, PhotoBorderError> {
+        Ok(ExifOptions {
+            camera_names: CameraNames::load(camera_names)?,
+            lenses: LensTable::load(lenses)?,
+            ..ExifOptions::default()
+        })
+    }
+
+    /// Command-line arguments of the EXIF options, shared by the commands
+    /// rendering or printing EXIF fields
+    pub fn args() -> Vec<Arg> {
+        vec![
+            Arg::new("exif_style")
+                .long("exif-style")
+                .help("Notation of EXIF values: standard (50 mm, 1/250 s, 2.5″) or compact (50mm, 1/250s, 2.5s)")
+                .value_name("STYLE")
+                .default_value("standard"),
+            Arg::new("camera_names")
+                .long("camera-names")
+                .help("TOML table of extra camera make/model names (same format as data/cameras.toml)")
+                .value_name("FILE"),
+            Arg::new("lens")
+                .long("lens")
+                .help("Lens name to print, replacing the one found in the EXIF data")
+                .value_name("NAME"),
+            Arg::new("lens_table")
+                .long("lens-table")
+                .help("TOML table of lens names by maker note ID or focal range (same format as data/lenses.toml)")
+                .value_name("FILE"),
+            Arg::new("set")
+                .long("set")
+                .help("Fill in or replace an EXIF field, e.g. --set camera=\"Leica M6\" --set iso=400 (repeatable)")
+                .value_name("FIELD=VALUE")
+                .action(clap::ArgAction::Append),
+            Arg::new("no_sidecar")
+                .long("no-sidecar")
+                .help("Ignore photo.jpg.json / photo.jpg.yaml sidecar files")
+                .action(clap::ArgAction::SetTrue),
+        ]
+    }
+
+    /// Builds the options from the arguments of [`Self::args`]
+    ///
+    /// # Returns
+    /// * `Ok(ExifOptions)` with the user's name tables loaded
+    /// * `Err(PhotoBorderError)` if the style, an override or a table is invalid
+    pub fn from_matches(matches: &ArgMatches) -> Result<Self, PhotoBorderError> {
+        Ok(ExifOptions {
+            style: ExifStyle::from_str(matches.get_one::<String>("exif_style").unwrap())
+                .map_err(|e| PhotoBorderError::ConfigError(e.to_string()))?,
+            lens_override: matches.get_one::<String>("lens").cloned(),
+            overrides: matches.get_many::<String>("set")
+                .into_iter()
+                .flatten()
+                .map(|assignment| overrides::parse_assignment(assignment))
+                .collect::<Result<_, _>>()?,
+            sidecars: !matches.get_flag("no_sidecar"),
+            ..ExifOptions::load(
+                matches.get_one::<String>("camera_names").map(Path::new),
+                matches.get_one::<String>("lens_table").map(Path::new),
+            )?
+        })
+    }
 }
 
 impl ExifData {
@@ -202,57 +540,428 @@ impl ExifData {
     ///
     /// # Functionality
     /// Uses the `exif` crate to read the EXIF container and extracts
-    /// standard photographic metadata fields.
+    /// standard photographic metadata fields, using the default
+    /// [`ExifOptions`] (standard style, built-in camera names).
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, PhotoBorderError> {
-        // Opening and preparing the file for EXIF reading
-        let file = fs::File::open(path)?;
-        let mut bufreader = std::io::BufReader::new(&file);
-        let exifreader = Reader::new();
-        let exif = exifreader.read_from_container(&mut bufreader)?;
+        Self::from_file_with(path, &ExifOptions::default())
+    }
+
+    /// Extracts EXIF data and applies the manual overrides
+    ///
+    /// # Arguments
+    /// * `path` - Path to the image file
+    /// * `options` - Extraction options, including the `--set` overrides
+    ///
+    /// # Returns
+    /// * `Ok(ExifData)` with the EXIF fields and XMP/IPTC descriptive
+    ///   fields, overridden by the sidecar file, then by `options.overrides`
+    ///   and finally by `options.lens_override`
+    /// * `Err(PhotoBorderError)` if the EXIF data cannot be read and there
+    ///   is nothing to replace it, or if the sidecar file is invalid
+    ///
+    /// # Functionality
+    /// Photos without EXIF data (scans, film) start from empty fields when
+    /// XMP/IPTC metadata, a sidecar file, overrides or `--lens` provide
+    /// values for them.
+    pub fn load<P: AsRef<Path>>(path: P, options: &ExifOptions) -> Result<Self, PhotoBorderError> {
+        let path = path.as_ref();
+        let sidecar = if options.sidecars { overrides::read_sidecar(path)? } else { None };
+        let descriptive = xmp::read_metadata(path);
+
+        let mut exif_data = match Self::from_file_with(path, options) {
+            Ok(exif_data) => exif_data,
+            Err(_) if sidecar.is_some()
+                || !options.overrides.is_empty()
+                || !descriptive.is_empty()
+                || options.lens_override.is_some() => {
+                ExifData::default()
+            }
+            Err(e) => return Err(e),
+        };
+
+        // Titles, captions, keywords, etc. written by the photo manager
+        exif_data.keywords = descriptive.keywords_text();
+        exif_data.rating = descriptive.rating_stars();
+        exif_data.location = descriptive.location();
+        exif_data.title = descriptive.title;
+        exif_data.caption = descriptive.caption;
+
+        // Sidecar values first, so that command-line values win
+        for (name, value) in sidecar.iter().flatten().chain(&options.overrides) {
+            exif_data.set(name, value, options.style)?;
+        }
+        if let Some(lens) = &options.lens_override {
+            exif_data.lens = Some(lens.clone());
+        }
+
+        Ok(exif_data)
+    }
+
+    /// Extracts EXIF data from an image file with custom formatting options
+    pub fn from_file_with<P: AsRef<Path>>(path: P, options: &ExifOptions) -> Result<Self, PhotoBorderError> {
+        let style = options.style;
+        // Reading the EXIF container (JPEG, PNG, TIFF, WebP, HEIF)
+        let exif = formats::read_exif(path.as_ref())?;
 
         let mut exif_data = ExifData::default();
 
         // Extract camera information
-        // Combines Make and Model to get the full name
-        if let Some(_make) = exif.get_field(Tag::Make, In::PRIMARY) {
-            if let Some(model) = exif.get_field(Tag::Model, In::PRIMARY) {
-                let model_str = model.display_value().to_string();
-                exif_data.camera = Some(format!("{}",
-                                                model_str.trim_matches('"')));
+        // Combines Make and Model into the marketing name ("Sony α7R IV")
+        let make = Self::text_field(&exif, Tag::Make);
+        exif_data.model = Self::text_field(&exif, Tag::Model);
+        exif_data.make = make.as_deref().map(|make| options.camera_names.brand(make));
+        exif_data.camera = exif_data.model.as_deref()
+            .map(|model| options.camera_names.display_name(make.as_deref(), model));
+
+        // Extract lens model, inferring it when the camera did not write
+        // LensModel (adapted lenses, older bodies)
+        exif_data.lens = Self::text_field(&exif, Tag::LensModel)
+            .or_else(|| lens::infer_lens(&exif, make.as_deref(), &options.lenses));
+
+        // Extract focal length ("50 mm" rather than "50.0mm")
+        exif_data.focal_length = Self::rational_field(&exif, Tag::FocalLength)
+            .map(|focal| style.focal_length(focal.to_f64()));
+
+        // Extract aperture ("f/2.8")
+        exif_data.aperture = Self::rational_field(&exif, Tag::FNumber)
+            .map(|aperture| style.aperture(aperture.to_f64()));
+
+        // Extract shutter speed as a reduced fraction or long exposure
+        exif_data.shutter_speed = Self::rational_field(&exif, Tag::ExposureTime)
+            .map(|shutter| style.shutter_speed(shutter));
+
+        // Extract ISO sensitivity
+        if let Some(iso) = exif.get_field(Tag::PhotographicSensitivity, In::PRIMARY) {
+            exif_data.iso = iso.value.get_uint(0).map(|iso| style.iso(iso));
+        }
+
+        // Extract date taken (available to caption templates only)
+        if let Some(date) = exif.get_field(Tag::DateTimeOriginal, In::PRIMARY) {
+            exif_data.date_taken = Some(date.display_value().to_string().trim_matches('"').to_string());
+        }
+
+        // Extract exposure compensation, rounded to 1/10 EV
+        if let Some(bias) = exif.get_field(Tag::ExposureBiasValue, In::PRIMARY) {
+            if let exif::Value::SRational(ref values) = bias.value {
+                if let Some(value) = values.first() {
+                    exif_data.exposure_compensation = Some(style.exposure_compensation(value.to_f64()));
+                }
             }
         }
 
-        // Extract lens model
-        if let Some(lens) = exif.get_field(Tag::LensModel, In::PRIMARY) {
-            exif_data.lens = Some(lens.display_value().to_string().trim_matches('"').to_string());
+        // Extract 35mm-equivalent focal length
+        if let Some(focal) = exif.get_field(Tag::FocalLengthIn35mmFilm, In::PRIMARY) {
+            exif_data.focal_length_35mm = focal.value.get_uint(0)
+                .map(|focal| style.focal_length(focal as f64));
         }
 
-        // Extract focal length with mm formatting
-        if let Some(focal) = exif.get_field(Tag::FocalLength, In::PRIMARY) {
-            exif_data.focal_length = Some(format!("{}mm", focal.display_value()));
+        // Extract enumerated shooting settings, using the descriptions
+        // provided by the exif crate (e.g. "aperture priority")
+        exif_data.exposure_program = Self::described_field(&exif, Tag::ExposureProgram);
+        exif_data.exposure_mode = Self::described_field(&exif, Tag::ExposureMode);
+        exif_data.metering_mode = Self::described_field(&exif, Tag::MeteringMode);
+        exif_data.flash = Self::described_field(&exif, Tag::Flash);
+        exif_data.white_balance = Self::described_field(&exif, Tag::WhiteBalance);
+
+        // Extract free-text fields
+        exif_data.lens_make = Self::text_field(&exif, Tag::LensMake);
+        exif_data.body_serial = Self::text_field(&exif, Tag::BodySerialNumber);
+        exif_data.artist = Self::text_field(&exif, Tag::Artist);
+        exif_data.copyright = Self::text_field(&exif, Tag::Copyright);
+        exif_data.image_description = Self::text_field(&exif, Tag::ImageDescription);
+
+        // Extract GPS position
+        exif_data.gps_latitude = Self::gps_coordinate(&exif, Tag::GPSLatitude, Tag::GPSLatitudeRef, "S")
+            .map(|lat| format!("{:.6}", lat));
+        exif_data.gps_longitude = Self::gps_coordinate(&exif, Tag::GPSLongitude, Tag::GPSLongitudeRef, "W")
+            .map(|lon| format!("{:.6}", lon));
+        if let Some(altitude) = Self::rational_field(&exif, Tag::GPSAltitude) {
+            // GPSAltitudeRef = 1 means below sea level
+            let below = exif.get_field(Tag::GPSAltitudeRef, In::PRIMARY)
+                .and_then(|r| r.value.get_uint(0))
+                == Some(1);
+            let meters = if below { -altitude.to_f64() } else { altitude.to_f64() };
+            exif_data.gps_altitude = Some(style.altitude(meters));
+        }
+
+        Ok(exif_data)
+    }
+
+    /// Reads an ASCII field, trimming quotes, padding and NUL bytes
+    ///
+    /// Returns `None` if the field is missing or blank.
+    fn text_field(exif: &exif::Exif, tag: Tag) -> Option<String> {
+        let field = exif.get_field(tag, In::PRIMARY)?;
+        let text = match field.value {
+            exif::Value::Ascii(ref values) => values.iter()
+                .map(|v| String::from_utf8_lossy(v).to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+            _ => field.display_value().to_string(),
+        };
+        let text = text.trim_matches(|c: char| c == '"' || c == '\0' || c.is_whitespace());
+
+        if text.is_empty() { None } else { Some(text.to_string()) }
+    }
+
+    /// Reads the first value of an unsigned rational field
+    fn rational_field(exif: &exif::Exif, tag: Tag) -> Option<exif::Rational> {
+        match exif.get_field(tag, In::PRIMARY)?.value {
+            exif::Value::Rational(ref values) => values.first().copied(),
+            _ => None,
+        }
+    }
+
+    /// Reads an enumerated field as its human-readable description
+    fn described_field(exif: &exif::Exif, tag: Tag) -> Option<String> {
+        exif.get_field(tag, In::PRIMARY)
+            .map(|field| field.display_value().to_string())
+    }
+
+    /// Converts a GPS degrees/minutes/seconds triple to decimal degrees
+    ///
+    /// # Arguments
+    /// * `exif` - Parsed EXIF container
+    /// * `tag` - Coordinate tag (GPSLatitude or GPSLongitude)
+    /// * `ref_tag` - Hemisphere tag (GPSLatitudeRef or GPSLongitudeRef)
+    /// * `negative_ref` - Hemisphere giving a negative value ("S" or "W")
+    fn gps_coordinate(exif: &exif::Exif, tag: Tag, ref_tag: Tag, negative_ref: &str) -> Option<f64> {
+        let field = exif.get_field(tag, In::PRIMARY)?;
+        let dms = match field.value {
+            exif::Value::Rational(ref values) if values.len() >= 3 => values,
+            _ => return None,
+        };
+
+        let degrees = dms[0].to_f64() + dms[1].to_f64() / 60.0 + dms[2].to_f64() / 3600.0;
+        let negative = Self::text_field(exif, ref_tag)
+            .map(|r| r.eq_ignore_ascii_case(negative_ref))
+            .unwrap_or(false);
+
+        Some(if negative { -degrees } else { degrees })
+    }
+
+    /// Lists every field with its name, in display order
+    ///
+    /// # Returns
+    /// Vector of (name, value) pairs, `None` for fields missing in the file
+    pub fn fields(&self) -> Vec<(&'static str, Option<&str>)> {
+        vec![
+            ("camera", self.camera.as_deref()),
+            ("make", self.make.as_deref()),
+            ("model", self.model.as_deref()),
+            ("lens", self.lens.as_deref()),
+            ("focal_length", self.focal_length.as_deref()),
+            ("aperture", self.aperture.as_deref()),
+            ("shutter_speed", self.shutter_speed.as_deref()),
+            ("iso", self.iso.as_deref()),
+            ("date_taken", self.date_taken.as_deref()),
+            ("exposure_compensation", self.exposure_compensation.as_deref()),
+            ("focal_length_35mm", self.focal_length_35mm.as_deref()),
+            ("exposure_program", self.exposure_program.as_deref()),
+            ("exposure_mode", self.exposure_mode.as_deref()),
+            ("metering_mode", self.metering_mode.as_deref()),
+            ("flash", self.flash.as_deref()),
+            ("white_balance", self.white_balance.as_deref()),
+            ("lens_make", self.lens_make.as_deref()),
+            ("body_serial", self.body_serial.as_deref()),
+            ("artist", self.artist.as_deref()),
+            ("copyright", self.copyright.as_deref()),
+            ("image_description", self.image_description.as_deref()),
+            ("title", self.title.as_deref()),
+            ("caption", self.caption.as_deref()),
+            ("keywords", self.keywords.as_deref()),
+            ("rating", self.rating.as_deref()),
+            ("location", self.location.as_deref()),
+            ("gps_latitude", self.gps_latitude.as_deref()),
+            ("gps_longitude", self.gps_longitude.as_deref()),
+            ("gps_altitude", self.gps_altitude.as_deref()),
+        ]
+    }
+
+    /// Returns the value of a field by name (see [`ExifData::fields`])
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields()
+            .into_iter()
+            .find(|(field, _)| *field == name)
+            .and_then(|(_, value)| value)
+    }
+
+    /// Whether `name` is a field name (see [`ExifData::fields`])
+    pub fn is_field(name: &str) -> bool {
+        ExifData::default().fields().iter().any(|(field, _)| *field == name)
+    }
+
+    /// Sets a field by name (see [`ExifData::fields`])
+    ///
+    /// # Arguments
+    /// * `name` - Field name
+    /// * `value` - New value; an empty value clears the field
+    /// * `style` - Notation applied to plain numbers, so that "400" for
+    ///   `iso` reads "ISO 400" and "1/250" for `shutter_speed` "1/250 s"
+    ///
+    /// # Returns
+    /// * `Err(PhotoBorderError::ConfigError)` if the field name is unknown
+    pub fn set(&mut self, name: &str, value: &str, style: ExifStyle) -> Result<(), PhotoBorderError> {
+        let value = value.trim();
+        let value = if value.is_empty() { None } else { Some(Self::format_manual_value(name, value, style)) };
+
+        let field = match name {
+            "camera" => &mut self.camera,
+            "make" => &mut self.make,
+            "model" => &mut self.model,
+            "lens" => &mut self.lens,
+            "focal_length" => &mut self.focal_length,
+            "aperture" => &mut self.aperture,
+            "shutter_speed" => &mut self.shutter_speed,
+            "iso" => &mut self.iso,
+            "date_taken" => &mut self.date_taken,
+            "exposure_compensation" => &mut self.exposure_compensation,
+            "focal_length_35mm" => &mut self.focal_length_35mm,
+            "exposure_program" => &mut self.exposure_program,
+            "exposure_mode" => &mut self.exposure_mode,
+            "metering_mode" => &mut self.metering_mode,
+            "flash" => &mut self.flash,
+            "white_balance" => &mut self.white_balance,
+            "lens_make" => &mut self.lens_make,
+            "body_serial" => &mut self.body_serial,
+            "artist" => &mut self.artist,
+            "copyright" => &mut self.copyright,
+            "image_description" => &mut self.image_description,
+            "title" => &mut self.title,
+            "caption" => &mut self.caption,
+            "keywords" => &mut self.keywords,
+            "rating" => &mut self.rating,
+            "location" => &mut self.location,
+            "gps_latitude" => &mut self.gps_latitude,
+            "gps_longitude" => &mut self.gps_longitude,
+            "gps_altitude" => &mut self.gps_altitude,
+            _ => return Err(PhotoBorderError::ConfigError(format!("Unknown field '{}'", name))),
+        };
+        *field = value;
+
+        Ok(())
+    }
+
+    /// Formats a manually entered value like the extracted ones
+    ///
+    /// Only plain numbers (and fractions for the shutter speed) are
+    /// formatted; anything else is kept as typed.
+    fn format_manual_value(name: &str, value: &str, style: ExifStyle) -> String {
+        let number = value.parse::<f64>().ok().filter(|v| v.is_finite());
+
+        match (name, number) {
+            ("focal_length" | "focal_length_35mm", Some(focal)) => style.focal_length(focal),
+            ("aperture", Some(aperture)) => style.aperture(aperture),
+            ("iso", Some(iso)) if iso >= 0.0 => style.iso(iso.round() as u32),
+            ("exposure_compensation", Some(ev)) => style.exposure_compensation(ev),
+            ("gps_altitude", Some(meters)) => style.altitude(meters),
+            ("rating", Some(stars)) if (1.0..=5.0).contains(&stars) => "★".repeat(stars.round() as usize),
+            ("shutter_speed", _) => Self::parse_exposure_time(value)
+                .map(|time| style.shutter_speed(time))
+                .unwrap_or_else(|| value.to_string()),
+            _ => value.to_string(),
         }
+    }
 
-        // Extract aperture with f/ formatting
-        if let Some(aperture) = exif.get_field(Tag::FNumber, In::PRIMARY) {
-            exif_data.aperture = Some(format!("f/{}", aperture.display_value()));
+    /// Parses an exposure time written as a fraction ("1/250") or in seconds ("2.5")
+    fn parse_exposure_time(value: &str) -> Option<exif::Rational> {
+        match value.split_once('/') {
+            Some((num, denom)) => Some(exif::Rational {
+                num: num.trim().parse().ok()?,
+                denom: denom.trim().parse().ok()?,
+            }),
+            None => {
+                let seconds = value.parse::<f64>().ok().filter(|s| s.is_finite() && *s > 0.0)?;
+                Some(exif::Rational { num: (seconds * 1000.0).round() as u32, denom: 1000 })
+            }
         }
+    }
 
-        // Extract shutter speed with seconds formatting
-        if let Some(shutter) = exif.get_field(Tag::ExposureTime, In::PRIMARY) {
-            exif_data.shutter_speed = Some(format!("{}s", shutter.display_value()));
+    /// Checks that every placeholder of a caption template is a known field
+    ///
+    /// # Returns
+    /// * `Ok(())` if the template is valid
+    /// * `Err(PhotoBorderError::ConfigError)` naming the first unknown field
+    pub fn check_template(template: &str) -> Result<(), PhotoBorderError> {
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..].find('}').ok_or_else(|| {
+                PhotoBorderError::ConfigError("Unclosed '{' in caption template".to_string())
+            })?;
+            let name = &rest[start + 1..start + end];
+            if !Self::is_field(name) {
+                return Err(PhotoBorderError::ConfigError(format!("Unknown caption field '{{{}}}'", name)));
+            }
+            rest = &rest[start + end + 1..];
         }
 
-        // Extract ISO sensitivity
-        if let Some(iso) = exif.get_field(Tag::PhotographicSensitivity, In::PRIMARY) {
-            exif_data.iso = Some(format!("ISO {}", iso.display_value()));
+        Ok(())
+    }
+
+    /// Renders a caption template
+    ///
+    /// # Arguments
+    /// * `template` - Text with `{field}` placeholders, e.g.
+    ///   `"{camera} | {focal_length} {aperture}[ | {artist}]"`
+    ///
+    /// # Returns
+    /// The rendered caption
+    ///
+    /// # Template Syntax
+    /// - `{field}` is replaced by the field value, or nothing if missing
+    /// - `[...]` is an optional section, dropped entirely if any field
+    ///   inside it is missing (useful for separators)
+    pub fn render_template(&self, template: &str) -> String {
+        let mut output = String::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find('[') {
+            output.push_str(&self.render_section(&rest[..start], false).unwrap_or_default());
+
+            match rest[start..].find(']') {
+                Some(end) => {
+                    let section = &rest[start + 1..start + end];
+                    if let Some(rendered) = self.render_section(section, true) {
+                        output.push_str(&rendered);
+                    }
+                    rest = &rest[start + end + 1..];
+                }
+                None => {
+                    // Unbalanced bracket: keep it as literal text
+                    output.push('[');
+                    rest = &rest[start + 1..];
+                }
+            }
         }
+        output.push_str(&self.render_section(rest, false).unwrap_or_default());
 
-        // Note: Date taken commented out - can be enabled if needed
-        //if let Some(date) = exif.get_field(Tag::DateTimeOriginal, In::PRIMARY) {
-        //    exif_data.date_taken = Some(date.display_value().to_string().trim_matches('"').to_string());
-        //}
+        output.trim().to_string()
+    }
 
-        Ok(exif_data)
+    /// Replaces the placeholders of a template section
+    ///
+    /// Returns `None` if `required` is set and a field is missing.
+    fn render_section(&self, section: &str, required: bool) -> Option<String> {
+        let mut output = String::new();
+        let mut rest = section;
+
+        while let Some(start) = rest.find('{') {
+            output.push_str(&rest[..start]);
+            let Some(end) = rest[start..].find('}') else {
+                break;
+            };
+
+            match self.get(&rest[start + 1..start + end]) {
+                Some(value) => output.push_str(value),
+                None if required => return None,
+                None => {}
+            }
+            rest = &rest[start + end + 1..];
+        }
+        output.push_str(rest);
+
+        Some(output)
     }
 
     /// Formats EXIF data for display on the image
@@ -264,7 +973,9 @@ impl ExifData {
     /// 1. Camera on the first line
     /// 2. Lens on the second line
     /// 3. Technical settings (focal, aperture, speed, ISO) separated by bullets
-    /// 4. Date taken last
+    ///
+    /// Other fields (date, GPS, copyright, title, etc.) are only shown through
+    /// a caption template (see [`ExifData::render_template`]).
     pub fn format_for_display(&self) -> Vec<String> {
         let mut lines = Vec::new();
 
@@ -298,11 +1009,6 @@ impl ExifData {
             lines.push(settings.join(" • "));
         }
 
-        // Add date if available
-        if let Some(date) = &self.date_taken {
-            lines.push(date.clone());
-        }
-
         lines
     }
 }
@@ -323,6 +1029,89 @@ pub struct PhotoBorder {
     show_exif: bool,
     /// Font data loaded in memory
     font_data: Option<Vec<u8>>,
+    /// Colour used to fill the border area
+    border_color: Rgb<u8>,
+    /// Content of the border area: the border colour, a blurred copy of the
+    /// photo, a gradient, a texture or an image
+    fill: BorderFill,
+    /// Colour used to draw the EXIF text
+    text_color: Rgb<u8>,
+    /// Optional caption template replacing the default EXIF line
+    caption_template: Option<String>,
+    /// Options used when reading EXIF data (value style, camera names)
+    exif_options: ExifOptions,
+    /// Brand logo settings, `None` to draw no logo
+    logo: Option<LogoOptions>,
+    /// Converts photos to sRGB instead of keeping their colour profile
+    to_srgb: bool,
+    /// EXIF data copied to the output files
+    metadata_policy: MetadataPolicy,
+    /// Output format and encoder settings
+    output_options: OutputOptions,
+    /// Handling of the alpha channel of transparent photos
+    alpha_mode: AlphaMode,
+    /// Paper size and resolution, `None` outside print mode
+    print: Option<PrintOptions>,
+    /// Diptych and triptych settings, `None` to frame each photo alone
+    composition: Option<ComposeOptions>,
+    /// Carousel tile settings, `None` to save each photo as one image
+    carousel: Option<CarouselOptions>,
+    /// Drop shadow cast by the photo onto the border, `None` for no shadow
+    shadow: Option<ShadowOptions>,
+    /// Keyline around the photo, `None` for no keyline
+    stroke: Option<StrokeOptions>,
+}
+
+/// Photo ready to be framed
+struct Frame {
+    /// Photo, scaled to the paper size in print mode
+    photo: DynamicImage,
+    /// Border thickness (top, right, bottom, left)
+    borders: (u32, u32, u32, u32),
+    /// Colour profile of the photo, `None` for sRGB
+    icc_profile: Option<Vec<u8>>,
+    /// Original photos: the whole photo, or each photo of a diptych or triptych
+    panels: Vec<Panel>,
+}
+
+/// Original photo in a frame
+struct Panel {
+    /// Position and size in the framed photo (x, y, width, height)
+    rect: (u32, u32, u32, u32),
+    /// EXIF data for the text and the logo, if needed and available
+    exif_data: Option<ExifData>,
+}
+
+/// Brand logo positioned in the bottom border
+struct PlacedLogo {
+    /// Logo, tinted with the text colour unless its colours are kept
+    image: RgbaImage,
+    /// Position of the top-left corner in the bordered image
+    x: u32,
+    y: u32,
+    /// Horizontal space taken before the EXIF text
+    text_shift: u32,
+}
+
+/// Shortens a line of text to a width in pixels, ending it with an ellipsis
+pub fn fit_text(font: &Font, text: &str, scale: f32, max_width: u32) -> String {
+    let width = |text: &str| {
+        font.layout(text, Scale::uniform(scale), point(0.0, 0.0))
+            .last()
+            .map_or(0.0, |glyph| glyph.position().x + glyph.unpositioned().h_metrics().advance_width)
+    };
+    if width(text) <= max_width as f32 {
+        return text.to_string();
+    }
+
+    let mut chars: Vec<char> = text.chars().collect();
+    while chars.pop().is_some() {
+        let shortened = format!("{}…", chars.iter().collect::<String>().trim_end());
+        if width(&shortened) <= max_width as f32 {
+            return shortened;
+        }
+    }
+    String::new()
 }
 
 impl PhotoBorder {
@@ -358,9 +1147,130 @@ impl PhotoBorder {
             border_type,
             show_exif,
             font_data: Some(font_data),
+            border_color: DEFAULT_BORDER_COLOR,
+            fill: BorderFill::default(),
+            text_color: DEFAULT_TEXT_COLOR,
+            caption_template: None,
+            exif_options: ExifOptions::default(),
+            logo: None,
+            to_srgb: false,
+            metadata_policy: MetadataPolicy::default(),
+            output_options: OutputOptions::default(),
+            alpha_mode: AlphaMode::default(),
+            print: None,
+            composition: None,
+            carousel: None,
+            shadow: None,
+            stroke: None,
         })
     }
 
+    /// Sets the colour used to fill the border (white by default)
+    pub fn with_border_color(mut self, color: Rgb<u8>) -> Self {
+        self.border_color = color;
+        self
+    }
+
+    /// Sets the content of the border area (`BorderFill::Solid`, the border
+    /// colour, by default)
+    pub fn with_border_fill(mut self, fill: BorderFill) -> Self {
+        self.fill = fill;
+        self
+    }
+
+    /// Sets the colour used to draw the EXIF text (dark gray by default)
+    pub fn with_text_color(mut self, color: Rgb<u8>) -> Self {
+        self.text_color = color;
+        self
+    }
+
+    /// Sets the options used when reading EXIF data (value notation,
+    /// camera and lens tables, lens override)
+    pub fn with_exif_options(mut self, options: ExifOptions) -> Self {
+        self.exif_options = options;
+        self
+    }
+
+    /// Enables the camera brand logo in the bottom border
+    pub fn with_logo(mut self, options: Option<LogoOptions>) -> Self {
+        self.logo = options;
+        self
+    }
+
+    /// Converts photos with a colour profile (Adobe RGB, Display P3...)
+    /// to sRGB, for web output; by default the profile is kept
+    pub fn with_srgb_output(mut self, to_srgb: bool) -> Self {
+        self.to_srgb = to_srgb;
+        self
+    }
+
+    /// Sets which EXIF data of the original is written to the output
+    /// (everything by default); the caption always uses the full data
+    pub fn with_metadata_policy(mut self, policy: MetadataPolicy) -> Self {
+        self.metadata_policy = policy;
+        self
+    }
+
+    /// Sets the output format and encoder quality (format of the
+    /// original by default)
+    pub fn with_output_options(mut self, options: OutputOptions) -> Self {
+        self.output_options = options;
+        self
+    }
+
+    /// Sets how transparent photos are framed (transparency kept inside
+    /// an opaque border by default)
+    pub fn with_alpha_mode(mut self, mode: AlphaMode) -> Self {
+        self.alpha_mode = mode;
+        self
+    }
+
+    /// Enables print mode: the output is sized for a paper format and
+    /// replaces the border type (`None` by default)
+    pub fn with_print(mut self, options: Option<PrintOptions>) -> Self {
+        self.print = options;
+        self
+    }
+
+    /// Enables diptychs and triptychs: the photos passed to
+    /// [`PhotoBorder::process_composition`] are framed together (`None` by default)
+    pub fn with_composition(mut self, options: Option<ComposeOptions>) -> Self {
+        self.composition = options;
+        self
+    }
+
+    /// Splits the bordered photos into carousel tiles saved as "_01",
+    /// "_02", ... (`None` by default)
+    pub fn with_carousel(mut self, options: Option<CarouselOptions>) -> Self {
+        self.carousel = options;
+        self
+    }
+
+    /// Casts a soft drop shadow of the photo onto the border (`None` by
+    /// default)
+    pub fn with_shadow(mut self, options: Option<ShadowOptions>) -> Self {
+        self.shadow = options;
+        self
+    }
+
+    /// Draws a keyline around the photo edge (`None` by default)
+    pub fn with_stroke(mut self, options: Option<StrokeOptions>) -> Self {
+        self.stroke = options;
+        self
+    }
+
+    /// Sets a caption template (see [`ExifData::render_template`])
+    ///
+    /// # Returns
+    /// * `Err(PhotoBorderError::ConfigError)` if the template uses an unknown field
+    pub fn with_caption_template(mut self, template: Option<&str>) -> Result<Self, PhotoBorderError> {
+        if let Some(template) = template {
+            ExifData::check_template(template)?;
+        }
+        self.caption_template = template.map(str::to_string);
+        Ok(self)
+    }
+
     /// Processes an individual image by adding a border
     ///
     /// # Arguments
@@ -372,97 +1282,365 @@ impl PhotoBorder {
     /// * `Err(PhotoBorderError)` in case of error
     ///
     /// # Processing Steps
-    /// 1. Load source image
-    /// 2. Calculate border dimensions
-    /// 3. Create new image with white border
-    /// 4. Copy original image to center
-    /// 5. Optionally add EXIF data
-    /// 6. Save result
+    /// 1. Load the photo, its borders and EXIF data (see [`PhotoBorder::load_frame`])
+    /// 2. Draw the frame (see [`PhotoBorder::render`]), with the borders
+    ///    widened to a whole number of tiles for a carousel
+    /// 3. Save result with the EXIF data and colour profile of the original,
+    ///    or each carousel tile ("photo_border_01.jpg", "photo_border_02.jpg", ...)
     pub fn process_image<P: AsRef<Path>>(&self, input_path: P, output_dir: Option<&Path>) -> Result<(), PhotoBorderError> {
-        use image::GenericImageView;
         let input_path = input_path.as_ref();
 
-        // Load source image
-        let img = image::open(input_path)?;
-
-        // Get original dimensions
-        let (width, height) = img.dimensions();
+        // Output format, the format of the original by default
+        let format = self.output_options.format_for(input_path);
 
-        // Calculate border dimensions according to chosen type
-        let (top, right, bottom, left) = self.border_type.get_border_size(width, height);
+        let Frame { photo, borders, icc_profile, panels } = self.load_frame(input_path, format)?;
 
-        // Calculate new dimensions with borders
-        let new_width = width + left + right;
-        let new_height = height + top + bottom;
+        // Widen the border so that the carousel tiles share it seamlessly
+        let borders = match &self.carousel {
+            Some(carousel) => carousel.pad((photo.width(), photo.height()), borders),
+            None => borders,
+        };
+        let bordered_img = self.render(photo, format, borders, &panels);
 
-        // Create new image with white background
-        // White (255, 255, 255) gives a professional and timeless appearance
-        let mut bordered_img = ImageBuffer::from_pixel(new_width, new_height, Rgb([255u8, 255u8, 255u8]));
+        let Some(carousel) = &self.carousel else {
+            // Generate output path, with the extension of the output format
+            let output_path = self.generate_output_path(input_path, output_dir, format, "border")?;
 
-        // Copy original image to center of new image
-        // Left and top offsets correctly position the image
-        image::imageops::overlay(&mut bordered_img, &img.to_rgb8(), left as i64, top as i64);
+            // Save final image
+            self.save_with_exif(&bordered_img, &output_path, format, input_path, icc_profile)?;
+            println!("Saved bordered image to: {}", output_path.display());
+            return Ok(());
+        };
 
-        // Add EXIF metadata if requested
-        if self.show_exif {
-            match ExifData::from_file(input_path) {
-                Ok(exif_data) => {
-                    // Attempt to draw EXIF text
-                    if let Err(e) = self.draw_exif_text(&mut bordered_img, &exif_data, left, new_height - bottom) {
-                        eprintln!("Warning: Could not draw EXIF text: {}", e);
-                    }
-                },
-                Err(e) => eprintln!("Warning: Could not read EXIF data: {}", e),
-            }
+        // Save each tile, numbered from left to right
+        let tiles = carousel.split(&bordered_img);
+        for (index, tile) in tiles.iter().enumerate() {
+            let suffix = format!("border_{:02}", index + 1);
+            let output_path = self.generate_output_path(input_path, output_dir, format, &suffix)?;
+            self.save_with_exif(tile, &output_path, format, input_path, icc_profile.clone())?;
+            println!("Saved tile {}/{} to: {}", index + 1, tiles.len(), output_path.display());
         }
 
-        // Generate output path
-        let output_path = self.generate_output_path(input_path, output_dir)?;
-
-        // Save final image
-        bordered_img.save(&output_path)?;
-        self.save_with_exif(&bordered_img, &output_path, input_path)?;
-        println!("Saved bordered image to: {}", output_path.display());
-
         Ok(())
     }
 
-    /// Generates output file path based on input
+    /// Loads a photo and computes its frame
     ///
     /// # Arguments
-    /// * `input_path` - Source file path
-    /// * `output_dir` - Optional output directory
+    /// * `input_path` - Path to source image
+    /// * `format` - Output format
     ///
     /// # Returns
-    /// Complete path to output file
-    ///
-    /// # Naming Convention
-    /// Adds "_border" to filename before extension
-    /// Ex: "photo.jpg" -> "photo_border.jpg"
-    fn generate_output_path(&self, input_path: &Path, output_dir: Option<&Path>) -> Result<std::path::PathBuf, PhotoBorderError> {
-        // Extract filename without extension
-        let stem = input_path.file_stem()
-            .ok_or_else(|| PhotoBorderError::IoError(io::Error::new(io::ErrorKind::InvalidInput, "Invalid filename")))?
-            .to_str()
-            .ok_or_else(|| PhotoBorderError::IoError(io::Error::new(io::ErrorKind::InvalidInput, "Invalid filename encoding")))?;
-
-        // Extract extension
-        let extension = input_path.extension()
-            .ok_or_else(|| PhotoBorderError::IoError(io::Error::new(io::ErrorKind::InvalidInput, "No file extension")))?
-            .to_str()
-            .ok_or_else(|| PhotoBorderError::IoError(io::Error::new(io::ErrorKind::InvalidInput, "Invalid extension encoding")))?;
-
-        // Build new name with "_border" suffix
-        let output_filename = format!("{}_border.{}", stem, extension);
-
-        // Determine destination directory
-        let output_path = if let Some(dir) = output_dir {
-            // Use specified directory
-            dir.join(output_filename)
-        } else {
-            // Save in same directory as original
-            let parent = input_path.parent().unwrap_or(Path::new("."));
-            parent.join(output_filename)
+    /// The photo, scaled to the paper size in print mode and optionally
+    /// converted to sRGB, with its borders, colour profile and EXIF data
+    fn load_frame(&self, input_path: &Path, format: OutputFormat) -> Result<Frame, PhotoBorderError> {
+        // Load source image and its colour profile, which `image` drops
+        let img = formats::open_image(input_path)?;
+        let icc_profile = color_profile::read_profile(input_path);
+
+        let panel = Panel {
+            rect: (0, 0, img.width(), img.height()),
+            exif_data: self.load_exif(input_path),
+        };
+        Ok(self.frame(img, icc_profile, vec![panel], format))
+    }
+
+    /// Reads the EXIF data of a photo, if needed for the text or the brand logo
+    fn load_exif(&self, input_path: &Path) -> Option<ExifData> {
+        if !self.show_exif && self.logo.is_none() {
+            return None;
+        }
+        match ExifData::load(input_path, &self.exif_options) {
+            Ok(exif_data) => Some(exif_data),
+            Err(e) => {
+                eprintln!("Warning: Could not read EXIF data: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Computes the frame of a loaded photo
+    ///
+    /// # Arguments
+    /// * `img` - Photo (a diptych or triptych is composed first)
+    /// * `icc_profile` - Colour profile of the photo
+    /// * `panels` - Original photos in it
+    /// * `format` - Output format
+    ///
+    /// # Returns
+    /// The photo, scaled to the paper size in print mode and optionally
+    /// converted to sRGB, with its borders
+    fn frame(&self, mut img: DynamicImage, icc_profile: Option<Vec<u8>>, mut panels: Vec<Panel>, format: OutputFormat) -> Frame {
+        // Calculate border dimensions according to chosen type, or fit the
+        // photo on the paper in print mode
+        let (width, height) = (img.width(), img.height());
+        let borders = match &self.print {
+            Some(print) => {
+                let layout = print.layout(width, height);
+                let (photo_width, photo_height) = layout.photo;
+                if photo_width > width {
+                    eprintln!(
+                        "Warning: Photo enlarged to fit the paper, printed at {} dpi",
+                        print.dpi as u64 * width as u64 / photo_width as u64,
+                    );
+                }
+                img = img.resize_exact(photo_width, photo_height, image::imageops::FilterType::Lanczos3);
+
+                // Scale the panels with the photo
+                let scale = |value: u32, new_size: u32, size: u32| (value as u64 * new_size as u64 / size as u64) as u32;
+                for panel in &mut panels {
+                    let (x, y, panel_width, panel_height) = panel.rect;
+                    panel.rect = (
+                        scale(x, photo_width, width),
+                        scale(y, photo_height, height),
+                        scale(panel_width, photo_width, width),
+                        scale(panel_height, photo_height, height),
+                    );
+                }
+                layout.borders
+            }
+            None => self.border_type.get_border_size(width, height),
+        };
+
+        // Convert the photo to sRGB if requested; the border colours are
+        // already sRGB. On success the output needs no profile any more.
+        let icc_profile = match icc_profile {
+            Some(profile) if self.to_srgb => {
+                let color_type = img.color();
+                if color_type.bytes_per_pixel() / color_type.channel_count() > 1 && format.supports_16_bit() {
+                    eprintln!("Warning: 16-bit photo reduced to 8 bits by the sRGB conversion");
+                }
+                match color_profile::convert_to_srgb(&mut img, &profile) {
+                    Ok(()) => None,
+                    Err(e) => {
+                        eprintln!("Warning: Could not convert to sRGB, keeping the colour profile: {}", e);
+                        Some(profile)
+                    }
+                }
+            }
+            profile => profile,
+        };
+
+        Frame { photo: img, borders, icc_profile, panels }
+    }
+
+    /// Draws the frame around a photo on a canvas of the pixel type of the
+    /// photo (8 or 16-bit, with or without alpha channel, as far as the
+    /// output format allows)
+    ///
+    /// # Arguments
+    /// * `photo` - Photo
+    /// * `format` - Output format
+    /// * `borders` - Border thickness (top, right, bottom, left)
+    /// * `panels` - Original photos, with the EXIF data for the text and the logo
+    fn render(&self, photo: DynamicImage, format: OutputFormat, borders: (u32, u32, u32, u32), panels: &[Panel]) -> DynamicImage {
+        match canvas::prepare(photo, format, self.alpha_mode, self.border_color) {
+            DynamicImage::ImageRgba8(photo) => self.draw_frame(&photo, borders, panels).into(),
+            DynamicImage::ImageRgb16(photo) => self.draw_frame(&photo, borders, panels).into(),
+            DynamicImage::ImageRgba16(photo) => self.draw_frame(&photo, borders, panels).into(),
+            photo => self.draw_frame(&photo.into_rgb8(), borders, panels).into(),
+        }
+    }
+
+    /// Draws the border, brand logo and EXIF text around a photo
+    ///
+    /// # Arguments
+    /// * `photo` - Photo, with the pixel type of the output
+    /// * `borders` - Border thickness (top, right, bottom, left)
+    /// * `panels` - Original photos, with the EXIF data for the text and
+    ///   the logo
+    ///
+    /// # Returns
+    /// The bordered image, with the pixel type of the photo
+    ///
+    /// # Process
+    /// 1. Create new image filled with the border colour (transparent
+    ///    with `AlphaMode::Transparent`) or another border fill
+    /// 2. Copy the photo to the center, with its alpha channel if any
+    /// 3. Optionally add the brand logo (of the first photo with EXIF data)
+    ///    and EXIF data: one line in the bottom border, combining the data
+    ///    of every photo of a diptych or triptych, or one caption below
+    ///    each of them with `CaptionMode::Each`. For a carousel, the logo
+    ///    and the line stay within the first or last tile
+    fn draw_frame<P>(&self, photo: &Canvas<P>, borders: (u32, u32, u32, u32), panels: &[Panel]) -> Canvas<P>
+    where
+        P: image::Pixel + 'static,
+        P::Subpixel: Sample + 'static,
+    {
+        // Get original dimensions
+        let (width, height) = photo.dimensions();
+        let (top, right, bottom, left) = borders;
+
+        // Calculate new dimensions with borders
+        let new_width = width + left + right;
+        let new_height = height + top + bottom;
+
+        // Create new image filled with the border colour or border fill
+        let opacity = if self.alpha_mode == AlphaMode::Transparent { 0.0 } else { 1.0 };
+        let mut bordered_img = fill::canvas(photo, (new_width, new_height), &self.fill, self.border_color, opacity);
+
+        // Shadow and keyline of each photo on the border, the keylines over
+        // the shadows of the neighbouring photos
+        let photo_rects: Vec<_> = panels.iter()
+            .map(|panel| (left + panel.rect.0, top + panel.rect.1, panel.rect.2, panel.rect.3))
+            .collect();
+        if let Some(shadow) = &self.shadow {
+            for rect in &photo_rects {
+                effects::draw_shadow(&mut bordered_img, *rect, shadow);
+            }
+        }
+        if let Some(stroke) = &self.stroke {
+            for rect in &photo_rects {
+                effects::draw_stroke(&mut bordered_img, *rect, stroke);
+            }
+        }
+
+        // Copy original image to center of new image, replacing the border
+        // pixels so that transparent areas stay transparent
+        // Left and top offsets correctly position the image. The photos of
+        // a composition are copied one by one, so that the gutters show the
+        // border fill, shadows and keylines like the outer border
+        let per_panel = panels.len() > 1
+            || !matches!(self.fill, BorderFill::Solid)
+            || self.shadow.is_some()
+            || self.stroke.is_some();
+        if per_panel {
+            for (rect, panel) in photo_rects.iter().zip(panels) {
+                let (x, y, panel_width, panel_height) = panel.rect;
+                let view = image::imageops::crop_imm(photo, x, y, panel_width, panel_height);
+                image::imageops::replace(&mut bordered_img, &*view, rect.0 as i64, rect.1 as i64);
+            }
+        } else {
+            image::imageops::replace(&mut bordered_img, photo, left as i64, top as i64);
+        }
+
+        // Area of the bottom border holding the logo and the EXIF line: the
+        // whole width, or the caption tile of a carousel, whose widened
+        // border keeps the logo in the band of the border type under the photo
+        let ((span_x, span_width), band) = match &self.carousel {
+            Some(carousel) => (
+                carousel.caption_span(new_width, new_height),
+                self.border_type.get_border_size(width, height).2.min(bottom),
+            ),
+            None => ((0, new_width), bottom),
+        };
+
+        // Text colour readable on a filled border, behind the EXIF line
+        let text_color = match self.fill {
+            BorderFill::Solid => self.text_color,
+            _ => {
+                let (_, margin) = self.text_margins();
+                let line_height = (self.caption_scale(new_width, new_height).ceil() as u32 + 2 * margin).min(bottom);
+                fill::text_color(&bordered_img, (span_x, new_height - bottom, span_width, line_height), self.text_color)
+            }
+        };
+
+        // Add the brand logo, which may push the EXIF text to the right
+        let mut text_offset = span_x + left;
+        let logo_exif = panels.iter().find_map(|panel| panel.exif_data.as_ref());
+        if let (Some(options), Some(exif_data)) = (&self.logo, logo_exif) {
+            let size = (span_width, new_height - bottom + band);
+            match self.place_brand_logo(size, exif_data, options, (left, right, band), text_color) {
+                Ok(Some(logo)) => {
+                    canvas::draw_layer(&mut bordered_img, &logo.image, span_x + logo.x, logo.y);
+                    text_offset += logo.text_shift;
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("Warning: Could not draw brand logo: {}", e),
+            }
+        }
+
+        // Add EXIF metadata if requested
+        if self.show_exif {
+            let each = panels.len() > 1
+                && self.composition.as_ref().is_some_and(|options| options.caption_mode == CaptionMode::Each);
+            let result = if each {
+                // Below each photo, the first one after the logo
+                panels.iter().enumerate().try_for_each(|(index, panel)| {
+                    let Some(exif_data) = &panel.exif_data else {
+                        return Ok(());
+                    };
+                    let (x, y, width, height) = panel.rect;
+                    let shift = if index == 0 { text_offset - left } else { 0 };
+                    self.draw_exif_text(
+                        &mut bordered_img,
+                        &self.caption_text(exif_data),
+                        text_color,
+                        left + x + shift,
+                        top + y + height,
+                        Some(width.saturating_sub(shift)),
+                    )
+                })
+            } else {
+                let captions: Vec<String> = panels.iter()
+                    .filter_map(|panel| panel.exif_data.as_ref())
+                    .map(|exif_data| self.caption_text(exif_data))
+                    .collect();
+                if captions.is_empty() {
+                    Ok(())
+                } else {
+                    let text = compose::combine_captions(&captions);
+                    let (margin, _) = self.text_margins();
+                    let max_width = self.carousel.as_ref().map(|_| (span_x + span_width).saturating_sub(text_offset + margin));
+                    self.draw_exif_text(&mut bordered_img, &text, text_color, text_offset, new_height - bottom, max_width)
+                }
+            };
+            // Attempt to draw EXIF text
+            if let Err(e) = result {
+                eprintln!("Warning: Could not draw EXIF text: {}", e);
+            }
+        }
+
+        bordered_img
+    }
+
+    /// Generates output file path based on input
+    ///
+    /// # Arguments
+    /// * `input_path` - Source file path
+    /// * `output_dir` - Optional output directory
+    /// * `format` - Output format
+    /// * `suffix` - Added to the file name ("border", "diptych", "triptych")
+    ///
+    /// # Returns
+    /// Complete path to output file
+    ///
+    /// # Naming Convention
+    /// Adds "_border" to filename before extension, and replaces the
+    /// extension when the format changes
+    /// Ex: "photo.jpg" -> "photo_border.jpg", "photo.heic" -> "photo_border.jpg"
+    fn generate_output_path(&self, input_path: &Path, output_dir: Option<&Path>, format: OutputFormat, suffix: &str) -> Result<std::path::PathBuf, PhotoBorderError> {
+        // Extract filename without extension
+        let stem = input_path.file_stem()
+            .ok_or_else(|| PhotoBorderError::IoError(io::Error::new(io::ErrorKind::InvalidInput, "Invalid filename")))?
+            .to_str()
+            .ok_or_else(|| PhotoBorderError::IoError(io::Error::new(io::ErrorKind::InvalidInput, "Invalid filename encoding")))?;
+
+        // Extract extension
+        let extension = input_path.extension()
+            .ok_or_else(|| PhotoBorderError::IoError(io::Error::new(io::ErrorKind::InvalidInput, "No file extension")))?
+            .to_str()
+            .ok_or_else(|| PhotoBorderError::IoError(io::Error::new(io::ErrorKind::InvalidInput, "Invalid extension encoding")))?;
+
+        // Keep the spelling of the original extension ("JPG", "jpeg") when
+        // the format is unchanged
+        let extension = if OutputFormat::from_extension(extension) == Some(format) {
+            extension
+        } else {
+            format.extension()
+        };
+
+        // Build new name with "_border" suffix
+        let output_filename = format!("{}_{}.{}", stem, suffix, extension);
+
+        // Determine destination directory
+        let output_path = if let Some(dir) = output_dir {
+            // Use specified directory
+            dir.join(output_filename)
+        } else {
+            // Save in same directory as original
+            let parent = input_path.parent().unwrap_or(Path::new("."));
+            parent.join(output_filename)
         };
 
         Ok(output_path)
@@ -472,64 +1650,184 @@ impl PhotoBorder {
     ///
     /// # Arguments
     /// * `img` - Destination image (mutable)
-    /// * `exif_data` - EXIF data to display
+    /// * `text` - Caption (see [`PhotoBorder::caption_text`])
+    /// * `color` - Text colour
     /// * `x_offset` - Horizontal starting position
     /// * `y_offset` - Vertical starting position
+    /// * `max_width` - Width available from `x_offset`, `None` up to the
+    ///   edge of the image; longer text is shortened with an ellipsis
     ///
     /// # Returns
     /// * `Ok(())` if text is drawn successfully
     /// * `Err(PhotoBorderError)` in case of font or drawing error
     ///
     /// # Text Style
-    /// - Size proportional to image (1/80 of smallest dimension)
-    /// - Dark gray color (64, 64, 64) for optimal readability on white background
-    /// - Positioning with 20px left margin and 5px from bottom
-    fn draw_exif_text(
+    /// - Size proportional to image (1/80 of smallest dimension), or the
+    ///   caption size in points in print mode
+    /// - Text colour: the configured one (dark gray (64, 64, 64) by
+    ///   default), or adapted to a filled border
+    /// - Positioning with 20px left margin and 5px from bottom (aligned
+    ///   with the photo and 3 mm below it in print mode)
+    /// - Rendered on an 8-bit layer blended onto the image, whatever its
+    ///   pixel type
+    fn draw_exif_text<P>(
         &self,
-        img: &mut RgbImage,
-        exif_data: &ExifData,
+        img: &mut Canvas<P>,
+        text: &str,
+        color: Rgb<u8>,
         x_offset: u32,
         y_offset: u32,
-    ) -> Result<(), PhotoBorderError> {
-        // Check font availability
-        let font_data = match &self.font_data {
-            Some(data) => data.clone(),
-            None => {
-                eprintln!("No font provided, skipping text rendering. Use -f flag to specify a font.");
-                return Ok(());
-            }
+        max_width: Option<u32>,
+    ) -> Result<(), PhotoBorderError>
+    where
+        P: image::Pixel,
+        P::Subpixel: Sample,
+    {
+        // Calculate font size proportional to image, or from the physical
+        // size in print mode
+        let (width, height) = img.dimensions();
+        let scale = self.caption_scale(width, height);
+        let (margin_x, margin_y) = self.text_margins();
+
+        let text = match (max_width, self.font()?) {
+            (Some(max_width), Some(font)) => fit_text(&font, text, scale, max_width.saturating_sub(margin_x)),
+            _ => text.to_string(),
         };
+        self.draw_text_line(img, &text, scale, color, x_offset + margin_x, y_offset + margin_y)
+    }
 
-        // Load font from memory data
-        let font = Font::try_from_vec(font_data)
-            .ok_or_else(|| PhotoBorderError::FontError("Invalid font data".to_string()))?;
+    /// Draws a line of text in the configured font
+    ///
+    /// # Arguments
+    /// * `img` - Destination image (mutable)
+    /// * `text` - Text to draw
+    /// * `scale` - Font size in pixels
+    /// * `color` - Text colour
+    /// * `x`, `y` - Position of the top-left corner of the text
+    ///
+    /// # Returns
+    /// * `Ok(())` if text is drawn successfully
+    /// * `Err(PhotoBorderError)` in case of font error
+    fn draw_text_line<P>(&self, img: &mut Canvas<P>, text: &str, scale: f32, color: Rgb<u8>, x: u32, y: u32) -> Result<(), PhotoBorderError>
+    where
+        P: image::Pixel,
+        P::Subpixel: Sample,
+    {
+        let Some(font) = self.font()? else {
+            eprintln!("No font provided, skipping text rendering. Use -f flag to specify a font.");
+            return Ok(());
+        };
+
+        let Rgb([r, g, b]) = color;
 
-        // Calculate font size proportional to image
+        // Draw text on a transparent layer covering the text area,
+        // with precise positioning
         let (width, height) = img.dimensions();
-        let min_dimension = width.min(height);
-        let scale = Scale::uniform((min_dimension / 80) as f32);
-
-        // Text color: dark gray for good readability
-        let color = Rgb([64u8, 64u8, 64u8]);
-
-        // Format EXIF data into single line with separators
-        let lines = exif_data.format_for_display();
-        let text = lines.join(" | ");
-
-        // Draw text on image with precise positioning
-        draw_text_mut(
-            img,
-            color,
-            x_offset as i32 + 20,  // 20px left margin
-            y_offset as i32 + 5,   // 5px bottom margin
-            scale,
-            &font,
-            &text,
+        let mut layer = RgbaImage::from_pixel(
+            width.saturating_sub(x),
+            height.saturating_sub(y),
+            Rgba([r, g, b, 0]),
         );
+        draw_text_mut(&mut layer, Rgba([r, g, b, 255]), 0, 0, Scale::uniform(scale), &font, text);
+        canvas::draw_layer(img, &layer, x, y);
 
         Ok(())
     }
 
+    /// Font of the EXIF text, `None` if no font is available
+    fn font(&self) -> Result<Option<Font<'_>>, PhotoBorderError> {
+        self.font_data
+            .as_deref()
+            .map(|data| Font::try_from_bytes(data).ok_or_else(|| PhotoBorderError::FontError("Invalid font data".to_string())))
+            .transpose()
+    }
+
+    /// Caption of a photo: the EXIF data on a single line with separators,
+    /// or the user's caption template
+    fn caption_text(&self, exif_data: &ExifData) -> String {
+        match &self.caption_template {
+            Some(template) => exif_data.render_template(template),
+            None => exif_data.format_for_display().join(" | "),
+        }
+    }
+
+    /// Font scale of the EXIF text in pixels: 1/80 of the smallest
+    /// dimension of the bordered image, or the caption size in print mode
+    fn caption_scale(&self, width: u32, height: u32) -> f32 {
+        match &self.print {
+            Some(print) => print.caption_pixels(),
+            None => (width.min(height) / 80) as f32,
+        }
+    }
+
+    /// Horizontal and vertical distance between the photo and the EXIF text
+    ///
+    /// 20px and 5px, or no horizontal margin (the text is aligned with
+    /// the photo) and 3 mm in print mode
+    fn text_margins(&self) -> (u32, u32) {
+        match &self.print {
+            Some(print) => (0, print.caption_gap()),
+            None => (20, 5),
+        }
+    }
+
+    /// Places the camera brand logo in the bottom border
+    ///
+    /// # Arguments
+    /// * `size` - Size of the bordered image
+    /// * `exif_data` - EXIF data giving the camera brand
+    /// * `options` - Logo settings
+    /// * `border` - Left, right and bottom border thickness
+    /// * `color` - Tint of the logo (the text colour)
+    ///
+    /// # Returns
+    /// * `Ok(Some(logo))` - Logo image, its position and the horizontal
+    ///   space taken before the EXIF text (0 unless the logo is on the left)
+    /// * `Ok(None)` if there is no logo for the brand or no bottom border
+    /// * `Err(PhotoBorderError)` if a logo file cannot be loaded
+    ///
+    /// # Layout
+    /// - Height: `options.size` of the bottom border, vertically centred
+    /// - Left: at the text margin from the photo edge, text after it
+    /// - Center: centred on the image
+    /// - Right: at the text margin from the right photo edge
+    fn place_brand_logo(
+        &self,
+        size: (u32, u32),
+        exif_data: &ExifData,
+        options: &LogoOptions,
+        border: (u32, u32, u32),
+        color: Rgb<u8>,
+    ) -> Result<Option<PlacedLogo>, PhotoBorderError> {
+        let (left, right, bottom) = border;
+        let (width, height) = size;
+
+        // No room for a logo without a bottom border
+        let logo_height = (bottom as f32 * options.size).round() as u32;
+        if logo_height == 0 {
+            return Ok(None);
+        }
+
+        let Some(logo) = logo::load_logo(exif_data.make.as_deref(), logo_height, color, options)? else {
+            return Ok(None);
+        };
+
+        // Same margin as the EXIF text, and half the logo height between them
+        let (margin, _) = self.text_margins();
+        let x = match options.position {
+            LogoPosition::Left => left + margin,
+            LogoPosition::Center => width.saturating_sub(logo.width()) / 2,
+            LogoPosition::Right => (width - right).saturating_sub(logo.width() + margin),
+        };
+        let y = height - bottom + bottom.saturating_sub(logo.height()) / 2;
+        let text_shift = match options.position {
+            LogoPosition::Left => logo.width() + logo_height / 2,
+            LogoPosition::Center | LogoPosition::Right => 0,
+        };
+
+        Ok(Some(PlacedLogo { image: logo, x, y, text_shift }))
+    }
+
     /// Processes multiple images in batch
     ///
     /// # Arguments
@@ -578,61 +1876,304 @@ impl PhotoBorder {
         Ok(())
     }
 
-    /// Saves image with EXIF data preserved from original
-    fn save_with_exif(&self, img: &RgbImage, output_path: &Path, original_path: &Path) -> Result<(), PhotoBorderError> {
-        // First, save the processed image to a temporary buffer
-        let mut processed_buffer = Vec::new();
-        {
-            let mut cursor = std::io::Cursor::new(&mut processed_buffer);
-            // Save as JPEG with high quality
-            img.write_to(&mut cursor, image::ImageOutputFormat::Jpeg(95))
-                .map_err(|e| PhotoBorderError::ImageError(e))?;
+    /// Composes two or three photos into a diptych or triptych and frames it
+    ///
+    /// # Arguments
+    /// * `input_paths` - Two or three photos, left to right or top to bottom
+    /// * `output_dir` - Optional output directory
+    ///
+    /// # Returns
+    /// * `Ok(())` if processing succeeds
+    /// * `Err(PhotoBorderError::ConfigError)` without composition settings
+    ///   or with a wrong number of photos
+    /// * `Err(PhotoBorderError)` if a photo cannot be loaded or the result
+    ///   cannot be saved
+    ///
+    /// # Processing
+    /// - Photos scaled to a common height or width (see [`compose::normalize`])
+    ///   and put together with uniform gutters, as thick as the widest
+    ///   border by default
+    /// - Colour profile kept when every photo has the same, photos
+    ///   converted to sRGB otherwise
+    /// - The composition is framed like a single photo; the output is named
+    ///   after the first photo ("a.jpg" -> "a_diptych.jpg", "a_triptych.jpg")
+    ///   and carries its EXIF data
+    pub fn process_composition<P: AsRef<Path>>(&self, input_paths: Vec<P>, output_dir: Option<&Path>) -> Result<(), PhotoBorderError> {
+        let Some(options) = &self.composition else {
+            return Err(PhotoBorderError::ConfigError("No diptych or triptych layout given".to_string()));
+        };
+        let input_paths: Vec<&Path> = input_paths.iter().map(|path| path.as_ref()).collect();
+        let suffix = match input_paths.len() {
+            2 => "diptych",
+            3 => "triptych",
+            _ => return Err(PhotoBorderError::ConfigError("A diptych or triptych needs 2 or 3 photos".to_string())),
+        };
+        println!("Composing {} image(s)...", input_paths.len());
+
+        // Load the photos and their colour profiles
+        let mut photos = Vec::with_capacity(input_paths.len());
+        let mut profiles = Vec::with_capacity(input_paths.len());
+        for (index, input_path) in input_paths.iter().enumerate() {
+            println!("[{}/{}] Loading: {}", index + 1, input_paths.len(), input_path.display());
+            photos.push(formats::open_image(input_path)?);
+            profiles.push(color_profile::read_profile(input_path));
         }
 
-        // Try to read EXIF from original image
-        match self.copy_exif_to_processed(&processed_buffer, original_path, output_path) {
-            Ok(_) => {
-                println!("EXIF data preserved in output image");
-            },
-            Err(e) => {
-                eprintln!("Warning: Could not preserve EXIF data: {}", e);
-                // Save without EXIF as fallback
-                std::fs::write(output_path, &processed_buffer)?;
+        // A single profile is embedded: keep it when the photos share it,
+        // convert them to sRGB otherwise
+        let icc_profile = if profiles.iter().all(|profile| *profile == profiles[0]) {
+            profiles[0].clone()
+        } else {
+            for ((photo, profile), input_path) in photos.iter_mut().zip(&profiles).zip(&input_paths) {
+                if let Some(profile) = profile {
+                    if let Err(e) = color_profile::convert_to_srgb(photo, profile) {
+                        eprintln!("Warning: Could not convert {} to sRGB: {}", input_path.display(), e);
+                    }
+                }
             }
+            None
+        };
+
+        // Put the photos together, the gutters in the border colour (the
+        // border fill replaces them when framing)
+        let photos = compose::normalize(photos, options.layout);
+        let (width, height) = compose::composed_size(&photos, options.layout, 0);
+        let mut gutter = options.gutter_pixels(width.min(height), self.border_type.get_border_size(width, height));
+        if self.show_exif && options.layout == Layout::Column && options.caption_mode == CaptionMode::Each {
+            // Captions below stacked photos would overlap the next photo
+            gutter = self.caption_gutter(&photos, options.layout, gutter);
         }
+        let opacity = if self.alpha_mode == AlphaMode::Transparent { 0.0 } else { 1.0 };
+        let (composed, rects) = compose::compose(&photos, options.layout, gutter, self.border_color, opacity);
+        let panels = rects.into_iter()
+            .zip(&input_paths)
+            .map(|(rect, input_path)| Panel { rect, exif_data: self.load_exif(input_path) })
+            .collect();
+
+        // Frame the composition like a single photo
+        let format = self.output_options.format_for(input_paths[0]);
+        let Frame { photo, borders, icc_profile, panels } = self.frame(composed, icc_profile, panels, format);
+        let bordered_img = self.render(photo, format, borders, &panels);
+
+        let output_path = self.generate_output_path(input_paths[0], output_dir, format, suffix)?;
+        self.save_with_exif(&bordered_img, &output_path, format, input_paths[0], icc_profile)?;
+        println!("Saved {} to: {}", suffix, output_path.display());
 
         Ok(())
     }
 
-    /// Copies EXIF data from original to processed image
-    fn copy_exif_to_processed(&self, processed_jpeg: &[u8], original_path: &Path, output_path: &Path) -> Result<(), PhotoBorderError> {
-        // Read original image to extract EXIF
-        let original_data = std::fs::read(original_path)?;
-        let original_jpeg = Jpeg::from_bytes(Bytes::from(original_data))
-            .map_err(|e| PhotoBorderError::IoError(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!("Could not parse original JPEG: {}", e)
-            )))?;
-
-        // Extract EXIF from original
-        let exif_data = original_jpeg.exif()
-            .ok_or_else(|| PhotoBorderError::IoError(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "No EXIF data found in original image"
-            )))?;
-
-        // Parse processed image
-        let mut processed_jpeg = Jpeg::from_bytes(Bytes::from(processed_jpeg.to_vec()))
-            .map_err(|e| PhotoBorderError::IoError(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!("Could not parse processed JPEG: {}", e)
-            )))?;
-
-        // Copy EXIF to processed image
-        processed_jpeg.set_exif(Some(exif_data.clone()));
-
-        // Save the final image with EXIF
-        std::fs::write(output_path, processed_jpeg.encoder().bytes())?;
+    /// Gutter holding the caption line below each stacked photo
+    ///
+    /// The caption size follows the size of the framed composition, which
+    /// depends on the gutter: the gutter is widened until the line fits.
+    ///
+    /// # Arguments
+    /// * `photos` - Photos, at their common width
+    /// * `layout` - Arrangement of the photos
+    /// * `gutter` - Requested gutter in pixels
+    fn caption_gutter(&self, photos: &[DynamicImage], layout: Layout, mut gutter: u32) -> u32 {
+        let (_, margin) = self.text_margins();
+        for _ in 0..8 {
+            let (width, height) = compose::composed_size(photos, layout, gutter);
+            // Line height in pixels of the composition, scaled to the
+            // paper in print mode
+            let line = match &self.print {
+                Some(print) => {
+                    let photo_height = print.layout(width, height).photo.1.max(1);
+                    (print.caption_pixels().ceil() + 2.0 * margin as f32) * height as f32 / photo_height as f32
+                }
+                None => {
+                    let (top, right, bottom, left) = self.border_type.get_border_size(width, height);
+                    self.caption_scale(width + left + right, height + top + bottom).ceil() + 2.0 * margin as f32
+                }
+            };
+            let line = line.ceil() as u32;
+            if gutter >= line {
+                break;
+            }
+            gutter = line;
+        }
+        gutter
+    }
+
+    /// Writes the bordered photos to a PDF document, one per page
+    ///
+    /// # Arguments
+    /// * `input_paths` - Vector of paths to images, in page order
+    /// * `output_path` - PDF file to write
+    /// * `options` - Page size, vector drawing and cover page
+    ///
+    /// # Returns
+    /// * `Ok(())` even if some images fail (they are left out)
+    /// * `Err(PhotoBorderError)` if no image could be added or the file
+    ///   cannot be written
+    ///
+    /// # Layout
+    /// - Each page is turned to the orientation of its photo, which is
+    ///   scaled to fit inside a 10 mm margin and centred (no margin in
+    ///   print mode, where the frame already has the size of the paper)
+    /// - The bordered image is embedded as a JPEG file, or with `vector`
+    ///   the original photo is embedded and the border, logo and EXIF text
+    ///   are drawn around it, the text in DejaVu Sans
+    /// - The optional cover page lists the photos with their captions
+    pub fn write_pdf<P: AsRef<Path>>(&self, input_paths: Vec<P>, output_path: &Path, options: &PdfOptions) -> Result<(), PhotoBorderError> {
+        println!("Writing {} image(s) to {}...", input_paths.len(), output_path.display());
+
+        // Title of the document, the name of the file by default
+        let title = options.title.clone()
+            .or_else(|| output_path.file_stem().and_then(|stem| stem.to_str()).map(str::to_string))
+            .unwrap_or_default();
+        let mut document = PdfDocument::new(Some(&title));
+
+        // File names and captions listed on the cover page
+        let mut entries = Vec::new();
+        let mut error_count = 0;
+
+        for (index, input_path) in input_paths.iter().enumerate() {
+            let input_path = input_path.as_ref();
+            println!("[{}/{}] Adding: {}", index + 1, input_paths.len(), input_path.display());
+
+            // Individual processing with non-blocking error handling
+            match self.add_pdf_page(&mut document, input_path, options) {
+                Ok(caption) => {
+                    let name = input_path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                    entries.push((name, caption));
+                }
+                Err(e) => {
+                    eprintln!("Error processing {}: {}", input_path.display(), e);
+                    error_count += 1;
+                }
+            }
+        }
+
+        if entries.is_empty() {
+            return Err(PhotoBorderError::ConfigError("No image could be added to the PDF".to_string()));
+        }
+
+        if options.cover {
+            for (index, page) in pdf::cover_pages(&title, options.paper, &entries).into_iter().enumerate() {
+                document.insert_page(index, page);
+            }
+        }
+
+        let page_count = document.page_count();
+        fs::write(output_path, document.finish())?;
+
+        // Display final summary
+        println!("\nSaved {} page(s) to: {}", page_count, output_path.display());
+        println!("  Successfully added: {} image(s)", entries.len());
+        if error_count > 0 {
+            println!("  Errors encountered: {} image(s)", error_count);
+        }
+
+        Ok(())
+    }
+
+    /// Adds the page of a photo to a PDF document
+    ///
+    /// # Returns
+    /// The caption of the photo, if its EXIF data could be read
+    fn add_pdf_page(&self, document: &mut PdfDocument, input_path: &Path, options: &PdfOptions) -> Result<Option<String>, PhotoBorderError> {
+        let Frame { photo, borders, icc_profile, panels } = self.load_frame(input_path, OutputFormat::Jpeg)?;
+        let exif_data = panels[0].exif_data.as_ref();
+        let caption = exif_data.map(|exif_data| self.caption_text(exif_data));
+
+        let (top, right, bottom, left) = borders;
+        let (width, height) = (photo.width() + left + right, photo.height() + top + bottom);
+        let margin = if self.print.is_some() { 0.0 } else { pdf::PAGE_MARGIN };
+        let (mut page, placement) = pdf::Page::fit(options.paper, margin, width, height);
+
+        // Only a solid border without shadow or keyline can be drawn as
+        // vector graphics
+        let plain = matches!(self.fill, BorderFill::Solid) && self.shadow.is_none() && self.stroke.is_none();
+        if !options.vector || !plain {
+            let bordered_img = self.render(photo, OutputFormat::Jpeg, borders, &panels).into_rgb8();
+            let image = document.add_photo(&bordered_img, icc_profile.as_deref())?;
+            page.draw_image(image, placement.x, placement.y, placement.length(width as f32), placement.length(height as f32));
+            document.add_page(page);
+            return Ok(caption);
+        }
+
+        // Border, left out when transparent
+        if self.alpha_mode != AlphaMode::Transparent {
+            page.fill_rect(placement.x, placement.y, placement.length(width as f32), placement.length(height as f32), self.border_color);
+        }
+
+        // Photo, composited onto the border colour if transparent
+        let photo = canvas::prepare(photo, OutputFormat::Jpeg, AlphaMode::Flatten, self.border_color).into_rgb8();
+        let image = document.add_photo(&photo, icc_profile.as_deref())?;
+        let (x, y) = placement.point(left, top);
+        page.draw_image(image, x, y, placement.length(photo.width() as f32), placement.length(photo.height() as f32));
+
+        // Brand logo, which may push the EXIF text to the right
+        let mut text_offset = left;
+        if let (Some(options), Some(exif_data)) = (&self.logo, exif_data) {
+            match self.place_brand_logo((width, height), exif_data, options, (left, right, bottom), self.text_color) {
+                Ok(Some(logo)) => {
+                    let image = document.add_rgba(&logo.image);
+                    let (x, y) = placement.point(logo.x, logo.y);
+                    page.draw_image(image, x, y, placement.length(logo.image.width() as f32), placement.length(logo.image.height() as f32));
+                    text_offset += logo.text_shift;
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("Warning: Could not draw brand logo: {}", e),
+            }
+        }
+
+        // EXIF text, at the position and size it has in the bordered image
+        if let (true, Some(caption)) = (self.show_exif, &caption) {
+            let size = pdf::font_size(FontStyle::Regular, placement.length(self.caption_scale(width, height)));
+            let (margin_x, margin_y) = self.text_margins();
+            let (x, y) = placement.point(text_offset + margin_x, height - bottom + margin_y);
+            page.text(x, y + pdf::ascent(FontStyle::Regular, size), size, FontStyle::Regular, self.text_color, caption);
+        }
+
+        document.add_page(page);
+        Ok(caption)
+    }
+
+    /// Saves image with EXIF data and colour profile preserved from original
+    ///
+    /// # Arguments
+    /// * `img` - Processed image
+    /// * `output_path` - Destination file
+    /// * `format` - Output format
+    /// * `original_path` - Source file, from which the EXIF data is copied
+    ///   according to the metadata policy
+    /// * `icc_profile` - Colour profile to embed, `None` for sRGB
+    fn save_with_exif(
+        &self,
+        img: &DynamicImage,
+        output_path: &Path,
+        format: OutputFormat,
+        original_path: &Path,
+        icc_profile: Option<Vec<u8>>,
+    ) -> Result<(), PhotoBorderError> {
+        // Print resolution, written to the file in print mode
+        let dpi = self.print.as_ref().map(|print| print.dpi);
+
+        // Try to copy EXIF from original image, filtered by the policy and
+        // updated for the bordered image (dimensions, resolution, thumbnail, software)
+        let mut exif_data = None;
+        if self.metadata_policy != MetadataPolicy::Strip {
+            let rewritten = output_metadata::read_exif_block(original_path)
+                .and_then(|exif| output_metadata::rewrite_exif(&self.metadata_policy, &exif, img, dpi));
+            match rewritten {
+                Ok(Some(exif)) => {
+                    exif_data = Some(exif);
+                    println!("EXIF data preserved in output image");
+                },
+                Ok(None) => {},
+                Err(e) => {
+                    // Save without EXIF as fallback
+                    eprintln!("Warning: Could not preserve EXIF data: {}", e);
+                }
+            }
+        }
+
+        // Encode with the EXIF data and colour profile, then save
+        let encoded = formats::encode(img, format, &self.output_options, exif_data, icc_profile, dpi)?;
+        std::fs::write(output_path, encoded)?;
 
         Ok(())
     }
@@ -655,10 +2196,46 @@ impl PhotoBorder {
 /// - `files`: One or more image files to process (required)
 /// - `-e, --exif`: Enable EXIF data display
 /// - `-t, --border-type`: Border type (s/small, m/medium, l/large)
+/// - `--border-size`, `--border-unit`: Custom per-side border size
+/// - `--border-color`, `--text-color`: Border and EXIF text colours
+/// - `--border-fill`: Fill the border with a blurred copy of the photo
+///   (`--fill-blur`, `--fill-darken`), a gradient from the border colour
+///   (`--fill-color`, `--fill-angle`), a texture or an image (`--fill-file`),
+///   the text colour adapting to it
+/// - `--shadow`: Soft drop shadow of the photo on the border
+///   (`--shadow-offset`, `--shadow-blur`, `--shadow-opacity`, `--shadow-color`)
+/// - `--stroke`, `--stroke-color`: Keyline around the photo edge
+/// - `-c, --caption`: Caption template using EXIF field placeholders
+/// - `--exif-style`: Notation of EXIF values (standard or compact)
+/// - `--camera-names`: Extra camera make/model name table (TOML)
+/// - `--lens`, `--lens-table`: Manual lens name, extra lens table (TOML)
+/// - `--set field=value`: Fill in or replace an EXIF field (repeatable);
+///   `photo.jpg.json` / `.yaml` sidecar files are read unless `--no-sidecar`
+/// - `--logo`: Draw the camera brand logo (`--logo-file`, `--logo-dir`,
+///   `--logo-position`, `--logo-size`, `--logo-colors`)
+/// - `--to-srgb`: Convert photos to sRGB instead of keeping their ICC profile
+/// - `--metadata`, `--metadata-remove`: EXIF data written to the output
+///   (keep, strip, minimal, or custom without gps/serials/makernote/thumbnail)
+/// - `--format`, `--quality`, `--lossless`: Output format (format of the
+///   original, jpeg, png, tiff, webp or avif) and encoder settings
+/// - `--alpha`: Transparent photos (keep, transparent border or flatten)
+/// - `--print`, `--dpi`, `--print-margin`, `--caption-size`: Print mode,
+///   output sized for a paper format with its resolution written to the file
+/// - `--compose`, `--gutter`, `--compose-captions`: Frame 2 or 3 photos
+///   together as a diptych or triptych (side by side or stacked)
+/// - `--carousel`, `--carousel-tiles`, `--carousel-caption`: Split each
+///   bordered photo into seamless 4:5 or 1:1 carousel tiles
+/// - `--pdf`: Write the bordered photos to a PDF file, one per page
+///   (`--pdf-paper`, `--pdf-vector`, `--pdf-cover`, `--pdf-title`)
 /// - `-f, --font`: Path to custom TTF font file
 /// - `-o, --output-dir`: Output directory for processed images
 /// - `--gui`: Force GUI mode even with arguments
 ///
+/// # Subcommands
+/// - `exif <files...>`: Print the extracted EXIF data (`--all`, `--json`)
+/// - `contact-sheet <files...>`: Lay out thumbnails in a grid with their
+///   file name and EXIF settings (`--columns`, `--rows`, `--paper`, ...)
+///
 /// # Returns
 /// * `Ok(())` if execution completes successfully
 /// * `Err(Box<dyn Error>)` in case of critical error
@@ -710,7 +2287,11 @@ fn launch_cli() -> Result<(), Box<dyn Error>> {
     // Configure command-line interface with clap
     let matches = Command::new("schnapsshot")
         .version("1.0")
-        .about("Add a border and exif data to one or more jpg or png photos")
+        .about("Add a border and exif data to one or more JPEG, PNG, TIFF, WebP, HEIC or raw photos")
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true)
+        .subcommand(inspect::command())
+        .subcommand(contact_sheet::command())
         .arg(
             Arg::new("files")
                 .help("Input image filename(s)")
@@ -733,6 +2314,307 @@ fn launch_cli() -> Result<(), Box<dyn Error>> {
                 .value_name("TYPE")
                 .default_value("s"),
         )
+        .arg(
+            Arg::new("border_size")
+                .long("border-size")
+                .help("Custom border size: 'all', 'vertical,horizontal' or 'top,right,bottom,left' (overrides --border_type)")
+                .value_name("SIZES"),
+        )
+        .arg(
+            Arg::new("border_unit")
+                .long("border-unit")
+                .help("Unit of --border-size: px for pixels, % for percent of the smallest dimension")
+                .value_name("UNIT")
+                .default_value("px"),
+        )
+        .arg(
+            Arg::new("border_color")
+                .long("border-color")
+                .help("Border colour as hex (e.g. #ffffff)")
+                .value_name("COLOR"),
+        )
+        .arg(
+            Arg::new("text_color")
+                .long("text-color")
+                .help("EXIF text colour as hex (e.g. #404040)")
+                .value_name("COLOR"),
+        )
+        .arg(
+            Arg::new("border_fill")
+                .long("border-fill")
+                .help("Border fill: solid (border colour), blur (blurred copy of the photo), linear or radial (gradient from the border colour to --fill-color), texture (tiled --fill-file) or image (--fill-file scaled to cover); the text colour adapts to the fill")
+                .value_name("FILL")
+                .default_value("solid"),
+        )
+        .arg(
+            Arg::new("fill_blur")
+                .long("fill-blur")
+                .help("Blur radius of the blurred fill, in percent of the smallest side")
+                .value_name("PERCENT")
+                .default_value("5"),
+        )
+        .arg(
+            Arg::new("fill_darken")
+                .long("fill-darken")
+                .help("Darkening of the blurred fill, in percent")
+                .value_name("PERCENT")
+                .default_value("0"),
+        )
+        .arg(
+            Arg::new("fill_color")
+                .long("fill-color")
+                .help("End colour of the gradient fills as hex, the border colour being the start (e.g. #c0c0c0)")
+                .value_name("COLOR"),
+        )
+        .arg(
+            Arg::new("fill_angle")
+                .long("fill-angle")
+                .help("Direction of the linear gradient in degrees: 0 for left to right, 90 for top to bottom")
+                .value_name("DEGREES")
+                .default_value("90"),
+        )
+        .arg(
+            Arg::new("fill_file")
+                .long("fill-file")
+                .help("Texture (tiled) or background image (scaled to cover) of the texture and image fills")
+                .value_name("FILE"),
+        )
+        .arg(
+            Arg::new("shadow")
+                .long("shadow")
+                .help("Cast a soft drop shadow of the photo onto the border")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("shadow_offset")
+                .long("shadow-offset")
+                .help("Shadow offset 'x,y' in percent of the smallest side of the photo (positive to the right and down)")
+                .value_name("X,Y")
+                .default_value("1,1")
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            Arg::new("shadow_blur")
+                .long("shadow-blur")
+                .help("Shadow blur radius in percent of the smallest side of the photo (0 for a hard shadow)")
+                .value_name("PERCENT")
+                .default_value("2"),
+        )
+        .arg(
+            Arg::new("shadow_opacity")
+                .long("shadow-opacity")
+                .help("Shadow opacity in percent")
+                .value_name("PERCENT")
+                .default_value("50"),
+        )
+        .arg(
+            Arg::new("shadow_color")
+                .long("shadow-color")
+                .help("Shadow colour as hex (e.g. #000000)")
+                .value_name("COLOR")
+                .default_value("#000000"),
+        )
+        .arg(
+            Arg::new("stroke")
+                .long("stroke")
+                .help("Draw a keyline of this width around the photo edge, in --border-unit")
+                .value_name("WIDTH"),
+        )
+        .arg(
+            Arg::new("stroke_color")
+                .long("stroke-color")
+                .help("Keyline colour as hex (e.g. #000000)")
+                .value_name("COLOR")
+                .default_value("#000000"),
+        )
+        .arg(
+            Arg::new("caption")
+                .short('c')
+                .long("caption")
+                .help("Caption template with {field} placeholders and [optional] sections, e.g. \"{camera}[ | {lens}] | {date_taken}\" (see `schnapsshot exif` for field names)")
+                .value_name("TEMPLATE"),
+        )
+        .args(ExifOptions::args())
+        .arg(
+            Arg::new("logo")
+                .long("logo")
+                .help("Draw the camera brand logo in the bottom border")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("logo_file")
+                .long("logo-file")
+                .help("PNG or SVG logo used for every photo (implies --logo)")
+                .value_name("FILE"),
+        )
+        .arg(
+            Arg::new("logo_dir")
+                .long("logo-dir")
+                .help("Directory of <brand>.svg / <brand>.png logos, searched before <config dir>/schnapsshot/logos (implies --logo)")
+                .value_name("DIR"),
+        )
+        .arg(
+            Arg::new("logo_position")
+                .long("logo-position")
+                .help("Logo position: left (before the text), center or right")
+                .value_name("POSITION")
+                .default_value("left"),
+        )
+        .arg(
+            Arg::new("logo_size")
+                .long("logo-size")
+                .help("Logo height in percent of the bottom border")
+                .value_name("PERCENT")
+                .default_value("50"),
+        )
+        .arg(
+            Arg::new("logo_colors")
+                .long("logo-colors")
+                .help("Keep the colours of logo files instead of tinting them with the text colour")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("to_srgb")
+                .long("to-srgb")
+                .help("Convert photos with a colour profile (Adobe RGB, Display P3) to sRGB for web output, instead of embedding the profile")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("metadata")
+                .long("metadata")
+                .help("EXIF data written to output files: keep (all), strip (none), minimal (camera, exposure, date, copyright) or custom (see --metadata-remove)")
+                .value_name("POLICY")
+                .default_value("keep"),
+        )
+        .arg(
+            Arg::new("metadata_remove")
+                .long("metadata-remove")
+                .help("Comma-separated groups removed by --metadata custom: gps, serials, makernote, thumbnail")
+                .value_name("GROUPS"),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .help("Output format: auto (format of the original, JPEG for HEIC and raw files), jpeg, png, tiff, webp or avif")
+                .value_name("FORMAT")
+                .default_value("auto"),
+        )
+        .arg(
+            Arg::new("quality")
+                .long("quality")
+                .help("Quality of JPEG, WebP and AVIF output, from 1 to 100 (defaults: 95, 90 and 80)")
+                .value_name("QUALITY"),
+        )
+        .arg(
+            Arg::new("lossless")
+                .long("lossless")
+                .help("Lossless WebP or AVIF output (requires --format webp or avif)")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("print")
+                .long("print")
+                .help("Print mode: size the output for a paper format (A4, A5, 13x18cm, 8x10in, ...), replacing the border size")
+                .value_name("PAPER"),
+        )
+        .arg(
+            Arg::new("dpi")
+                .long("dpi")
+                .help("Print resolution in dots per inch, written to the output file")
+                .value_name("DPI")
+                .default_value("300"),
+        )
+        .arg(
+            Arg::new("print_margin")
+                .long("print-margin")
+                .help("Minimum margins around the photo in print mode, in millimetres (all, vertical,horizontal or top,right,bottom,left)")
+                .value_name("MM")
+                .default_value("5"),
+        )
+        .arg(
+            Arg::new("caption_size")
+                .long("caption-size")
+                .help("Caption font size in points in print mode")
+                .value_name("PT")
+                .default_value("9"),
+        )
+        .arg(
+            Arg::new("compose")
+                .long("compose")
+                .help("Frame 2 or 3 photos together as a diptych or triptych: row (side by side, same height) or column (stacked, same width)")
+                .value_name("LAYOUT")
+                .conflicts_with("pdf"),
+        )
+        .arg(
+            Arg::new("gutter")
+                .long("gutter")
+                .help("Space between the photos of a diptych or triptych, in --border-unit (defaults to the widest border)")
+                .value_name("SIZE"),
+        )
+        .arg(
+            Arg::new("compose_captions")
+                .long("compose-captions")
+                .help("Captions of a diptych or triptych: combined (one line, shared values written once) or each (below each photo)")
+                .value_name("MODE")
+                .default_value("combined"),
+        )
+        .arg(
+            Arg::new("carousel")
+                .long("carousel")
+                .help("Split each bordered photo into carousel tiles of ratio 4:5 or 1:1, with a continuous border, saved as _01, _02, ...")
+                .value_name("RATIO")
+                .conflicts_with_all(["compose", "pdf", "print"]),
+        )
+        .arg(
+            Arg::new("carousel_tiles")
+                .long("carousel-tiles")
+                .help("Number of carousel tiles, from 1 to 20 (defaults to the shape of the photo)")
+                .value_name("N"),
+        )
+        .arg(
+            Arg::new("carousel_caption")
+                .long("carousel-caption")
+                .help("Carousel tile holding the logo and the EXIF text: first or last")
+                .value_name("TILE")
+                .default_value("last"),
+        )
+        .arg(
+            Arg::new("pdf")
+                .long("pdf")
+                .help("Write the bordered photos to a single PDF file, one per page, instead of image files")
+                .value_name("FILE"),
+        )
+        .arg(
+            Arg::new("pdf_paper")
+                .long("pdf-paper")
+                .help("PDF page size (A4, letter, 13x18cm, ...), defaults to the --print paper or A4")
+                .value_name("PAPER"),
+        )
+        .arg(
+            Arg::new("pdf_vector")
+                .long("pdf-vector")
+                .help("Embed the original photos in the PDF and draw the border and text as vector graphics")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("pdf_cover")
+                .long("pdf-cover")
+                .help("Add a cover page listing the photos to the PDF")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("pdf_title")
+                .long("pdf-title")
+                .help("Title of the PDF, shown on the cover page (defaults to the file name)")
+                .value_name("TITLE"),
+        )
+        .arg(
+            Arg::new("alpha")
+                .long("alpha")
+                .help("Transparent photos: keep (transparency kept, opaque border), transparent (transparent border too) or flatten (composited onto the border colour)")
+                .value_name("MODE")
+                .default_value("keep"),
+        )
         .arg(
             Arg::new("font")
                 .short('f')
@@ -755,6 +2637,13 @@ fn launch_cli() -> Result<(), Box<dyn Error>> {
         )
         .get_matches();
 
+    // Dispatch subcommands
+    match matches.subcommand() {
+        Some(("exif", sub_matches)) => return inspect::run(sub_matches),
+        Some(("contact-sheet", sub_matches)) => return contact_sheet::run(sub_matches),
+        _ => {}
+    }
+
     // Extract command-line arguments
     let files: Vec<String> = matches.get_many::<String>("files")
         .unwrap()
@@ -766,9 +2655,137 @@ fn launch_cli() -> Result<(), Box<dyn Error>> {
     let font_path = matches.get_one::<String>("font");
     let output_dir = matches.get_one::<String>("output_dir");
 
-    // Convert border type from string
-    let border_type = BorderType::from_str(border_type_str)
-        .map_err(|e| PhotoBorderError::FontError(e.to_string()))?;
+    // Convert border type from string, custom sizes taking precedence
+    let border_type = match matches.get_one::<String>("border_size") {
+        Some(sizes) => {
+            let unit = SizeUnit::from_str(matches.get_one::<String>("border_unit").unwrap())
+                .map_err(|e| PhotoBorderError::ConfigError(e.to_string()))?;
+            BorderType::parse_custom(sizes, unit)
+                .map_err(|e| PhotoBorderError::ConfigError(e.to_string()))?
+        }
+        None => BorderType::from_str(border_type_str)
+            .map_err(|e| PhotoBorderError::ConfigError(e.to_string()))?,
+    };
+
+    // Build EXIF reading options from the name tables and overrides
+    let exif_options = ExifOptions::from_matches(&matches)?;
+
+    // Parse optional colours
+    let border_color = matches.get_one::<String>("border_color")
+        .map(|c| parse_color(c))
+        .transpose()?
+        .unwrap_or(DEFAULT_BORDER_COLOR);
+    let text_color = matches.get_one::<String>("text_color")
+        .map(|c| parse_color(c))
+        .transpose()?
+        .unwrap_or(DEFAULT_TEXT_COLOR);
+    let fill_color = matches.get_one::<String>("fill_color")
+        .map(|c| parse_color(c))
+        .transpose()?
+        .unwrap_or(DEFAULT_FILL_COLOR);
+    let border_fill = BorderFill::parse(
+        matches.get_one::<String>("border_fill").unwrap(),
+        matches.get_one::<String>("fill_blur").unwrap(),
+        matches.get_one::<String>("fill_darken").unwrap(),
+        (border_color, fill_color),
+        matches.get_one::<String>("fill_angle").unwrap(),
+        matches.get_one::<String>("fill_file").map(|s| s.as_str()),
+    )?;
+
+    // Drop shadow and keyline
+    let shadow_options = matches.get_flag("shadow")
+        .then(|| ShadowOptions::parse(
+            matches.get_one::<String>("shadow_offset").unwrap(),
+            matches.get_one::<String>("shadow_blur").unwrap(),
+            matches.get_one::<String>("shadow_opacity").unwrap(),
+            matches.get_one::<String>("shadow_color").unwrap(),
+        ))
+        .transpose()?;
+    let stroke_options = matches.get_one::<String>("stroke")
+        .map(|width| StrokeOptions::parse(
+            width,
+            matches.get_one::<String>("border_unit").unwrap(),
+            matches.get_one::<String>("stroke_color").unwrap(),
+        ))
+        .transpose()?;
+
+    // Build logo settings when a logo is requested
+    let logo_options = if matches.get_flag("logo")
+        || matches.contains_id("logo_file")
+        || matches.contains_id("logo_dir")
+    {
+        let size = matches.get_one::<String>("logo_size").unwrap()
+            .parse::<f32>()
+            .ok()
+            .filter(|size| size.is_finite() && *size > 0.0)
+            .ok_or_else(|| PhotoBorderError::ConfigError("Logo size must be a positive number".to_string()))?;
+        Some(LogoOptions {
+            position: LogoPosition::from_str(matches.get_one::<String>("logo_position").unwrap())
+                .map_err(|e| PhotoBorderError::ConfigError(e.to_string()))?,
+            size: size / 100.0,
+            tint: !matches.get_flag("logo_colors"),
+            file: matches.get_one::<String>("logo_file").map(PathBuf::from),
+            dir: matches.get_one::<String>("logo_dir").map(PathBuf::from),
+        })
+    } else {
+        None
+    };
+
+    // Select the EXIF data written to the output
+    let metadata_policy = MetadataPolicy::parse(
+        matches.get_one::<String>("metadata").unwrap(),
+        matches.get_one::<String>("metadata_remove").map(|s| s.as_str()),
+    )?;
+
+    // Select the output format and encoder settings
+    let output_options = OutputOptions::parse(
+        matches.get_one::<String>("format").unwrap(),
+        matches.get_one::<String>("quality").map(|s| s.as_str()),
+        matches.get_flag("lossless"),
+    )?;
+    let alpha_mode = AlphaMode::from_str(matches.get_one::<String>("alpha").unwrap())
+        .map_err(|e| PhotoBorderError::ConfigError(e.to_string()))?;
+
+    // Print mode
+    let print_options = matches.get_one::<String>("print")
+        .map(|paper| PrintOptions::parse(
+            paper,
+            matches.get_one::<String>("dpi").unwrap(),
+            matches.get_one::<String>("print_margin").unwrap(),
+            matches.get_one::<String>("caption_size").unwrap(),
+        ))
+        .transpose()?;
+
+    // Diptych or triptych
+    let compose_options = matches.get_one::<String>("compose")
+        .map(|layout| ComposeOptions::parse(
+            layout,
+            matches.get_one::<String>("gutter").map(|s| s.as_str()),
+            matches.get_one::<String>("border_unit").unwrap(),
+            matches.get_one::<String>("compose_captions").unwrap(),
+        ))
+        .transpose()?;
+
+    // Carousel tiles
+    let carousel_options = matches.get_one::<String>("carousel")
+        .map(|ratio| CarouselOptions::parse(
+            ratio,
+            matches.get_one::<String>("carousel_tiles").map(|s| s.as_str()),
+            matches.get_one::<String>("carousel_caption").unwrap(),
+        ))
+        .transpose()?;
+
+    // PDF output, on the paper of the print mode by default
+    let pdf_options = matches.get_one::<String>("pdf")
+        .map(|_| PdfOptions::parse(
+            matches.get_one::<String>("pdf_paper")
+                .or(matches.get_one::<String>("print"))
+                .map_or("A4", |s| s.as_str()),
+            matches.get_flag("pdf_vector"),
+            matches.get_flag("pdf_cover"),
+            matches.get_one::<String>("pdf_title").map(|s| s.as_str()),
+        ))
+        .transpose()?;
 
     // Validate and create output directory if necessary
     if let Some(dir) = output_dir {
@@ -791,10 +2808,30 @@ fn launch_cli() -> Result<(), Box<dyn Error>> {
         border_type,
         true,  // Use the actual show_exif flag from CLI
         font_path.map(|s| s.as_str()),
-    )?;
-
-    // Launch image processing
-    photo_border.process_multiple_images(files, output_dir.map(|s| Path::new(s)))?;
+    )?
+    .with_border_color(border_color)
+    .with_border_fill(border_fill)
+    .with_shadow(shadow_options)
+    .with_stroke(stroke_options)
+    .with_text_color(text_color)
+    .with_exif_options(exif_options)
+    .with_logo(logo_options)
+    .with_srgb_output(matches.get_flag("to_srgb"))
+    .with_metadata_policy(metadata_policy)
+    .with_output_options(output_options)
+    .with_alpha_mode(alpha_mode)
+    .with_print(print_options)
+    .with_composition(compose_options)
+    .with_carousel(carousel_options)
+    .with_caption_template(matches.get_one::<String>("caption").map(|s| s.as_str()))?;
+
+    // Launch image processing, to a PDF document, a diptych or triptych,
+    // or to image files
+    match (matches.get_one::<String>("pdf"), &pdf_options) {
+        (Some(pdf_path), Some(pdf_options)) => photo_border.write_pdf(files, Path::new(pdf_path), pdf_options)?,
+        _ if matches.contains_id("compose") => photo_border.process_composition(files, output_dir.map(Path::new))?,
+        _ => photo_border.process_multiple_images(files, output_dir.map(Path::new))?,
+    }
 
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,300 @@
+//! Minimal maker note parsing for lens identification
+//!
+//! Maker notes are proprietary blocks stored in the EXIF `MakerNote` tag.
+//! Most of them are TIFF-like IFDs, but each brand has its own header,
+//! byte order and offset base. Only the few tags needed to identify the
+//! mounted lens are read:
+//!
+//! - Canon: `LensModel` (0x0095) or the `LensType` ID of `CameraSettings`
+//! - Sony: `LensType` ID (0xB027), used by A-mount and adapted lenses
+//! - Nikon: lens ID from `LensData` (0x0098) and `LensType` (0x0083),
+//!   and `Lens` focal range and aperture (0x0084); only the unencrypted
+//!   `LensData` versions 0100 and 0101 (bodies up to the D2X and D70)
+//!   are decoded
+//! - Fujifilm: focal range and maximum apertures (0x1404-0x1407); the
+//!   maker note holds no lens ID
+//!
+//! Fujifilm lenses, and Nikon lenses on later bodies, are therefore named
+//! from their focal range and aperture, through the `[specs]` entries of
+//! the lens table or the `LensMake` + `LensSpecification` fallback.
+
+use crate::lens::LensSpec;
+use exif::{Exif, In, Tag, Value};
+
+/// Lens information found in a maker note
+#[derive(Debug, Default)]
+pub struct MakerNoteLens {
+    /// Lens name written by the camera
+    pub name: Option<String>,
+    /// Brand-specific lens ID, to be looked up in the lens table
+    pub id: Option<(LensIdBrand, u64)>,
+    /// Focal range and maximum aperture
+    pub spec: Option<LensSpec>,
+}
+
+/// Brands whose lens IDs can be looked up in the lens table
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LensIdBrand {
+    Canon,
+    Nikon,
+    Sony,
+}
+
+/// Reads the lens information of the maker note, if the brand is supported
+///
+/// # Arguments
+/// * `exif` - Parsed EXIF container (its raw buffer is needed because
+///   Canon and Sony offsets are relative to the EXIF TIFF header)
+/// * `make` - Raw EXIF Make, used to pick the maker note format
+pub fn read_lens(exif: &Exif, make: Option<&str>) -> MakerNoteLens {
+    let Some(field) = exif.get_field(Tag::MakerNote, In::PRIMARY) else {
+        return MakerNoteLens::default();
+    };
+    let Value::Undefined(ref data, offset) = field.value else {
+        return MakerNoteLens::default();
+    };
+    let make = make.unwrap_or("").to_lowercase();
+
+    let lens = if make.contains("canon") {
+        read_canon(exif, offset as usize)
+    } else if make.contains("sony") {
+        read_sony(exif, data, offset as usize)
+    } else if make.contains("nikon") {
+        read_nikon(data)
+    } else if make.contains("fujifilm") {
+        read_fujifilm(data)
+    } else {
+        None
+    };
+
+    lens.unwrap_or_default()
+}
+
+/// Canon: plain IFD at the maker note position, EXIF byte order and offsets
+fn read_canon(exif: &Exif, offset: usize) -> Option<MakerNoteLens> {
+    let ifd = Ifd { data: exif.buf(), little_endian: exif.little_endian() };
+    let entries = ifd.entries(offset)?;
+    let mut lens = MakerNoteLens::default();
+
+    // LensModel string, written by most bodies since 2008
+    if let Some(entry) = entries.iter().find(|e| e.tag == 0x0095) {
+        lens.name = ifd.ascii(entry);
+    }
+
+    // CameraSettings: index 22 is LensType, 23/24 the long/short focal
+    // lengths, expressed in units of index 25 (focal units per mm)
+    if let Some(entry) = entries.iter().find(|e| e.tag == 0x0001) {
+        let settings = ifd.shorts(entry);
+        if let Some(&lens_type) = settings.get(22) {
+            // 0 and 65535 mean no lens information
+            if lens_type != 0 && lens_type != 0xFFFF {
+                lens.id = Some((LensIdBrand::Canon, lens_type as u64));
+            }
+        }
+        if let (Some(&long), Some(&short), Some(&units)) = (settings.get(23), settings.get(24), settings.get(25)) {
+            let units = if units == 0 { 1.0 } else { units as f64 };
+            lens.spec = LensSpec::new(short as f64 / units, long as f64 / units, None, None);
+        }
+    }
+
+    Some(lens)
+}
+
+/// Sony: IFD at the maker note position, optionally behind a 12-byte
+/// "SONY DSC " / "SONY CAM " header, EXIF byte order and offsets
+fn read_sony(exif: &Exif, data: &[u8], offset: usize) -> Option<MakerNoteLens> {
+    let header = if data.starts_with(b"SONY DSC ") || data.starts_with(b"SONY CAM ") { 12 } else { 0 };
+    let ifd = Ifd { data: exif.buf(), little_endian: exif.little_endian() };
+    let entries = ifd.entries(offset + header)?;
+    let mut lens = MakerNoteLens::default();
+
+    if let Some(entry) = entries.iter().find(|e| e.tag == 0xB027) {
+        // 65535 means an E-mount lens (or none), whose ID is not stored here
+        if let Some(id) = ifd.uint(entry).filter(|id| *id != 0xFFFF) {
+            lens.id = Some((LensIdBrand::Sony, id as u64));
+        }
+    }
+
+    Some(lens)
+}
+
+/// Nikon type 3: "Nikon\0" + version, then an embedded TIFF header at
+/// offset 10 with its own byte order; offsets are relative to it
+fn read_nikon(data: &[u8]) -> Option<MakerNoteLens> {
+    if !data.starts_with(b"Nikon\0") || data.len() < 18 {
+        return None;
+    }
+
+    let tiff = &data[10..];
+    let little_endian = match &tiff[0..2] {
+        b"II" => true,
+        b"MM" => false,
+        _ => return None,
+    };
+    let ifd = Ifd { data: tiff, little_endian };
+    let first_ifd = ifd.u32(4)? as usize;
+    let entries = ifd.entries(first_ifd)?;
+
+    let spec = entries.iter()
+        .find(|e| e.tag == 0x0084)
+        .map(|entry| ifd.rationals(entry))
+        .and_then(|values| LensSpec::from_values(&values));
+
+    let value = |tag: u16| entries.iter().find(|e| e.tag == tag).and_then(|entry| ifd.value(entry));
+    let id = match (value(0x0098), value(0x0083).and_then(|lens_type| lens_type.first())) {
+        (Some(lens_data), Some(&lens_type)) => nikon_lens_id(lens_data, lens_type)
+            .map(|id| (LensIdBrand::Nikon, id)),
+        _ => None,
+    };
+
+    Some(MakerNoteLens { id, spec, ..MakerNoteLens::default() })
+}
+
+/// Nikon lens ID, as listed by ExifTool and Lensfun: the 7 bytes of
+/// `LensData` from `LensIDNumber` to `MCUVersion`, then `LensType`
+///
+/// # Arguments
+/// * `lens_data` - `LensData` block, starting with its version ("0100")
+/// * `lens_type` - `LensType` bit field
+///
+/// # Returns
+/// The 8 bytes as a big-endian number, `None` for encrypted versions
+/// (0201 and later, keyed by the body serial number and shutter count)
+/// and for lenses without CPU
+fn nikon_lens_id(lens_data: &[u8], lens_type: u8) -> Option<u64> {
+    let start = match lens_data.get(0..4)? {
+        b"0100" => 6,
+        b"0101" => 11,
+        _ => return None,
+    };
+    let bytes = lens_data.get(start..start + 7).filter(|bytes| bytes.iter().any(|&byte| byte != 0))?;
+    Some(bytes.iter().chain([&lens_type]).fold(0, |id, &byte| id << 8 | byte as u64))
+}
+
+/// Fujifilm: "FUJIFILM" + little-endian IFD offset; always little-endian,
+/// offsets relative to the start of the maker note
+fn read_fujifilm(data: &[u8]) -> Option<MakerNoteLens> {
+    if !data.starts_with(b"FUJIFILM") {
+        return None;
+    }
+
+    let ifd = Ifd { data, little_endian: true };
+    let first_ifd = ifd.u32(8)? as usize;
+    let entries = ifd.entries(first_ifd)?;
+    let rational = |tag: u16| {
+        entries.iter()
+            .find(|e| e.tag == tag)
+            .and_then(|entry| ifd.rationals(entry).first().copied())
+    };
+
+    let spec = LensSpec::from_values(&[
+        rational(0x1404).unwrap_or(0.0),
+        rational(0x1405).unwrap_or(0.0),
+        rational(0x1406).unwrap_or(0.0),
+        rational(0x1407).unwrap_or(0.0),
+    ]);
+
+    Some(MakerNoteLens { spec, ..MakerNoteLens::default() })
+}
+
+/// A directory entry of a TIFF-like IFD
+struct Entry {
+    tag: u16,
+    kind: u16,
+    count: u32,
+    /// Position of the 4-byte value/offset field in the buffer
+    value_pos: usize,
+}
+
+/// Reader over a buffer containing TIFF-like IFDs
+struct Ifd<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> Ifd<'a> {
+    fn u16(&self, pos: usize) -> Option<u16> {
+        let bytes: [u8; 2] = self.data.get(pos..pos + 2)?.try_into().ok()?;
+        Some(if self.little_endian { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) })
+    }
+
+    fn u32(&self, pos: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(pos..pos + 4)?.try_into().ok()?;
+        Some(if self.little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    }
+
+    /// Reads the entries of the IFD starting at `offset`
+    fn entries(&self, offset: usize) -> Option<Vec<Entry>> {
+        let count = self.u16(offset)? as usize;
+        // Guard against garbage counts in corrupted maker notes
+        if count == 0 || offset + 2 + count * 12 > self.data.len() {
+            return None;
+        }
+
+        (0..count)
+            .map(|i| {
+                let pos = offset + 2 + i * 12;
+                Some(Entry {
+                    tag: self.u16(pos)?,
+                    kind: self.u16(pos + 2)?,
+                    count: self.u32(pos + 4)?,
+                    value_pos: pos + 8,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the raw bytes of an entry value (inline or at its offset)
+    fn value(&self, entry: &Entry) -> Option<&'a [u8]> {
+        let unit = match entry.kind {
+            1 | 2 | 6 | 7 => 1,
+            3 | 8 => 2,
+            4 | 9 | 11 => 4,
+            5 | 10 | 12 => 8,
+            _ => return None,
+        };
+        let size = unit * entry.count as usize;
+        let start = if size <= 4 { entry.value_pos } else { self.u32(entry.value_pos)? as usize };
+        self.data.get(start..start + size)
+    }
+
+    fn ascii(&self, entry: &Entry) -> Option<String> {
+        let bytes = self.value(entry)?;
+        let text = String::from_utf8_lossy(bytes);
+        let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+        if text.is_empty() { None } else { Some(text.to_string()) }
+    }
+
+    fn shorts(&self, entry: &Entry) -> Vec<u16> {
+        let Some(bytes) = self.value(entry).filter(|_| entry.kind == 3) else {
+            return Vec::new();
+        };
+        let reader = Ifd { data: bytes, little_endian: self.little_endian };
+        (0..entry.count as usize).filter_map(|i| reader.u16(i * 2)).collect()
+    }
+
+    fn uint(&self, entry: &Entry) -> Option<u32> {
+        let bytes = self.value(entry)?;
+        let reader = Ifd { data: bytes, little_endian: self.little_endian };
+        match entry.kind {
+            3 => reader.u16(0).map(u32::from),
+            4 => reader.u32(0),
+            _ => None,
+        }
+    }
+
+    fn rationals(&self, entry: &Entry) -> Vec<f64> {
+        let Some(bytes) = self.value(entry).filter(|_| entry.kind == 5) else {
+            return Vec::new();
+        };
+        let reader = Ifd { data: bytes, little_endian: self.little_endian };
+        (0..entry.count as usize)
+            .filter_map(|i| {
+                let num = reader.u32(i * 8)?;
+                let denom = reader.u32(i * 8 + 4)?;
+                // 0/0 marks an unknown value
+                Some(if denom == 0 { 0.0 } else { num as f64 / denom as f64 })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,617 @@
+//! EXIF data written to output files
+//!
+//! The EXIF block of the original is copied to the output, which also
+//! delivers the GPS position, serial numbers and maker note. A
+//! [`MetadataPolicy`] selects what is kept; the caption is always rendered
+//! from the full data of the original.
+//!
+//! The copied block is updated to describe the bordered image: pixel
+//! dimensions, thumbnail, `Software` and modification date. With the
+//! default policy the block is patched in place, so that every other byte
+//! keeps its offset: maker notes whose offsets are relative to the EXIF
+//! TIFF header (Canon, Sony) stay readable, and fields of unknown type are
+//! kept. Policies removing fields, and originals whose EXIF data shares
+//! its structure with the pixels (TIFF and raw files), rebuild the block
+//! from the fields kept.
+
+use crate::PhotoBorderError;
+use chrono::Local;
+use crate::formats;
+use exif::{Context, Exif, Field, In, Reader, Rational, Tag, Value};
+use image::imageops::FilterType;
+use image::DynamicImage;
+use std::io::Cursor;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Value of the `Software` tag of output files
+const SOFTWARE: &str = "Schnaps-Shot";
+
+/// Largest thumbnail size, as recommended by the EXIF specification
+const THUMBNAIL_WIDTH: u32 = 160;
+const THUMBNAIL_HEIGHT: u32 = 120;
+
+/// JPEG quality of regenerated thumbnails
+const THUMBNAIL_QUALITY: u8 = 80;
+
+/// Tags describing the image itself, replaced with values for the output
+const REPLACED_TAGS: &[Tag] = &[
+    Tag::PixelXDimension,
+    Tag::PixelYDimension,
+    Tag::Software,
+    Tag::DateTime,
+    Tag::OffsetTime,
+];
+
+/// Tags of the print resolution, replaced in print mode
+pub const RESOLUTION_TAGS: &[Tag] = &[Tag::XResolution, Tag::YResolution, Tag::ResolutionUnit];
+
+/// Tags describing how the pixels of a TIFF original are stored, which
+/// share the primary IFD with its EXIF data
+pub const IMAGE_STRUCTURE_TAGS: &[Tag] = &[
+    Tag(Context::Tiff, 254), // NewSubfileType
+    Tag::ImageWidth,
+    Tag::ImageLength,
+    Tag::BitsPerSample,
+    Tag::Compression,
+    Tag::PhotometricInterpretation,
+    Tag::SamplesPerPixel,
+    Tag::RowsPerStrip,
+    Tag::PlanarConfiguration,
+    Tag(Context::Tiff, 317), // Predictor
+    Tag(Context::Tiff, 322), // TileWidth
+    Tag(Context::Tiff, 323), // TileLength
+    Tag(Context::Tiff, 330), // SubIFDs
+    Tag(Context::Tiff, 338), // ExtraSamples
+    Tag(Context::Tiff, 339), // SampleFormat
+    formats::TIFF_ICC_PROFILE,
+];
+
+/// Tags of the pixel data of TIFF originals
+const TAG_STRIP_OFFSETS: Tag = Tag(Context::Tiff, 273);
+const TAG_TILE_OFFSETS: Tag = Tag(Context::Tiff, 324);
+
+/// Tags kept by [`MetadataPolicy::Minimal`]: camera, exposure and rights
+const MINIMAL_TAGS: &[Tag] = &[
+    Tag::Make,
+    Tag::Model,
+    Tag::Orientation,
+    Tag::Artist,
+    Tag::Copyright,
+    Tag::DateTimeOriginal,
+    Tag::OffsetTimeOriginal,
+    Tag::ExposureTime,
+    Tag::FNumber,
+    Tag::ExposureProgram,
+    Tag::PhotographicSensitivity,
+    Tag::ExposureBiasValue,
+    Tag::MeteringMode,
+    Tag::Flash,
+    Tag::FocalLength,
+    Tag::FocalLengthIn35mmFilm,
+    Tag::ExposureMode,
+    Tag::WhiteBalance,
+    Tag::ColorSpace,
+    Tag::LensMake,
+    Tag::LensModel,
+    Tag::LensSpecification,
+];
+
+/// Tags identifying the camera, lens or owner
+const SERIAL_TAGS: &[Tag] = &[
+    Tag::BodySerialNumber,
+    Tag::LensSerialNumber,
+    Tag::CameraOwnerName,
+    Tag::ImageUniqueID,
+];
+
+/// Group of EXIF fields that can be removed from output files
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetadataGroup {
+    /// GPS position, altitude and timestamps
+    Gps,
+    /// Body and lens serial numbers, owner name, unique image ID
+    Serials,
+    /// Proprietary maker note block
+    MakerNote,
+    /// Embedded thumbnail image (IFD1)
+    Thumbnail,
+}
+
+impl FromStr for MetadataGroup {
+    type Err = &'static str;
+
+    /// Parses a group name ("gps", "serials", "makernote" or "thumbnail")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "gps" => Ok(MetadataGroup::Gps),
+            "serial" | "serials" => Ok(MetadataGroup::Serials),
+            "makernote" | "maker-note" => Ok(MetadataGroup::MakerNote),
+            "thumbnail" => Ok(MetadataGroup::Thumbnail),
+            _ => Err("Invalid metadata group (expected gps, serials, makernote or thumbnail)"),
+        }
+    }
+}
+
+impl MetadataGroup {
+    /// Whether a field belongs to the group
+    fn contains(self, field: &Field) -> bool {
+        match self {
+            MetadataGroup::Gps => matches!(field.tag, Tag(exif::Context::Gps, _)),
+            MetadataGroup::Serials => SERIAL_TAGS.contains(&field.tag),
+            MetadataGroup::MakerNote => field.tag == Tag::MakerNote,
+            MetadataGroup::Thumbnail => field.ifd_num == In::THUMBNAIL,
+        }
+    }
+}
+
+/// What EXIF data is written to output files
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum MetadataPolicy {
+    /// Copy all the EXIF data of the original
+    #[default]
+    Keep,
+    /// Write no EXIF data
+    Strip,
+    /// Keep only camera, lens, exposure settings, date and copyright
+    Minimal,
+    /// Keep everything except the listed groups
+    Custom(Vec<MetadataGroup>),
+}
+
+impl MetadataPolicy {
+    /// Builds a policy from the command-line options
+    ///
+    /// # Arguments
+    /// * `name` - "keep", "strip", "minimal" or "custom"
+    /// * `remove` - Comma-separated groups removed by the custom policy;
+    ///   giving them implies "custom"
+    pub fn parse(name: &str, remove: Option<&str>) -> Result<Self, PhotoBorderError> {
+        let config_error = |e: &str| PhotoBorderError::ConfigError(e.to_string());
+
+        match (name.to_lowercase().as_str(), remove) {
+            ("custom", Some(groups)) | ("keep", Some(groups)) => {
+                let groups = groups.split(',')
+                    .map(MetadataGroup::from_str)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(config_error)?;
+                Ok(MetadataPolicy::Custom(groups))
+            }
+            ("custom", None) => Err(config_error("--metadata custom requires --metadata-remove")),
+            (_, Some(_)) => Err(config_error("--metadata-remove can only be used with --metadata custom")),
+            ("keep", None) => Ok(MetadataPolicy::Keep),
+            ("strip", None) => Ok(MetadataPolicy::Strip),
+            ("minimal", None) => Ok(MetadataPolicy::Minimal),
+            _ => Err(config_error("Invalid metadata policy (expected keep, strip, minimal or custom)")),
+        }
+    }
+
+    /// Whether a field of the original is written to the output
+    fn keeps(&self, field: &Field) -> bool {
+        match self {
+            MetadataPolicy::Keep => true,
+            MetadataPolicy::Strip => false,
+            MetadataPolicy::Minimal => field.ifd_num == In::PRIMARY && MINIMAL_TAGS.contains(&field.tag),
+            MetadataPolicy::Custom(groups) => !groups.iter().any(|group| group.contains(field)),
+        }
+    }
+}
+
+/// Reads the EXIF block of an original, whatever its container
+///
+/// # Returns
+/// * `Ok(block)` with the TIFF structure holding the EXIF data (the whole
+///   file for TIFF originals)
+/// * `Err(PhotoBorderError)` if the file has no EXIF data
+pub fn read_exif_block(path: &Path) -> Result<Vec<u8>, PhotoBorderError> {
+    Ok(formats::read_exif(path)?.buf().to_vec())
+}
+
+/// Builds the EXIF block of an output file from the one of the original
+///
+/// # Arguments
+/// * `policy` - Metadata policy
+/// * `original` - EXIF block (TIFF structure, without the "Exif\0\0" prefix)
+/// * `output` - Bordered image, whose dimensions and thumbnail are written
+///
+/// # Returns
+/// * `Ok(Some(block))` with the EXIF block to write
+/// * `Ok(None)` if nothing is left to write
+/// * `Err(PhotoBorderError)` if the original cannot be parsed or rewritten
+///
+/// # Updated Fields
+/// - `PixelXDimension` / `PixelYDimension`: size of the bordered image
+/// - `XResolution` / `YResolution`: print resolution, when given
+/// - IFD1 thumbnail: regenerated from the bordered image when the
+///   original has one and the policy keeps it
+/// - `Software`: "Schnaps-Shot"
+/// - `DateTime` (modification date) and `OffsetTime`: now
+pub fn rewrite_exif(
+    policy: &MetadataPolicy,
+    original: &[u8],
+    output: &DynamicImage,
+    dpi: Option<u32>,
+) -> Result<Option<Vec<u8>>, PhotoBorderError> {
+    if *policy == MetadataPolicy::Strip {
+        return Ok(None);
+    }
+    if *policy == MetadataPolicy::Keep {
+        if let Some(block) = patch_exif(original, output, dpi)? {
+            return Ok(Some(block));
+        }
+    }
+
+    let exif = Reader::new().read_raw(original.to_vec())?;
+    let keep_thumbnail = thumbnail(&exif).is_some()
+        && exif.fields().any(|field| field.ifd_num == In::THUMBNAIL && policy.keeps(field));
+
+    let mut fields: Vec<&Field> = exif.fields()
+        // The thumbnail IFD is rebuilt from scratch
+        .filter(|field| field.ifd_num == In::PRIMARY && policy.keeps(field))
+        .filter(|field| !REPLACED_TAGS.contains(&field.tag))
+        .filter(|field| dpi.is_none() || !RESOLUTION_TAGS.contains(&field.tag))
+        .filter(|field| !IMAGE_STRUCTURE_TAGS.contains(&field.tag))
+        // Fields of unknown type cannot be written back
+        .filter(|field| !matches!(field.value, Value::Unknown(..)))
+        .collect();
+
+    // The writer needs a non-empty primary IFD
+    if fields.is_empty() {
+        return Ok(None);
+    }
+
+    let updated = updated_fields(output, dpi);
+    fields.extend(&updated);
+
+    let (thumbnail_fields, thumbnail_jpeg) = if keep_thumbnail {
+        (thumbnail_fields(), Some(encode_thumbnail(output)?))
+    } else {
+        (Vec::new(), None)
+    };
+    fields.extend(&thumbnail_fields);
+
+    write_exif(&fields, thumbnail_jpeg.as_deref(), exif.little_endian())
+}
+
+/// Updates an EXIF block in place for the output image
+///
+/// Changed values are written over the old ones when they fit and
+/// appended otherwise; an IFD gaining fields is copied to the end of the
+/// block. The thumbnail is regenerated, or unlinked if it is not a JPEG.
+///
+/// # Returns
+/// * `Ok(Some(block))` with the patched block
+/// * `Ok(None)` if the block holds an image or has no EXIF IFD, and must
+///   be rebuilt
+/// * `Err(PhotoBorderError)` if the thumbnail cannot be encoded
+fn patch_exif(original: &[u8], output: &DynamicImage, dpi: Option<u32>) -> Result<Option<Vec<u8>>, PhotoBorderError> {
+    let little_endian = match original.get(0..2) {
+        Some(b"II") => true,
+        Some(b"MM") => false,
+        _ => return Ok(None),
+    };
+    let mut block = TiffPatcher { data: original.to_vec(), little_endian };
+    let Some(primary) = block.u32(4).map(|offset| offset as usize) else {
+        return Ok(None);
+    };
+    let Some(entries) = block.entries(primary) else {
+        return Ok(None);
+    };
+    let position = |tag: Tag| entries.iter().find(|(number, _)| *number == tag.number()).map(|(_, pos)| *pos);
+
+    // The pixels of TIFF and raw originals would be copied along
+    if position(TAG_STRIP_OFFSETS).is_some() || position(TAG_TILE_OFFSETS).is_some() {
+        return Ok(None);
+    }
+    let Some(exif_pointer) = position(Tag::ExifIFDPointer) else {
+        return Ok(None);
+    };
+
+    let now = Local::now();
+    let ascii = |text: String| Value::Ascii(vec![text.into_bytes()]);
+    let patched = (|| {
+        // EXIF IFD, then the primary IFD pointing to it
+        let exif_ifd = block.u32(exif_pointer + 8)? as usize;
+        let exif_ifd = block.set_entries(exif_ifd, &[
+            (Tag::PixelXDimension, Value::Long(vec![output.width()])),
+            (Tag::PixelYDimension, Value::Long(vec![output.height()])),
+            (Tag::OffsetTime, ascii(now.format("%:z").to_string())),
+        ])?;
+        block.put_u32(exif_pointer + 8, exif_ifd as u32);
+
+        let mut values = vec![
+            (Tag::Software, ascii(SOFTWARE.to_string())),
+            (Tag::DateTime, ascii(now.format("%Y:%m:%d %H:%M:%S").to_string())),
+        ];
+        if position(Tag::ImageWidth).is_some() {
+            values.push((Tag::ImageWidth, Value::Long(vec![output.width()])));
+            values.push((Tag::ImageLength, Value::Long(vec![output.height()])));
+        }
+        if let Some(dpi) = dpi {
+            let dpi = Value::Rational(vec![Rational { num: dpi, denom: 1 }]);
+            values.push((Tag::XResolution, dpi.clone()));
+            values.push((Tag::YResolution, dpi));
+            // 2 = inches
+            values.push((Tag::ResolutionUnit, Value::Short(vec![2])));
+        }
+        let primary = block.set_entries(primary, &values)?;
+        block.put_u32(4, primary as u32);
+        Some(primary)
+    })();
+    let Some(primary) = patched else {
+        return Ok(None);
+    };
+
+    // Thumbnail IFD
+    let next_pos = block.next_ifd_position(primary);
+    let thumbnail_ifd = next_pos.and_then(|pos| block.u32(pos)).unwrap_or(0) as usize;
+    if thumbnail_ifd != 0 {
+        let thumbnail_entries = block.entries(thumbnail_ifd).unwrap_or_default();
+        let value = |tag: Tag| {
+            thumbnail_entries.iter()
+                .find(|(number, _)| *number == tag.number())
+                .and_then(|(_, pos)| block.u32(pos + 8))
+        };
+        let patched = match (value(Tag::JPEGInterchangeFormat), value(Tag::JPEGInterchangeFormatLength)) {
+            (Some(offset), Some(length)) => {
+                let jpeg = encode_thumbnail(output)?;
+                // Over the old thumbnail if the new one fits
+                let offset = if jpeg.len() <= length as usize {
+                    block.put(offset as usize, &jpeg).map(|_| offset)
+                } else {
+                    block.append(&jpeg)
+                };
+                offset.and_then(|offset| block.set_entries(thumbnail_ifd, &[
+                    (Tag::JPEGInterchangeFormat, Value::Long(vec![offset])),
+                    (Tag::JPEGInterchangeFormatLength, Value::Long(vec![jpeg.len() as u32])),
+                ]))
+            }
+            _ => None,
+        };
+        if let Some(next_pos) = next_pos {
+            // A thumbnail that cannot be regenerated would show the photo
+            // without its border
+            block.put_u32(next_pos, patched.unwrap_or(0) as u32);
+        }
+    }
+
+    Ok(Some(block.data))
+}
+
+/// Fields describing the output image
+fn updated_fields(output: &DynamicImage, dpi: Option<u32>) -> Vec<Field> {
+    let now = Local::now();
+    let field = |tag: Tag, value: Value| Field { tag, ifd_num: In::PRIMARY, value };
+    let ascii = |text: String| Value::Ascii(vec![text.into_bytes()]);
+
+    let mut fields = vec![
+        field(Tag::PixelXDimension, Value::Long(vec![output.width()])),
+        field(Tag::PixelYDimension, Value::Long(vec![output.height()])),
+        field(Tag::Software, ascii(SOFTWARE.to_string())),
+        field(Tag::DateTime, ascii(now.format("%Y:%m:%d %H:%M:%S").to_string())),
+        field(Tag::OffsetTime, ascii(now.format("%:z").to_string())),
+    ];
+    if let Some(dpi) = dpi {
+        let dpi = Value::Rational(vec![Rational { num: dpi, denom: 1 }]);
+        fields.push(field(Tag::XResolution, dpi.clone()));
+        fields.push(field(Tag::YResolution, dpi));
+        // 2 = inches
+        fields.push(field(Tag::ResolutionUnit, Value::Short(vec![2])));
+    }
+    fields
+}
+
+/// IFD1 fields of a JPEG thumbnail (compression and resolution)
+fn thumbnail_fields() -> Vec<Field> {
+    let field = |tag: Tag, value: Value| Field { tag, ifd_num: In::THUMBNAIL, value };
+    let dpi = Value::Rational(vec![Rational { num: 72, denom: 1 }]);
+
+    vec![
+        // 6 = JPEG compression
+        field(Tag::Compression, Value::Short(vec![6])),
+        field(Tag::XResolution, dpi.clone()),
+        field(Tag::YResolution, dpi),
+        // 2 = inches
+        field(Tag::ResolutionUnit, Value::Short(vec![2])),
+    ]
+}
+
+/// Encodes a JPEG thumbnail of the bordered image
+fn encode_thumbnail(output: &DynamicImage) -> Result<Vec<u8>, PhotoBorderError> {
+    let thumbnail = output.resize(THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT, FilterType::Triangle).to_rgb8();
+
+    let mut buffer = Cursor::new(Vec::new());
+    thumbnail.write_to(&mut buffer, image::ImageOutputFormat::Jpeg(THUMBNAIL_QUALITY))?;
+    Ok(buffer.into_inner())
+}
+
+/// Encodes fields (and an optional JPEG thumbnail) as an EXIF block
+///
+/// Maker notes are copied as-is, at a new position in the block: those
+/// whose offsets are relative to the EXIF TIFF header (Canon, Sony) then
+/// point to the wrong bytes and may not be readable. Only
+/// the policies removing fields, and TIFF and raw originals, go through
+/// this rebuild.
+fn write_exif(fields: &[&Field], thumbnail: Option<&[u8]>, little_endian: bool) -> Result<Option<Vec<u8>>, PhotoBorderError> {
+    let mut writer = exif::experimental::Writer::new();
+    for field in fields {
+        writer.push_field(field);
+    }
+    if let Some(thumbnail) = thumbnail {
+        writer.set_jpeg(thumbnail, In::THUMBNAIL);
+    }
+
+    let mut buffer = Cursor::new(Vec::new());
+    writer.write(&mut buffer, little_endian)?;
+    Ok(Some(buffer.into_inner()))
+}
+
+/// JPEG thumbnail stored in IFD1, if any
+pub fn thumbnail(exif: &Exif) -> Option<&[u8]> {
+    let offset = exif.get_field(Tag::JPEGInterchangeFormat, In::THUMBNAIL)?.value.get_uint(0)? as usize;
+    let length = exif.get_field(Tag::JPEGInterchangeFormatLength, In::THUMBNAIL)?.value.get_uint(0)? as usize;
+    exif.buf().get(offset..offset + length)
+}
+
+/// EXIF block (TIFF structure) edited in place
+///
+/// Values are rewritten where they are and appended when they grow, so
+/// that data referenced by offsets elsewhere in the block stays put.
+struct TiffPatcher {
+    data: Vec<u8>,
+    little_endian: bool,
+}
+
+impl TiffPatcher {
+    fn u16(&self, pos: usize) -> Option<u16> {
+        let bytes: [u8; 2] = self.data.get(pos..pos + 2)?.try_into().ok()?;
+        Some(if self.little_endian { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) })
+    }
+
+    fn u32(&self, pos: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(pos..pos + 4)?.try_into().ok()?;
+        Some(if self.little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    }
+
+    fn u16_bytes(&self, value: u16) -> [u8; 2] {
+        if self.little_endian { value.to_le_bytes() } else { value.to_be_bytes() }
+    }
+
+    fn u32_bytes(&self, value: u32) -> [u8; 4] {
+        if self.little_endian { value.to_le_bytes() } else { value.to_be_bytes() }
+    }
+
+    /// Overwrites bytes, if they are inside the block
+    fn put(&mut self, pos: usize, bytes: &[u8]) -> Option<()> {
+        self.data.get_mut(pos..pos + bytes.len())?.copy_from_slice(bytes);
+        Some(())
+    }
+
+    fn put_u32(&mut self, pos: usize, value: u32) {
+        let bytes = self.u32_bytes(value);
+        self.put(pos, &bytes);
+    }
+
+    /// Adds bytes at the end of the block, at an even offset
+    fn append(&mut self, bytes: &[u8]) -> Option<u32> {
+        if self.data.len() % 2 == 1 {
+            self.data.push(0);
+        }
+        let offset = u32::try_from(self.data.len()).ok()?;
+        self.data.extend_from_slice(bytes);
+        Some(offset)
+    }
+
+    /// Entries of the IFD at `offset`, as (tag, position of the entry)
+    fn entries(&self, offset: usize) -> Option<Vec<(u16, usize)>> {
+        let count = self.u16(offset)? as usize;
+        if offset + 2 + count * 12 + 4 > self.data.len() {
+            return None;
+        }
+        (0..count)
+            .map(|i| {
+                let pos = offset + 2 + i * 12;
+                Some((self.u16(pos)?, pos))
+            })
+            .collect()
+    }
+
+    /// Position of the offset of the next IFD
+    fn next_ifd_position(&self, offset: usize) -> Option<usize> {
+        Some(offset + 2 + self.u16(offset)? as usize * 12)
+    }
+
+    /// Encodes a value as (type, count, bytes)
+    fn encode(&self, value: &Value) -> Option<(u16, u32, Vec<u8>)> {
+        let (kind, count, bytes): (u16, usize, Vec<u8>) = match value {
+            Value::Ascii(texts) => {
+                let mut bytes = texts.first()?.clone();
+                bytes.push(0);
+                (2, bytes.len(), bytes)
+            }
+            Value::Short(values) => (3, values.len(), values.iter().flat_map(|v| self.u16_bytes(*v)).collect()),
+            Value::Long(values) => (4, values.len(), values.iter().flat_map(|v| self.u32_bytes(*v)).collect()),
+            Value::Rational(values) => (
+                5,
+                values.len(),
+                values.iter().flat_map(|v| [self.u32_bytes(v.num), self.u32_bytes(v.denom)].concat()).collect(),
+            ),
+            _ => return None,
+        };
+        Some((kind, count as u32, bytes))
+    }
+
+    /// Writes the value of an existing entry, in its old place when it fits
+    fn write_entry(&mut self, pos: usize, kind: u16, count: u32, bytes: &[u8]) -> Option<()> {
+        let old_unit = match self.u16(pos + 2)? {
+            1 | 2 | 6 | 7 => 1,
+            3 | 8 => 2,
+            4 | 9 | 11 | 13 => 4,
+            5 | 10 | 12 => 8,
+            _ => 0,
+        };
+        let old_size = old_unit * self.u32(pos + 4)? as usize;
+
+        if bytes.len() <= 4 {
+            let mut inline = [0; 4];
+            inline[..bytes.len()].copy_from_slice(bytes);
+            self.put(pos + 8, &inline)?;
+        } else if old_size > 4 && bytes.len() <= old_size {
+            let offset = self.u32(pos + 8)? as usize;
+            self.put(offset, bytes)?;
+        } else {
+            let offset = self.append(bytes)?;
+            self.put_u32(pos + 8, offset);
+        }
+        let kind = self.u16_bytes(kind);
+        self.put(pos + 2, &kind)?;
+        self.put_u32(pos + 4, count);
+        Some(())
+    }
+
+    /// Sets values in the IFD at `offset`
+    ///
+    /// # Returns
+    /// The offset of the IFD, which is copied to the end of the block when
+    /// fields are added
+    fn set_entries(&mut self, offset: usize, values: &[(Tag, Value)]) -> Option<usize> {
+        let entries = self.entries(offset)?;
+        let mut added = Vec::new();
+        for (tag, value) in values {
+            let (kind, count, bytes) = self.encode(value)?;
+            match entries.iter().find(|(number, _)| *number == tag.number()) {
+                Some(&(_, pos)) => self.write_entry(pos, kind, count, &bytes)?,
+                None => added.push((tag.number(), kind, count, bytes)),
+            }
+        }
+        if added.is_empty() {
+            return Some(offset);
+        }
+
+        // Copy of the IFD with the new entries, in tag order
+        let mut raw: Vec<(u16, Vec<u8>)> = entries.iter()
+            .map(|&(number, pos)| (number, self.data[pos..pos + 12].to_vec()))
+            .collect();
+        for (number, kind, count, bytes) in added {
+            let value = if bytes.len() <= 4 {
+                let mut inline = bytes;
+                inline.resize(4, 0);
+                inline
+            } else {
+                let offset = self.append(&bytes)?;
+                self.u32_bytes(offset).to_vec()
+            };
+            let entry = [&self.u16_bytes(number)[..], &self.u16_bytes(kind), &self.u32_bytes(count), &value].concat();
+            raw.push((number, entry));
+        }
+        raw.sort_by_key(|(number, _)| *number);
+
+        let next = self.u32(self.next_ifd_position(offset)?)?;
+        let mut ifd = self.u16_bytes(raw.len() as u16).to_vec();
+        for (_, entry) in &raw {
+            ifd.extend_from_slice(entry);
+        }
+        ifd.extend_from_slice(&self.u32_bytes(next));
+        self.append(&ifd).map(|offset| offset as usize)
+    }
+}
@@ -0,0 +1,112 @@
+//! Manual metadata overrides
+//!
+//! Scans and film photos carry no EXIF data, and digital files sometimes
+//! carry wrong data (adapted lens, unset clock). Fields can be filled in
+//! or replaced from:
+//!
+//! - A sidecar file next to the photo, named after it with an extra
+//!   `.json`, `.yaml` or `.yml` extension (`photo.jpg.json`), holding a
+//!   map of field names to values:
+//!
+//!   ```yaml
+//!   camera: Leica M6
+//!   lens: Summicron 35mm f/2
+//!   iso: 400
+//!   ```
+//!
+//! - `--set field=value` on the command line, which takes precedence
+//!   over the sidecar file
+//!
+//! Field names are those listed by `schnapsshot exif`.
+
+use crate::{ExifData, PhotoBorderError};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Sidecar extensions, appended to the photo file name, in lookup order
+const SIDECAR_EXTENSIONS: [&str; 3] = ["json", "yaml", "yml"];
+
+/// A value of a sidecar file
+///
+/// Numbers are accepted so that `iso: 400` does not need quotes; `null`
+/// clears the field.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum SidecarValue {
+    Text(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+impl SidecarValue {
+    fn into_string(self) -> String {
+        match self {
+            SidecarValue::Text(text) => text,
+            SidecarValue::Integer(value) => value.to_string(),
+            SidecarValue::Float(value) => value.to_string(),
+            SidecarValue::Bool(value) => value.to_string(),
+        }
+    }
+}
+
+/// Parses a `field=value` command-line override
+///
+/// # Returns
+/// * `Ok((field, value))` if the field is known
+/// * `Err(PhotoBorderError::ConfigError)` if `=` is missing or the field is unknown
+pub fn parse_assignment(assignment: &str) -> Result<(String, String), PhotoBorderError> {
+    let (name, value) = assignment.split_once('=').ok_or_else(|| {
+        PhotoBorderError::ConfigError(format!("Expected field=value, got '{}'", assignment))
+    })?;
+    let name = name.trim();
+
+    if !ExifData::is_field(name) {
+        return Err(PhotoBorderError::ConfigError(format!("Unknown field '{}'", name)));
+    }
+
+    Ok((name.to_string(), value.to_string()))
+}
+
+/// Path of the sidecar file of a photo, if one exists
+pub fn sidecar_path(image_path: &Path) -> Option<PathBuf> {
+    let file_name = image_path.file_name()?.to_str()?;
+
+    SIDECAR_EXTENSIONS.iter()
+        .map(|ext| image_path.with_file_name(format!("{}.{}", file_name, ext)))
+        .find(|path| path.is_file())
+}
+
+/// Reads the sidecar file of a photo
+///
+/// # Returns
+/// * `Ok(Some(fields))` with (field, value) pairs; an empty value clears the field
+/// * `Ok(None)` if the photo has no sidecar file
+/// * `Err(PhotoBorderError)` if the sidecar cannot be read, parsed, or
+///   names an unknown field
+pub fn read_sidecar(image_path: &Path) -> Result<Option<Vec<(String, String)>>, PhotoBorderError> {
+    let Some(path) = sidecar_path(image_path) else {
+        return Ok(None);
+    };
+    let invalid = |e: String| PhotoBorderError::ConfigError(format!("Invalid sidecar {}: {}", path.display(), e));
+
+    let content = fs::read_to_string(&path)?;
+    let is_json = path.extension().map(|ext| ext == "json").unwrap_or(false);
+    let values: BTreeMap<String, Option<SidecarValue>> = if is_json {
+        serde_json::from_str(&content).map_err(|e| invalid(e.to_string()))?
+    } else {
+        serde_yaml::from_str(&content).map_err(|e| invalid(e.to_string()))?
+    };
+
+    values.into_iter()
+        .map(|(name, value)| {
+            if !ExifData::is_field(&name) {
+                return Err(invalid(format!("unknown field '{}'", name)));
+            }
+            Ok((name, value.map(SidecarValue::into_string).unwrap_or_default()))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Some)
+}
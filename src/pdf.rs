@@ -0,0 +1,675 @@
+//! PDF documents
+//!
+//! A small PDF 1.4 writer for proof sheets: one bordered photo per page,
+//! plus an optional cover page listing the set. Pages hold filled
+//! rectangles, images and single lines of text; nothing else is needed.
+//!
+//! - Photos are embedded as JPEG (`DCTDecode`), with their ICC profile
+//!   as an `ICCBased` colour space; logos are compressed RGB with a soft
+//!   mask for their alpha channel
+//! - Text is set in the embedded DejaVu Sans fonts, embedded whole as
+//!   TrueType (`CIDFontType2`, glyph IDs as character codes) with a
+//!   `ToUnicode` map so that it can be searched and copied
+//! - Content streams and fonts are compressed with zlib (`FlateDecode`)
+//!
+//! Coordinates are given in points from the top-left corner of the page.
+
+use crate::print::PaperSize;
+use crate::PhotoBorderError;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use image::codecs::jpeg::JpegEncoder;
+use image::{Rgb, RgbImage, RgbaImage};
+use rusttype::{Font, Scale};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::str::FromStr;
+
+/// Points per millimetre
+pub const POINTS_PER_MM: f32 = 72.0 / 25.4;
+
+/// Object numbers of the catalog and the page tree
+const CATALOG_ID: usize = 1;
+const PAGES_ID: usize = 2;
+
+/// Quality of the JPEG compression of photos
+const JPEG_QUALITY: u8 = 95;
+
+/// Glyph mappings per `beginbfchar` block (limit of the CMap format)
+const BFCHAR_BLOCK: usize = 100;
+
+/// Embedded font faces
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FontStyle {
+    Regular,
+    Bold,
+}
+
+impl FontStyle {
+    /// Index of the face in the document fonts
+    fn index(self) -> usize {
+        match self {
+            FontStyle::Regular => 0,
+            FontStyle::Bold => 1,
+        }
+    }
+
+    fn data(self) -> &'static [u8] {
+        match self {
+            FontStyle::Regular => include_bytes!("../fonts/DejaVuSans.ttf"),
+            FontStyle::Bold => include_bytes!("../fonts/DejaVuSans-Bold.ttf"),
+        }
+    }
+
+    fn base_name(self) -> &'static str {
+        match self {
+            FontStyle::Regular => "DejaVuSans",
+            FontStyle::Bold => "DejaVuSans-Bold",
+        }
+    }
+
+    fn font(self) -> Font<'static> {
+        Font::try_from_bytes(self.data()).expect("embedded font is valid")
+    }
+}
+
+/// Width of a line of text in points
+///
+/// # Arguments
+/// * `style` - Font face
+/// * `text` - Text
+/// * `size` - Font size in points
+pub fn text_width(style: FontStyle, text: &str, size: f32) -> f32 {
+    let font = style.font();
+    let em = em_scale(&font, size);
+    text.chars().map(|c| font.glyph(c).scaled(em).h_metrics().advance_width).sum()
+}
+
+/// Font size in points of text drawn by rusttype at a given scale
+///
+/// # Arguments
+/// * `style` - Font face
+/// * `scale` - rusttype scale, in the same unit as the result
+pub fn font_size(style: FontStyle, scale: f32) -> f32 {
+    let font = style.font();
+    let metrics = font.v_metrics_unscaled();
+    scale * font.units_per_em() as f32 / (metrics.ascent - metrics.descent)
+}
+
+/// Height of the ascender above the baseline, in points
+pub fn ascent(style: FontStyle, size: f32) -> f32 {
+    let font = style.font();
+    font.v_metrics_unscaled().ascent * size / font.units_per_em() as f32
+}
+
+/// Shortens a line of text to a width, ending it with an ellipsis
+fn truncate(style: FontStyle, text: &str, size: f32, max_width: f32) -> String {
+    if text_width(style, text, size) <= max_width {
+        return text.to_string();
+    }
+    let mut chars: Vec<char> = text.chars().collect();
+    while !chars.is_empty() {
+        chars.pop();
+        let shortened = format!("{}…", chars.iter().collect::<String>().trim_end());
+        if text_width(style, &shortened, size) <= max_width {
+            return shortened;
+        }
+    }
+    String::new()
+}
+
+/// Lays out the cover pages: the title, the number of photos and the
+/// date, then one line per photo with its file name and caption
+///
+/// # Arguments
+/// * `title` - Document title
+/// * `paper` - Paper size, used in portrait orientation
+/// * `entries` - File name and caption of each photo, in page order
+///
+/// # Returns
+/// As many pages as needed to list every photo
+pub fn cover_pages(title: &str, paper: PaperSize, entries: &[(String, Option<String>)]) -> Vec<Page> {
+    let page_width = paper.width.min(paper.height) * POINTS_PER_MM;
+    let page_height = paper.width.max(paper.height) * POINTS_PER_MM;
+    let line_width = page_width - 2.0 * COVER_MARGIN;
+
+    let mut pages = Vec::new();
+    let mut page = Page::new(page_width, page_height);
+    let mut y = COVER_MARGIN + ascent(FontStyle::Bold, COVER_TITLE_SIZE);
+    page.text(COVER_MARGIN, y, COVER_TITLE_SIZE, FontStyle::Bold, COVER_TITLE_COLOR,
+        &truncate(FontStyle::Bold, title, COVER_TITLE_SIZE, line_width));
+    y += COVER_TITLE_SIZE;
+    let summary = format!(
+        "{} photo{}, {}",
+        entries.len(),
+        if entries.len() == 1 { "" } else { "s" },
+        chrono::Local::now().format("%Y-%m-%d"),
+    );
+    page.text(COVER_MARGIN, y, COVER_TEXT_SIZE, FontStyle::Regular, COVER_TEXT_COLOR, &summary);
+    y += 2.0 * COVER_LINE_HEIGHT;
+
+    // Numbers right-aligned in a column wide enough for the largest
+    let widest_number = format!("{}.", "0".repeat(entries.len().to_string().len()));
+    let number_width = text_width(FontStyle::Regular, &widest_number, COVER_TEXT_SIZE);
+    for (index, (name, caption)) in entries.iter().enumerate() {
+        if y > page_height - COVER_MARGIN {
+            pages.push(page);
+            page = Page::new(page_width, page_height);
+            y = COVER_MARGIN + ascent(FontStyle::Regular, COVER_TEXT_SIZE);
+        }
+
+        let number = format!("{}.", index + 1);
+        let number_x = COVER_MARGIN + number_width - text_width(FontStyle::Regular, &number, COVER_TEXT_SIZE);
+        page.text(number_x, y, COVER_TEXT_SIZE, FontStyle::Regular, COVER_TEXT_COLOR, &number);
+
+        let x = COVER_MARGIN + number_width + COVER_TEXT_SIZE;
+        let name = truncate(FontStyle::Bold, name, COVER_TEXT_SIZE, COVER_MARGIN + line_width - x);
+        page.text(x, y, COVER_TEXT_SIZE, FontStyle::Bold, COVER_TITLE_COLOR, &name);
+
+        if let Some(caption) = caption.as_deref().filter(|caption| !caption.is_empty()) {
+            let x = x + text_width(FontStyle::Bold, &name, COVER_TEXT_SIZE) + COVER_TEXT_SIZE;
+            let caption = truncate(FontStyle::Regular, caption, COVER_TEXT_SIZE, COVER_MARGIN + line_width - x);
+            page.text(x, y, COVER_TEXT_SIZE, FontStyle::Regular, COVER_TEXT_COLOR, &caption);
+        }
+        y += COVER_LINE_HEIGHT;
+    }
+    pages.push(page);
+    pages
+}
+
+/// rusttype scale of a font size (rusttype scales the height from the
+/// descender to the ascender, PDF sizes are em sizes)
+fn em_scale(font: &Font, size: f32) -> Scale {
+    let metrics = font.v_metrics_unscaled();
+    Scale::uniform(size * (metrics.ascent - metrics.descent) / font.units_per_em() as f32)
+}
+
+/// Settings of the PDF output
+#[derive(Debug, Clone)]
+pub struct PdfOptions {
+    /// Page size, turned to the orientation of each photo
+    pub paper: PaperSize,
+    /// Draw the border and text as vector graphics around the original
+    /// photo, instead of embedding the bordered image
+    pub vector: bool,
+    /// Add a cover page listing the photos
+    pub cover: bool,
+    /// Document title, shown on the cover page
+    pub title: Option<String>,
+}
+
+impl PdfOptions {
+    /// Builds the PDF settings from the command-line options
+    ///
+    /// # Arguments
+    /// * `paper` - Page size ("A4", "13x18cm", "8x10in")
+    /// * `vector` - Vector border and text
+    /// * `cover` - Cover page
+    /// * `title` - Document title
+    pub fn parse(paper: &str, vector: bool, cover: bool, title: Option<&str>) -> Result<Self, PhotoBorderError> {
+        let paper = PaperSize::from_str(paper).map_err(|e| PhotoBorderError::ConfigError(e.to_string()))?;
+        Ok(PdfOptions { paper, vector, cover, title: title.map(str::to_string) })
+    }
+}
+
+/// Page margin around the photos, in millimetres
+pub const PAGE_MARGIN: f32 = 10.0;
+
+/// Cover page layout, in points
+const COVER_MARGIN: f32 = 56.0;
+const COVER_TITLE_SIZE: f32 = 20.0;
+const COVER_TEXT_SIZE: f32 = 9.0;
+const COVER_LINE_HEIGHT: f32 = 15.0;
+const COVER_TITLE_COLOR: Rgb<u8> = Rgb([0, 0, 0]);
+const COVER_TEXT_COLOR: Rgb<u8> = Rgb([64, 64, 64]);
+
+/// Position of a frame on its page
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    /// Top-left corner of the frame, in points
+    pub x: f32,
+    pub y: f32,
+    /// Points per pixel of the frame
+    pub scale: f32,
+}
+
+impl Placement {
+    /// Position on the page of a pixel of the frame
+    pub fn point(&self, x: u32, y: u32) -> (f32, f32) {
+        (self.x + x as f32 * self.scale, self.y + y as f32 * self.scale)
+    }
+
+    /// Length in points of a distance in pixels
+    pub fn length(&self, pixels: f32) -> f32 {
+        pixels * self.scale
+    }
+}
+
+/// Image stored in a document
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageId(usize);
+
+/// Drawing operation of a page
+enum Operation {
+    Rect { x: f32, y: f32, width: f32, height: f32, color: Rgb<u8> },
+    Image { image: ImageId, x: f32, y: f32, width: f32, height: f32 },
+    Text { x: f32, y: f32, size: f32, style: FontStyle, color: Rgb<u8>, text: String },
+}
+
+/// Page being composed
+pub struct Page {
+    width: f32,
+    height: f32,
+    operations: Vec<Operation>,
+}
+
+impl Page {
+    /// Creates an empty page
+    ///
+    /// # Arguments
+    /// * `width`, `height` - Page size in points
+    pub fn new(width: f32, height: f32) -> Self {
+        Page { width, height, operations: Vec::new() }
+    }
+
+    /// Creates a page for a frame, turned to its orientation
+    ///
+    /// # Arguments
+    /// * `paper` - Paper size
+    /// * `margin` - Minimum space around the frame, in millimetres
+    /// * `width`, `height` - Size of the frame in pixels
+    ///
+    /// # Returns
+    /// The page and the position of the frame, scaled to fit inside the
+    /// margin and centred
+    pub fn fit(paper: PaperSize, margin: f32, width: u32, height: u32) -> (Self, Placement) {
+        let (short, long) = (paper.width.min(paper.height), paper.width.max(paper.height));
+        let (page_width, page_height) = if width > height { (long, short) } else { (short, long) };
+        let (page_width, page_height) = (page_width * POINTS_PER_MM, page_height * POINTS_PER_MM);
+
+        let margin = margin * POINTS_PER_MM;
+        let scale = ((page_width - 2.0 * margin) / width as f32).min((page_height - 2.0 * margin) / height as f32);
+        let placement = Placement {
+            x: (page_width - width as f32 * scale) / 2.0,
+            y: (page_height - height as f32 * scale) / 2.0,
+            scale,
+        };
+
+        (Page::new(page_width, page_height), placement)
+    }
+
+    /// Fills a rectangle with a colour
+    pub fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Rgb<u8>) {
+        self.operations.push(Operation::Rect { x, y, width, height, color });
+    }
+
+    /// Draws an image stretched over a rectangle
+    pub fn draw_image(&mut self, image: ImageId, x: f32, y: f32, width: f32, height: f32) {
+        self.operations.push(Operation::Image { image, x, y, width, height });
+    }
+
+    /// Draws a line of text
+    ///
+    /// # Arguments
+    /// * `x` - Left edge of the text
+    /// * `y` - Baseline
+    /// * `size` - Font size in points
+    /// * `style` - Font face
+    /// * `color` - Text colour
+    /// * `text` - Text; characters missing from the font are not drawn
+    pub fn text(&mut self, x: f32, y: f32, size: f32, style: FontStyle, color: Rgb<u8>, text: &str) {
+        self.operations.push(Operation::Text { x, y, size, style, color, text: text.to_string() });
+    }
+}
+
+/// Font embedded on first use
+struct EmbeddedFont {
+    /// Object number of the font dictionary, written at the end
+    id: usize,
+    /// Glyphs used in the document, with the character they represent
+    glyphs: BTreeMap<u16, char>,
+}
+
+/// PDF document being built in memory
+pub struct PdfDocument {
+    /// Object bodies, object `n` at index `n - 1`
+    objects: Vec<Vec<u8>>,
+    /// Page objects, in order
+    pages: Vec<usize>,
+    /// Regular and bold fonts
+    fonts: [Option<EmbeddedFont>; 2],
+    /// Document title
+    title: Option<String>,
+}
+
+impl PdfDocument {
+    /// Creates an empty document
+    pub fn new(title: Option<&str>) -> Self {
+        let mut document = PdfDocument {
+            objects: Vec::new(),
+            pages: Vec::new(),
+            fonts: [None, None],
+            title: title.map(str::to_string),
+        };
+        // The catalog and page tree are written last
+        document.reserve();
+        document.reserve();
+        document
+    }
+
+    /// Reserves an object number
+    fn reserve(&mut self) -> usize {
+        self.objects.push(Vec::new());
+        self.objects.len()
+    }
+
+    /// Adds an object, returning its number
+    fn add_object(&mut self, body: Vec<u8>) -> usize {
+        self.objects.push(body);
+        self.objects.len()
+    }
+
+    /// Adds a stream object
+    ///
+    /// # Arguments
+    /// * `dictionary` - Dictionary entries, without `/Length`
+    /// * `data` - Stream content, already encoded
+    fn add_stream(&mut self, dictionary: &str, data: &[u8]) -> usize {
+        let mut body = format!("<< {} /Length {} >>\nstream\n", dictionary, data.len()).into_bytes();
+        body.extend_from_slice(data);
+        body.extend_from_slice(b"\nendstream");
+        self.add_object(body)
+    }
+
+    /// Adds a photo, compressed as a JPEG file
+    ///
+    /// # Arguments
+    /// * `photo` - Photo
+    /// * `icc_profile` - Colour profile of the pixels, `None` for sRGB
+    pub fn add_photo(&mut self, photo: &RgbImage, icc_profile: Option<&[u8]>) -> Result<ImageId, PhotoBorderError> {
+        let mut jpeg = Vec::new();
+        JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY).encode_image(photo)?;
+
+        let color_space = match icc_profile {
+            Some(profile) => {
+                let profile = self.add_stream("/N 3 /Alternate /DeviceRGB /Filter /FlateDecode", &compress(profile));
+                format!("[/ICCBased {} 0 R]", profile)
+            }
+            None => "/DeviceRGB".to_string(),
+        };
+        let dictionary = format!(
+            "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace {} /BitsPerComponent 8 /Filter /DCTDecode",
+            photo.width(), photo.height(), color_space,
+        );
+        Ok(ImageId(self.add_stream(&dictionary, &jpeg)))
+    }
+
+    /// Adds an RGBA image, its alpha channel becoming a soft mask
+    pub fn add_rgba(&mut self, image: &RgbaImage) -> ImageId {
+        let (width, height) = image.dimensions();
+        let rgb: Vec<u8> = image.pixels().flat_map(|pixel| [pixel[0], pixel[1], pixel[2]]).collect();
+        let alpha: Vec<u8> = image.pixels().map(|pixel| pixel[3]).collect();
+
+        let mask = self.add_stream(
+            &format!(
+                "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceGray /BitsPerComponent 8 /Filter /FlateDecode",
+                width, height,
+            ),
+            &compress(&alpha),
+        );
+        ImageId(self.add_stream(
+            &format!(
+                "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB /BitsPerComponent 8 /SMask {} 0 R /Filter /FlateDecode",
+                width, height, mask,
+            ),
+            &compress(&rgb),
+        ))
+    }
+
+    /// Adds a page at the end of the document
+    pub fn add_page(&mut self, page: Page) {
+        let id = self.write_page(page);
+        self.pages.push(id);
+    }
+
+    /// Inserts a page before the page at `index` (0 for the first page)
+    pub fn insert_page(&mut self, index: usize, page: Page) {
+        let id = self.write_page(page);
+        self.pages.insert(index.min(self.pages.len()), id);
+    }
+
+    /// Number of pages
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Writes the content stream and the object of a page
+    fn write_page(&mut self, page: Page) -> usize {
+        let mut content = String::new();
+        let mut images = Vec::new();
+        let mut fonts = Vec::new();
+        // PDF coordinates start at the bottom-left corner
+        let flip = |y: f32| page.height - y;
+
+        for operation in &page.operations {
+            match operation {
+                Operation::Rect { x, y, width, height, color } => {
+                    let _ = writeln!(
+                        content,
+                        "{} rg {} {} {} {} re f",
+                        rgb(*color), number(*x), number(flip(y + height)), number(*width), number(*height),
+                    );
+                }
+                Operation::Image { image, x, y, width, height } => {
+                    let _ = writeln!(
+                        content,
+                        "q {} 0 0 {} {} {} cm /Im{} Do Q",
+                        number(*width), number(*height), number(*x), number(flip(y + height)), image.0,
+                    );
+                    if !images.contains(&image.0) {
+                        images.push(image.0);
+                    }
+                }
+                Operation::Text { x, y, size, style, color, text } => {
+                    let codes = self.encode_text(*style, text);
+                    let _ = writeln!(
+                        content,
+                        "BT {} rg /F{} {} Tf {} {} Td <{}> Tj ET",
+                        rgb(*color), style.index() + 1, number(*size), number(*x), number(flip(*y)), codes,
+                    );
+                    if !fonts.contains(style) {
+                        fonts.push(*style);
+                    }
+                }
+            }
+        }
+
+        let contents = self.add_stream("/Filter /FlateDecode", &compress(content.as_bytes()));
+
+        let mut resources = String::new();
+        if !images.is_empty() {
+            resources.push_str("/XObject <<");
+            for image in &images {
+                let _ = write!(resources, " /Im{} {} 0 R", image, image);
+            }
+            resources.push_str(" >> ");
+        }
+        if !fonts.is_empty() {
+            resources.push_str("/Font <<");
+            for style in &fonts {
+                let font = self.fonts[style.index()].as_ref().expect("font registered by encode_text");
+                let _ = write!(resources, " /F{} {} 0 R", style.index() + 1, font.id);
+            }
+            resources.push_str(" >>");
+        }
+
+        self.add_object(format!(
+            "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Resources << {} >> /Contents {} 0 R >>",
+            PAGES_ID, number(page.width), number(page.height), resources, contents,
+        ).into_bytes())
+    }
+
+    /// Encodes text as hexadecimal glyph IDs, registering the glyphs used
+    fn encode_text(&mut self, style: FontStyle, text: &str) -> String {
+        if self.fonts[style.index()].is_none() {
+            let id = self.reserve();
+            self.fonts[style.index()] = Some(EmbeddedFont { id, glyphs: BTreeMap::new() });
+        }
+        let font = style.font();
+        let embedded = self.fonts[style.index()].as_mut().expect("font registered above");
+
+        let mut codes = String::new();
+        for c in text.chars() {
+            let glyph = font.glyph(c).id().0;
+            // Glyph 0 is the "missing glyph" box
+            if glyph == 0 {
+                continue;
+            }
+            embedded.glyphs.entry(glyph).or_insert(c);
+            let _ = write!(codes, "{:04X}", glyph);
+        }
+        codes
+    }
+
+    /// Writes the embedded fonts: the font program, its descriptor,
+    /// glyph widths and the map back to Unicode
+    fn write_fonts(&mut self) {
+        for style in [FontStyle::Regular, FontStyle::Bold] {
+            let Some(embedded) = self.fonts[style.index()].take() else {
+                continue;
+            };
+            let font = style.font();
+            let data = style.data();
+            let metrics = font.v_metrics_unscaled();
+            let units = 1000.0 / font.units_per_em() as f32;
+            let (ascent, descent) = (metrics.ascent * units, metrics.descent * units);
+
+            let program = self.add_stream(&format!("/Length1 {} /Filter /FlateDecode", data.len()), &compress(data));
+            let descriptor = self.add_object(format!(
+                "<< /Type /FontDescriptor /FontName /{} /Flags 32 /FontBBox [-1000 {} 2000 {}] /ItalicAngle 0 \
+                 /Ascent {} /Descent {} /CapHeight {} /StemV 80 /FontFile2 {} 0 R >>",
+                style.base_name(), number(descent), number(ascent), number(ascent), number(descent), number(ascent), program,
+            ).into_bytes());
+
+            // Advance widths in thousandths of an em
+            let em = em_scale(&font, 1000.0);
+            let mut widths = String::new();
+            for (glyph, c) in &embedded.glyphs {
+                let _ = write!(widths, " {} [{}]", glyph, number(font.glyph(*c).scaled(em).h_metrics().advance_width));
+            }
+            let descendant = self.add_object(format!(
+                "<< /Type /Font /Subtype /CIDFontType2 /BaseFont /{} \
+                 /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> \
+                 /FontDescriptor {} 0 R /W [{}] /CIDToGIDMap /Identity >>",
+                style.base_name(), descriptor, widths,
+            ).into_bytes());
+
+            let to_unicode = self.add_stream("/Filter /FlateDecode", &compress(to_unicode_cmap(&embedded.glyphs).as_bytes()));
+            self.objects[embedded.id - 1] = format!(
+                "<< /Type /Font /Subtype /Type0 /BaseFont /{} /Encoding /Identity-H \
+                 /DescendantFonts [{} 0 R] /ToUnicode {} 0 R >>",
+                style.base_name(), descendant, to_unicode,
+            ).into_bytes();
+        }
+    }
+
+    /// Serializes the document
+    ///
+    /// # Returns
+    /// The content of the PDF file
+    pub fn finish(mut self) -> Vec<u8> {
+        self.write_fonts();
+
+        let kids: Vec<String> = self.pages.iter().map(|id| format!("{} 0 R", id)).collect();
+        self.objects[PAGES_ID - 1] = format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "), self.pages.len(),
+        ).into_bytes();
+        self.objects[CATALOG_ID - 1] = format!("<< /Type /Catalog /Pages {} 0 R >>", PAGES_ID).into_bytes();
+
+        let mut info = format!(
+            "<< /Producer {} /CreationDate (D:{})",
+            text_string("Schnaps-Shot"),
+            chrono::Local::now().format("%Y%m%d%H%M%S"),
+        );
+        if let Some(title) = &self.title {
+            let _ = write!(info, " /Title {}", text_string(title));
+        }
+        info.push_str(" >>");
+        let info = self.add_object(info.into_bytes());
+
+        // Header, with a comment of binary characters so that transfer
+        // tools treat the file as binary
+        let mut output = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::with_capacity(self.objects.len());
+        for (index, body) in self.objects.iter().enumerate() {
+            offsets.push(output.len());
+            let _ = writeln!(output, "{} 0 obj", index + 1);
+            output.extend_from_slice(body);
+            output.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref = output.len();
+        let _ = write!(output, "xref\n0 {}\n0000000000 65535 f \n", self.objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(output, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            output,
+            "trailer\n<< /Size {} /Root {} 0 R /Info {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.objects.len() + 1, CATALOG_ID, info, xref,
+        );
+        output
+    }
+}
+
+/// Compresses stream data with zlib
+fn compress(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).expect("writing to memory cannot fail");
+    encoder.finish().expect("writing to memory cannot fail")
+}
+
+/// Number with at most two decimals
+fn number(value: f32) -> String {
+    let text = format!("{:.2}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Fill colour operands
+fn rgb(color: Rgb<u8>) -> String {
+    format!("{} {} {}", number(color[0] as f32 / 255.0), number(color[1] as f32 / 255.0), number(color[2] as f32 / 255.0))
+}
+
+/// Text string in UTF-16 (document information)
+fn text_string(text: &str) -> String {
+    let mut hex = String::from("<FEFF");
+    for unit in text.encode_utf16() {
+        let _ = write!(hex, "{:04X}", unit);
+    }
+    hex.push('>');
+    hex
+}
+
+/// CMap mapping glyph IDs back to the characters they represent
+fn to_unicode_cmap(glyphs: &BTreeMap<u16, char>) -> String {
+    let mut cmap = String::from(
+        "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+         /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+         /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+         1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n",
+    );
+    let glyphs: Vec<_> = glyphs.iter().collect();
+    for block in glyphs.chunks(BFCHAR_BLOCK) {
+        let _ = writeln!(cmap, "{} beginbfchar", block.len());
+        for (glyph, c) in block {
+            let mut units = [0u16; 2];
+            let unicode: String = c.encode_utf16(&mut units).iter().map(|unit| format!("{:04X}", unit)).collect();
+            let _ = writeln!(cmap, "<{:04X}> <{}>", glyph, unicode);
+        }
+        cmap.push_str("endbfchar\n");
+    }
+    cmap.push_str("endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend\n");
+    cmap
+}
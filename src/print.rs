@@ -0,0 +1,203 @@
+//! Print layout
+//!
+//! Photo labs print files at a fixed size: in print mode the output has
+//! the exact pixel dimensions of a paper format at the chosen resolution
+//! (A4 at 300 dpi: 3508 × 2480 pixels). The photo is scaled to fit inside
+//! the margins and centred in the space they leave; the rest of the sheet
+//! is the border. The resolution is written to the file (JFIF density,
+//! PNG pHYs chunk, EXIF/TIFF resolution tags) so that it prints at the
+//! intended size, and the caption is set in points at a distance from the
+//! photo given in millimetres.
+//!
+//! The paper is turned to the orientation of the photo.
+
+use crate::{parse_sides, PhotoBorderError};
+use std::str::FromStr;
+
+/// Distance between the photo and the top of the caption, in millimetres
+const CAPTION_GAP: f32 = 3.0;
+
+const MM_PER_INCH: f32 = 25.4;
+
+/// Points per inch, the unit of the caption size
+const POINTS_PER_INCH: f32 = 72.0;
+
+/// Named paper formats, portrait, in millimetres
+const NAMED_SIZES: &[(&str, f32, f32)] = &[
+    ("a3", 297.0, 420.0),
+    ("a4", 210.0, 297.0),
+    ("a5", 148.0, 210.0),
+    ("a6", 105.0, 148.0),
+    ("letter", 215.9, 279.4),
+    ("legal", 215.9, 355.6),
+];
+
+/// Paper format, in millimetres
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaperSize {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl FromStr for PaperSize {
+    type Err = &'static str;
+
+    /// Parses a paper size: a named format ("A4", "letter") or
+    /// "<width>x<height>" followed by a unit ("13x18 cm", "8x10in", "100x150mm")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        if let Some((_, width, height)) = NAMED_SIZES.iter().find(|(name, _, _)| *name == s) {
+            return Ok(PaperSize { width: *width, height: *height });
+        }
+
+        let (dimensions, mm_per_unit) = if let Some(dimensions) = s.strip_suffix("mm") {
+            (dimensions, 1.0)
+        } else if let Some(dimensions) = s.strip_suffix("cm") {
+            (dimensions, 10.0)
+        } else if let Some(dimensions) = s.strip_suffix("in") {
+            (dimensions, MM_PER_INCH)
+        } else {
+            return Err("Invalid paper size (expected A3-A6, letter, legal or e.g. 13x18cm, 8x10in)");
+        };
+
+        let (width, height) = dimensions
+            .split_once('x')
+            .and_then(|(width, height)| Some((width.trim().parse::<f32>().ok()?, height.trim().parse::<f32>().ok()?)))
+            .ok_or("Invalid paper size (expected e.g. 13x18cm or 8x10in)")?;
+        if !(width > 0.0 && height > 0.0 && width.is_finite() && height.is_finite()) {
+            return Err("Paper dimensions must be positive numbers");
+        }
+
+        Ok(PaperSize { width: width * mm_per_unit, height: height * mm_per_unit })
+    }
+}
+
+/// Settings of the print mode
+#[derive(Debug, Clone)]
+pub struct PrintOptions {
+    /// Paper format
+    pub paper: PaperSize,
+    /// Print resolution in dots per inch
+    pub dpi: u32,
+    /// Minimum space around the photo in millimetres (top, right, bottom, left)
+    pub margins: (f32, f32, f32, f32),
+    /// Caption font size in points
+    pub caption_size: f32,
+}
+
+/// Position of the photo on the sheet
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PrintLayout {
+    /// Size of the scaled photo in pixels
+    pub photo: (u32, u32),
+    /// Border thickness in pixels (top, right, bottom, left)
+    pub borders: (u32, u32, u32, u32),
+}
+
+impl PrintOptions {
+    /// Builds the print settings from the command-line options
+    ///
+    /// # Arguments
+    /// * `paper` - Paper size ("A4", "13x18cm", "8x10in")
+    /// * `dpi` - Print resolution
+    /// * `margins` - Margins in millimetres ("all", "vertical,horizontal"
+    ///   or "top,right,bottom,left")
+    /// * `caption_size` - Caption font size in points
+    ///
+    /// # Returns
+    /// * `Err(PhotoBorderError::ConfigError)` if a value is invalid or the
+    ///   margins leave no room for the photo
+    pub fn parse(paper: &str, dpi: &str, margins: &str, caption_size: &str) -> Result<Self, PhotoBorderError> {
+        let config_error = |e: &str| PhotoBorderError::ConfigError(e.to_string());
+
+        let paper = PaperSize::from_str(paper).map_err(config_error)?;
+        let dpi = dpi.parse::<u32>()
+            .ok()
+            .filter(|dpi| (1..=u16::MAX as u32).contains(dpi))
+            .ok_or_else(|| config_error("Resolution must be a number of dots per inch"))?;
+        let margins = parse_sides(margins).map_err(config_error)?;
+        let caption_size = caption_size.parse::<f32>()
+            .ok()
+            .filter(|size| *size > 0.0 && size.is_finite())
+            .ok_or_else(|| config_error("Caption size must be a positive number of points"))?;
+
+        let (top, right, bottom, left) = margins;
+        if left + right >= paper.width.min(paper.height) || top + bottom >= paper.width.min(paper.height) {
+            return Err(config_error("Margins leave no room for the photo on this paper size"));
+        }
+
+        Ok(PrintOptions { paper, dpi, margins, caption_size })
+    }
+
+    /// Converts a length in millimetres to pixels at the print resolution
+    pub fn to_pixels(&self, mm: f32) -> u32 {
+        mm_to_pixels(mm, self.dpi)
+    }
+
+    /// Caption font size in pixels
+    pub fn caption_pixels(&self) -> f32 {
+        points_to_pixels(self.caption_size, self.dpi)
+    }
+
+    /// Distance between the photo and the caption in pixels
+    pub fn caption_gap(&self) -> u32 {
+        self.to_pixels(CAPTION_GAP)
+    }
+
+    /// Fits a photo on the sheet
+    ///
+    /// # Arguments
+    /// * `width`, `height` - Size of the photo in pixels
+    ///
+    /// # Returns
+    /// The size of the scaled photo and the borders filling the rest of
+    /// the sheet, in the orientation of the photo
+    pub fn layout(&self, width: u32, height: u32) -> PrintLayout {
+        // Turn the paper to the orientation of the photo
+        let (short, long) = (self.paper.width.min(self.paper.height), self.paper.width.max(self.paper.height));
+        let (paper_width, paper_height) = if width > height { (long, short) } else { (short, long) };
+        let (paper_width, paper_height) = (self.to_pixels(paper_width), self.to_pixels(paper_height));
+
+        let (top, right, bottom, left) = self.margins;
+        let (top, right, bottom, left) = (self.to_pixels(top), self.to_pixels(right), self.to_pixels(bottom), self.to_pixels(left));
+        let available_width = paper_width.saturating_sub(left + right).max(1);
+        let available_height = paper_height.saturating_sub(top + bottom).max(1);
+
+        // Largest size keeping the aspect ratio
+        let scale = (available_width as f64 / width as f64).min(available_height as f64 / height as f64);
+        let photo_width = ((width as f64 * scale).round() as u32).clamp(1, available_width);
+        let photo_height = ((height as f64 * scale).round() as u32).clamp(1, available_height);
+
+        // Centre the photo in the space left by the margins
+        let border_left = left + (available_width - photo_width) / 2;
+        let border_top = top + (available_height - photo_height) / 2;
+
+        PrintLayout {
+            photo: (photo_width, photo_height),
+            borders: (
+                border_top,
+                paper_width.saturating_sub(border_left + photo_width),
+                paper_height.saturating_sub(border_top + photo_height),
+                border_left,
+            ),
+        }
+    }
+}
+
+/// Converts a length in millimetres to pixels
+///
+/// # Arguments
+/// * `mm` - Length in millimetres
+/// * `dpi` - Resolution in dots per inch
+pub fn mm_to_pixels(mm: f32, dpi: u32) -> u32 {
+    (mm / MM_PER_INCH * dpi as f32).round() as u32
+}
+
+/// Converts a font size in points to pixels
+///
+/// # Arguments
+/// * `points` - Font size in points
+/// * `dpi` - Resolution in dots per inch
+pub fn points_to_pixels(points: f32, dpi: u32) -> f32 {
+    points / POINTS_PER_INCH * dpi as f32
+}
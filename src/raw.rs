@@ -0,0 +1,416 @@
+//! Camera raw files
+//!
+//! Raw files are not developed: every camera embeds a JPEG preview rendered
+//! with its own settings, usually at or near full resolution. The largest
+//! preview is extracted and framed like any JPEG, which is enough for
+//! proofs straight from the card.
+//!
+//! - NEF, ARW, DNG: TIFF structures; previews are found in the IFD chain
+//!   and the SubIFDs, as a JPEG interchange format pair (0x201/0x202) or as
+//!   a JPEG-compressed reduced-resolution strip
+//! - RAF: the file header points to the preview
+//! - CR3: ISO base media file; the full-size JPEG is the first track and a
+//!   smaller one is stored in the PRVW box
+//!
+//! The EXIF data is read from the raw file itself (TIFF IFDs, CR3 CMT
+//! boxes), or from the preview for RAF files.
+
+use crate::PhotoBorderError;
+use exif::{Context, Field, In, Reader, Tag, Value};
+use img_parts::{jpeg::Jpeg, Bytes, ImageEXIF};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+/// Extensions of the supported raw files
+pub const RAW_EXTENSIONS: &[&str] = &["cr3", "nef", "arw", "raf", "dng"];
+
+/// Signature of Fujifilm RAF files, and offset of the preview position
+const RAF_SIGNATURE: &[u8] = b"FUJIFILMCCD-RAW ";
+const RAF_PREVIEW_OFFSET: usize = 84;
+
+/// Major brand of Canon CR3 files
+const CR3_BRAND: &[u8] = b"crx ";
+
+/// UUID of the CR3 box holding the TIFF metadata blocks (CMT1-CMT4)
+const CR3_METADATA_UUID: [u8; 16] = [
+    0x85, 0xc0, 0xb6, 0x87, 0x82, 0x0f, 0x11, 0xe0, 0x81, 0x11, 0xf4, 0xce, 0x46, 0x2b, 0x6a, 0x48,
+];
+
+/// UUID of the CR3 box holding the PRVW preview
+const CR3_PREVIEW_UUID: [u8; 16] = [
+    0xea, 0xf4, 0x2b, 0x5e, 0x1c, 0x98, 0x4b, 0x88, 0xb9, 0xfb, 0xb7, 0xdc, 0x40, 0x6e, 0x4d, 0x16,
+];
+
+/// CR3 metadata boxes and the IFD each one holds (CMT3, the maker
+/// note, uses Canon offsets and is not copied)
+const CR3_METADATA_BOXES: [(&[u8; 4], Context); 3] = [
+    (b"CMT1", Context::Tiff),
+    (b"CMT2", Context::Exif),
+    (b"CMT4", Context::Gps),
+];
+
+/// TIFF tags used to locate previews
+const TAG_NEW_SUBFILE_TYPE: u16 = 254;
+const TAG_COMPRESSION: u16 = 259;
+const TAG_STRIP_OFFSETS: u16 = 273;
+const TAG_STRIP_BYTE_COUNTS: u16 = 279;
+const TAG_SUB_IFDS: u16 = 330;
+const TAG_JPEG_OFFSET: u16 = 0x201;
+const TAG_JPEG_LENGTH: u16 = 0x202;
+
+/// Limit on the IFDs visited, against corrupted files with IFD loops
+const MAX_IFDS: usize = 64;
+
+/// Container of a raw file
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RawContainer {
+    Tiff,
+    Raf,
+    Cr3,
+}
+
+impl RawContainer {
+    /// Detects the container from the file signature
+    fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(RAF_SIGNATURE) {
+            Some(RawContainer::Raf)
+        } else if data.get(4..8) == Some(b"ftyp") && data.get(8..12) == Some(CR3_BRAND) {
+            Some(RawContainer::Cr3)
+        } else if data.starts_with(b"II*\0") || data.starts_with(b"MM\0*") {
+            Some(RawContainer::Tiff)
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether a file is a supported raw file, from its extension
+pub fn is_raw(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| RAW_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Reads a raw file and detects its container
+fn read_raw_file(path: &Path) -> Result<(Vec<u8>, RawContainer), PhotoBorderError> {
+    let data = fs::read(path)?;
+    let container = RawContainer::detect(&data).ok_or_else(|| {
+        PhotoBorderError::ConfigError(format!("Unsupported raw file: {}", path.display()))
+    })?;
+    Ok((data, container))
+}
+
+/// Extracts the largest JPEG preview of a raw file
+///
+/// # Returns
+/// * `Ok(jpeg)` with the content of the preview
+/// * `Err(PhotoBorderError)` if the file cannot be read, its format is not
+///   supported, or it has no preview that can be decoded
+pub fn extract_preview(path: &Path) -> Result<Vec<u8>, PhotoBorderError> {
+    let (data, container) = read_raw_file(path)?;
+    let previews = match container {
+        RawContainer::Tiff => tiff_previews(&data),
+        RawContainer::Raf => raf_preview(&data).into_iter().collect(),
+        RawContainer::Cr3 => cr3_previews(&data),
+    };
+
+    previews.into_iter()
+        .filter(|preview| is_decodable_jpeg(preview))
+        .max_by_key(|preview| preview.len())
+        .map(<[u8]>::to_vec)
+        .ok_or_else(|| PhotoBorderError::ConfigError(format!("No JPEG preview found in {}", path.display())))
+}
+
+/// Reads the EXIF block of a raw file
+///
+/// # Returns
+/// * `Ok(block)` with a TIFF structure holding the EXIF data (the whole
+///   file for TIFF-based raws)
+/// * `Err(PhotoBorderError)` if the file has no EXIF data
+pub fn read_exif_block(path: &Path) -> Result<Vec<u8>, PhotoBorderError> {
+    let (data, container) = read_raw_file(path)?;
+    let no_exif = || PhotoBorderError::ConfigError(format!("No EXIF data found in {}", path.display()));
+
+    match container {
+        RawContainer::Tiff => Ok(data),
+        RawContainer::Raf => raf_preview(&data)
+            .and_then(|preview| Jpeg::from_bytes(Bytes::copy_from_slice(preview)).ok())
+            .and_then(|preview| preview.exif())
+            .map(|exif| exif.to_vec())
+            .ok_or_else(no_exif),
+        RawContainer::Cr3 => cr3_exif(&data)?.ok_or_else(no_exif),
+    }
+}
+
+/// Whether data is a JPEG that `image` can decode
+///
+/// Raw data is sometimes stored as lossless JPEG (DNG, older NEF), which
+/// is structurally a JPEG but has a lossless frame header.
+fn is_decodable_jpeg(data: &[u8]) -> bool {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return false;
+    }
+
+    // Walk the segments up to the frame header
+    let mut pos = 2;
+    while let (Some(0xFF), Some(&marker)) = (data.get(pos), data.get(pos + 1)) {
+        match marker {
+            // Baseline, extended and progressive Huffman frames
+            0xC0..=0xC2 => return true,
+            // Lossless, hierarchical and arithmetic frames, or image data
+            0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF | 0xDA => return false,
+            _ => {}
+        }
+        let Some(length) = data.get(pos + 2..pos + 4) else {
+            return false;
+        };
+        pos += 2 + u16::from_be_bytes([length[0], length[1]]) as usize;
+    }
+    false
+}
+
+/// Entries of an IFD, as (tag, values)
+type IfdEntries = Vec<(u16, Vec<u32>)>;
+
+/// Minimal TIFF reader, walking IFDs that the `exif` crate does not follow
+/// (SubIFDs)
+struct TiffReader<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> TiffReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        TiffReader { data, little_endian: data.starts_with(b"II") }
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let bytes: [u8; 2] = self.data.get(offset..offset + 2)?.try_into().ok()?;
+        Some(if self.little_endian { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) })
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if self.little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    }
+
+    /// Reads the IFD at `offset`
+    ///
+    /// # Returns
+    /// The (tag, values) entries, with the values of SHORT, LONG and IFD
+    /// entries (other types are left empty), and the offset of the next IFD
+    fn ifd(&self, offset: usize) -> Option<(IfdEntries, usize)> {
+        let count = self.u16(offset)? as usize;
+        let mut entries = Vec::with_capacity(count);
+
+        for index in 0..count {
+            let entry = offset + 2 + index * 12;
+            let tag = self.u16(entry)?;
+            let value_type = self.u16(entry + 2)?;
+            let value_count = self.u32(entry + 4)? as usize;
+
+            let size = match value_type {
+                3 => 2,
+                4 | 13 => 4,
+                _ => 0,
+            };
+            let values = if size == 0 || value_count == 0 {
+                Vec::new()
+            } else {
+                // Values up to 4 bytes are stored in the entry itself
+                let start = if size * value_count <= 4 { entry + 8 } else { self.u32(entry + 8)? as usize };
+                (0..value_count)
+                    .map(|i| match size {
+                        2 => self.u16(start + i * 2).map(u32::from),
+                        _ => self.u32(start + i * 4),
+                    })
+                    .collect::<Option<Vec<_>>>()?
+            };
+            entries.push((tag, values));
+        }
+
+        let next = self.u32(offset + 2 + count * 12)? as usize;
+        Some((entries, next))
+    }
+
+    /// Bytes at an offset and length read from the file
+    fn slice(&self, offset: u32, length: u32) -> Option<&'a [u8]> {
+        self.data.get(offset as usize..(offset as usize).checked_add(length as usize)?)
+    }
+}
+
+/// JPEG previews of a TIFF-based raw file
+fn tiff_previews(data: &[u8]) -> Vec<&[u8]> {
+    let tiff = TiffReader::new(data);
+    let mut previews = Vec::new();
+    let mut visited = HashSet::new();
+    let mut pending: Vec<usize> = tiff.u32(4).map(|offset| offset as usize).into_iter().collect();
+
+    while let Some(offset) = pending.pop() {
+        if offset == 0 || visited.len() >= MAX_IFDS || !visited.insert(offset) {
+            continue;
+        }
+        let Some((entries, next)) = tiff.ifd(offset) else {
+            continue;
+        };
+        let value = |tag: u16| entries.iter().find(|(t, _)| *t == tag).map(|(_, values)| values.as_slice());
+
+        pending.push(next);
+        pending.extend(value(TAG_SUB_IFDS).unwrap_or_default().iter().map(|&offset| offset as usize));
+
+        // JPEG interchange format (NEF JpgFromRaw, ARW preview, thumbnails)
+        if let (Some(&[offset]), Some(&[length])) = (value(TAG_JPEG_OFFSET), value(TAG_JPEG_LENGTH)) {
+            previews.extend(tiff.slice(offset, length));
+        }
+
+        // Reduced-resolution image stored as a single JPEG strip (DNG);
+        // 6 = old-style JPEG, 7 = JPEG
+        let is_preview = value(TAG_NEW_SUBFILE_TYPE) == Some(&[1]);
+        let is_jpeg = matches!(value(TAG_COMPRESSION), Some(&[6]) | Some(&[7]));
+        if let (true, true, Some(&[offset]), Some(&[length])) =
+            (is_preview, is_jpeg, value(TAG_STRIP_OFFSETS), value(TAG_STRIP_BYTE_COUNTS))
+        {
+            previews.extend(tiff.slice(offset, length));
+        }
+    }
+
+    previews
+}
+
+/// JPEG preview referenced by the RAF header
+fn raf_preview(data: &[u8]) -> Option<&[u8]> {
+    let read = |offset: usize| -> Option<usize> {
+        let bytes: [u8; 4] = data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(u32::from_be_bytes(bytes) as usize)
+    };
+    let offset = read(RAF_PREVIEW_OFFSET)?;
+    let length = read(RAF_PREVIEW_OFFSET + 4)?;
+    data.get(offset..offset.checked_add(length)?)
+}
+
+/// Child boxes of an ISO base media box, as (type, content)
+fn boxes(mut data: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let mut children = Vec::new();
+
+    while data.len() >= 8 {
+        let size = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as u64;
+        let (header, size) = match size {
+            // 64-bit size following the type
+            1 => match data.get(8..16) {
+                Some(large) => (16, u64::from_be_bytes(large.try_into().unwrap_or_default())),
+                None => break,
+            },
+            // Box extending to the end of its parent
+            0 => (8, data.len() as u64),
+            size => (8, size),
+        };
+        if size < header || size > data.len() as u64 {
+            break;
+        }
+        children.push((&data[4..8], &data[header as usize..size as usize]));
+        data = &data[size as usize..];
+    }
+
+    children
+}
+
+/// Content of the first child box of a given type
+fn child<'a>(data: &'a [u8], box_type: &[u8; 4]) -> Option<&'a [u8]> {
+    boxes(data).into_iter().find(|(t, _)| t == box_type).map(|(_, content)| content)
+}
+
+/// Content of a `uuid` box, after its UUID
+fn uuid_child<'a>(data: &'a [u8], uuid: &[u8; 16]) -> Option<&'a [u8]> {
+    boxes(data).into_iter()
+        .filter(|(t, _)| t == b"uuid")
+        .find(|(_, content)| content.starts_with(uuid))
+        .map(|(_, content)| &content[uuid.len()..])
+}
+
+/// JPEG previews of a CR3 file: the full-size image of the first track,
+/// and the PRVW preview
+fn cr3_previews(data: &[u8]) -> Vec<&[u8]> {
+    [cr3_track_preview(data), cr3_prvw_preview(data)].into_iter().flatten().collect()
+}
+
+/// First sample of the first track, which is a full-size JPEG
+fn cr3_track_preview(data: &[u8]) -> Option<&[u8]> {
+    let read_u32 = |bytes: &[u8], offset: usize| -> Option<u64> {
+        Some(u32::from_be_bytes(bytes.get(offset..offset + 4)?.try_into().ok()?) as u64)
+    };
+
+    let moov = child(data, b"moov")?;
+    let table = [b"trak", b"mdia", b"minf", b"stbl"].into_iter()
+        .try_fold(moov, |parent, box_type| child(parent, box_type))?;
+
+    // Sample size: fixed (non-zero) or first entry of the table
+    let sizes = child(table, b"stsz")?;
+    let size = match read_u32(sizes, 4)? {
+        0 => read_u32(sizes, 12)?,
+        size => size,
+    };
+
+    // Chunk offsets, 64-bit (co64) or 32-bit (stco)
+    let offset = match child(table, b"co64") {
+        Some(offsets) => u64::from_be_bytes(offsets.get(8..16)?.try_into().ok()?),
+        None => read_u32(child(table, b"stco")?, 8)?,
+    };
+
+    data.get(usize::try_from(offset).ok()?..usize::try_from(offset.checked_add(size)?).ok()?)
+}
+
+/// Preview of the PRVW box (1620x1080 on current bodies)
+fn cr3_prvw_preview(data: &[u8]) -> Option<&[u8]> {
+    let content = uuid_child(data, &CR3_PREVIEW_UUID)?;
+    // The PRVW box follows 8 bytes of unknown use
+    let prvw = child(content.get(8..)?, b"PRVW")?;
+    let length = u32::from_be_bytes(prvw.get(12..16)?.try_into().ok()?) as usize;
+    prvw.get(16..16 + length)
+}
+
+/// Merges the CMT boxes of a CR3 file into a single EXIF block
+///
+/// Each box is a TIFF structure whose IFD0 holds the fields of one IFD
+/// (main, EXIF, GPS); their tags are moved to the right context.
+fn cr3_exif(data: &[u8]) -> Result<Option<Vec<u8>>, PhotoBorderError> {
+    let Some(metadata) = child(data, b"moov").and_then(|moov| uuid_child(moov, &CR3_METADATA_UUID)) else {
+        return Ok(None);
+    };
+
+    let mut fields = Vec::new();
+    let mut little_endian = true;
+    for (box_type, context) in CR3_METADATA_BOXES {
+        let Some(block) = child(metadata, box_type) else {
+            continue;
+        };
+        let exif = Reader::new().read_raw(block.to_vec())?;
+        if context == Context::Tiff {
+            little_endian = exif.little_endian();
+        }
+        fields.extend(exif.fields()
+            .filter(|field| field.ifd_num == In::PRIMARY)
+            // Fields of unknown type cannot be written back
+            .filter(|field| !matches!(field.value, Value::Unknown(..)))
+            .map(|field| Field {
+                tag: Tag(context, field.tag.number()),
+                ifd_num: In::PRIMARY,
+                value: field.value.clone(),
+            }));
+    }
+
+    // The writer needs a non-empty primary IFD
+    if !fields.iter().any(|field| field.tag.context() == Context::Tiff) {
+        return Ok(None);
+    }
+
+    let mut writer = exif::experimental::Writer::new();
+    for field in &fields {
+        writer.push_field(field);
+    }
+    let mut buffer = Cursor::new(Vec::new());
+    writer.write(&mut buffer, little_endian)?;
+    Ok(Some(buffer.into_inner()))
+}
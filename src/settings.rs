@@ -0,0 +1,177 @@
+//! Persistent GUI settings
+//!
+//! The last used options of the GUI are stored in a TOML file in the
+//! per-user configuration directory (e.g. `~/.config/schnapsshot/settings.toml`
+//! on Linux, `%APPDATA%\schnapsshot\settings.toml` on Windows) so that they
+//! can be restored on the next launch.
+
+use crate::PhotoBorderError;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Maximum number of entries kept in the recent folders / fonts lists
+const MAX_RECENT: usize = 8;
+
+/// Settings saved between GUI sessions
+///
+/// Unknown or missing keys fall back to their default value, so that
+/// files written by older versions can still be loaded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Border type ("small", "medium", "large" or "custom")
+    pub border_type: String,
+    /// Whether EXIF data is printed on the border
+    pub show_exif: bool,
+    /// Last output directory (empty to save next to the originals)
+    pub output_directory: String,
+    /// Last custom font (empty for the embedded DejaVu Sans)
+    pub font_path: String,
+    /// Unit of the custom border sizes ("px" or "%")
+    pub size_unit: String,
+    /// Custom border sizes (top, right, bottom, left)
+    pub custom_sizes: [f32; 4],
+    /// Border colour as hex
+    pub border_color: String,
+    /// Text colour as hex
+    pub text_color: String,
+    /// Border fill ("solid", "blur", "linear", "radial", "texture" or "image")
+    pub border_fill: String,
+    /// Blur radius of the blurred fill, in percent of the smallest side
+    pub fill_blur: f32,
+    /// Darkening of the blurred fill, in percent
+    pub fill_darken: f32,
+    /// End colour of the gradient fills as hex
+    pub fill_color: String,
+    /// Direction of the linear gradient in degrees
+    pub fill_angle: f32,
+    /// Texture or background image of the texture and image fills
+    pub fill_file: String,
+    /// Casts a drop shadow of the photo onto the border
+    pub shadow: bool,
+    /// Shadow offset to the right and down, in percent of the smallest side
+    pub shadow_offset: f32,
+    /// Shadow blur radius, in percent of the smallest side
+    pub shadow_blur: f32,
+    /// Shadow opacity, in percent
+    pub shadow_opacity: f32,
+    /// Shadow colour as hex
+    pub shadow_color: String,
+    /// Draws a keyline around the photo
+    pub stroke: bool,
+    /// Keyline width, in pixels
+    pub stroke_width: f32,
+    /// Keyline colour as hex
+    pub stroke_color: String,
+    /// Recently used output directories, most recent first
+    pub recent_output_dirs: Vec<String>,
+    /// Recently used fonts, most recent first
+    pub recent_fonts: Vec<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            border_type: "small".to_string(),
+            show_exif: true,
+            output_directory: String::new(),
+            font_path: String::new(),
+            size_unit: "%".to_string(),
+            custom_sizes: [2.0, 2.0, 8.0, 2.0],
+            border_color: "#ffffff".to_string(),
+            text_color: "#404040".to_string(),
+            border_fill: "solid".to_string(),
+            fill_blur: 5.0,
+            fill_darken: 0.0,
+            fill_color: "#c0c0c0".to_string(),
+            fill_angle: 90.0,
+            fill_file: String::new(),
+            shadow: false,
+            shadow_offset: 1.0,
+            shadow_blur: 2.0,
+            shadow_opacity: 50.0,
+            shadow_color: "#000000".to_string(),
+            stroke: false,
+            stroke_width: 2.0,
+            stroke_color: "#000000".to_string(),
+            recent_output_dirs: Vec::new(),
+            recent_fonts: Vec::new(),
+        }
+    }
+}
+
+/// Per-user configuration directory of the application, if one exists
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("schnapsshot"))
+}
+
+/// Path of a user data file in the configuration directory, if it exists
+pub fn user_file(name: &str) -> Option<PathBuf> {
+    config_dir()
+        .map(|dir| dir.join(name))
+        .filter(|path| path.exists())
+}
+
+impl Settings {
+    /// Path of the settings file, if a configuration directory exists
+    pub fn path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join("settings.toml"))
+    }
+
+    /// Loads the saved settings
+    ///
+    /// Returns the defaults if no file exists yet. An unreadable file is
+    /// reported on stderr and replaced by the defaults rather than
+    /// preventing the GUI from starting.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Settings::default();
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content).unwrap_or_else(|e| {
+                eprintln!("Warning: Ignoring invalid settings file {}: {}", path.display(), e);
+                Settings::default()
+            }),
+            Err(_) => Settings::default(),
+        }
+    }
+
+    /// Writes the settings file, creating its directory if needed
+    pub fn save(&self) -> Result<(), PhotoBorderError> {
+        let path = Self::path().ok_or_else(|| {
+            PhotoBorderError::ConfigError("No configuration directory available".to_string())
+        })?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let content = toml::to_string_pretty(self)
+            .map_err(|e| PhotoBorderError::ConfigError(e.to_string()))?;
+        fs::write(path, content)?;
+
+        Ok(())
+    }
+
+    /// Records an output directory as the most recently used one
+    pub fn add_recent_output_dir(&mut self, dir: &str) {
+        push_recent(&mut self.recent_output_dirs, dir);
+    }
+
+    /// Records a font as the most recently used one
+    pub fn add_recent_font(&mut self, font: &str) {
+        push_recent(&mut self.recent_fonts, font);
+    }
+}
+
+/// Moves `value` to the front of `list`, dropping duplicates and old entries
+fn push_recent(list: &mut Vec<String>, value: &str) {
+    if value.is_empty() {
+        return;
+    }
+    list.retain(|v| v != value);
+    list.insert(0, value.to_string());
+    list.truncate(MAX_RECENT);
+}
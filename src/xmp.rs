@@ -0,0 +1,321 @@
+//! Descriptive metadata from XMP and IPTC
+//!
+//! Lightroom and most photo managers store titles, captions, keywords,
+//! star ratings and locations outside of EXIF:
+//!
+//! - In an XMP packet (RDF/XML) embedded in the file (JPEG APP1, PNG
+//!   iTXt, TIFF tag), or in an `.xmp` sidecar file next to it
+//! - In an IPTC-IIM block inside the Photoshop APP13 segment of JPEGs
+//!
+//! All sources are read and merged, later ones overriding earlier ones:
+//! IPTC-IIM, embedded XMP, then the sidecar (`photo.xmp` or
+//! `photo.jpg.xmp`), which is usually the most recent edit.
+
+use img_parts::jpeg::{markers, Jpeg};
+use img_parts::Bytes;
+use roxmltree::{Document, Node};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const NS_RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const NS_XML: &str = "http://www.w3.org/XML/1998/namespace";
+const NS_DC: &str = "http://purl.org/dc/elements/1.1/";
+const NS_XMP: &str = "http://ns.adobe.com/xap/1.0/";
+const NS_PHOTOSHOP: &str = "http://ns.adobe.com/photoshop/1.0/";
+const NS_IPTC_CORE: &str = "http://iptc.org/std/Iptc4xmpCore/1.0/xmlns/";
+
+/// Start and end of an XMP packet, found by scanning the file
+const XMP_START: &[u8] = b"<x:xmpmeta";
+const XMP_END: &[u8] = b"</x:xmpmeta>";
+
+/// Header of the Photoshop image resources segment (JPEG APP13)
+const PHOTOSHOP_HEADER: &[u8] = b"Photoshop 3.0\0";
+
+/// Photoshop image resource holding the IPTC-IIM block
+const IPTC_RESOURCE_ID: u16 = 0x0404;
+
+/// Titles, captions and other descriptive fields of a photo
+#[derive(Debug, Default, Clone)]
+pub struct DescriptiveMetadata {
+    /// Picture title (XMP `dc:title`, IPTC Object Name)
+    pub title: Option<String>,
+    /// Caption (XMP `dc:description`, IPTC Caption/Abstract)
+    pub caption: Option<String>,
+    /// Keywords (XMP `dc:subject`, IPTC Keywords)
+    pub keywords: Vec<String>,
+    /// Star rating from 1 to 5 (XMP `xmp:Rating`); 0 and -1 (rejected) are ignored
+    pub rating: Option<u8>,
+    /// Location name within the city (XMP `Iptc4xmpCore:Location`, IPTC Sub-location)
+    pub sublocation: Option<String>,
+    /// City
+    pub city: Option<String>,
+    /// Province or state
+    pub state: Option<String>,
+    /// Country name
+    pub country: Option<String>,
+}
+
+impl DescriptiveMetadata {
+    /// Whether no field was found
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.caption.is_none()
+            && self.keywords.is_empty()
+            && self.rating.is_none()
+            && self.location().is_none()
+    }
+
+    /// Keywords separated by commas
+    pub fn keywords_text(&self) -> Option<String> {
+        if self.keywords.is_empty() { None } else { Some(self.keywords.join(", ")) }
+    }
+
+    /// Rating as stars ("★★★")
+    pub fn rating_stars(&self) -> Option<String> {
+        self.rating.map(|rating| "★".repeat(rating as usize))
+    }
+
+    /// Location from the most to the least precise part
+    /// ("Tour Eiffel, Paris, Île-de-France, France")
+    pub fn location(&self) -> Option<String> {
+        let parts: Vec<&str> = [&self.sublocation, &self.city, &self.state, &self.country]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        if parts.is_empty() { None } else { Some(parts.join(", ")) }
+    }
+
+    /// Replaces the fields that are set in `other`
+    fn merge(&mut self, other: DescriptiveMetadata) {
+        fn take(field: &mut Option<String>, value: Option<String>) {
+            if value.is_some() {
+                *field = value;
+            }
+        }
+
+        take(&mut self.title, other.title);
+        take(&mut self.caption, other.caption);
+        take(&mut self.sublocation, other.sublocation);
+        take(&mut self.city, other.city);
+        take(&mut self.state, other.state);
+        take(&mut self.country, other.country);
+        if !other.keywords.is_empty() {
+            self.keywords = other.keywords;
+        }
+        if other.rating.is_some() {
+            self.rating = other.rating;
+        }
+    }
+}
+
+/// Reads the IPTC and XMP metadata of a photo and of its XMP sidecar
+///
+/// Missing or malformed metadata is skipped: an invalid sidecar only
+/// prints a warning, so that it does not prevent processing.
+pub fn read_metadata(path: &Path) -> DescriptiveMetadata {
+    let mut metadata = DescriptiveMetadata::default();
+
+    if let Ok(data) = fs::read(path) {
+        if let Some(iptc) = read_jpeg_iptc(&data) {
+            metadata.merge(iptc);
+        }
+        if let Some(xmp) = find_xmp_packet(&data).and_then(parse_xmp) {
+            metadata.merge(xmp);
+        }
+    }
+
+    if let Some(sidecar) = sidecar_path(path) {
+        match fs::read(&sidecar) {
+            Ok(data) => match parse_xmp(&data) {
+                Some(xmp) => metadata.merge(xmp),
+                None => eprintln!("Warning: Ignoring invalid XMP sidecar {}", sidecar.display()),
+            },
+            Err(e) => eprintln!("Warning: Could not read XMP sidecar {}: {}", sidecar.display(), e),
+        }
+    }
+
+    metadata
+}
+
+/// Path of the XMP sidecar of a photo: `photo.xmp` (Adobe) or
+/// `photo.jpg.xmp` (darktable, digiKam), if one exists
+fn sidecar_path(path: &Path) -> Option<PathBuf> {
+    let file_name = path.file_name()?.to_str()?;
+
+    [path.with_extension("xmp"), path.with_file_name(format!("{}.xmp", file_name))]
+        .into_iter()
+        .find(|sidecar| sidecar.is_file())
+}
+
+// ============================================================================
+// XMP
+// ============================================================================
+
+/// Finds the XMP packet in a file, whatever the container
+fn find_xmp_packet(data: &[u8]) -> Option<&[u8]> {
+    let start = find(data, XMP_START)?;
+    let end = start + find(&data[start..], XMP_END)? + XMP_END.len();
+    Some(&data[start..end])
+}
+
+/// Parses an XMP packet or sidecar
+///
+/// Properties may be written as attributes of `rdf:Description` or as
+/// child elements, both forms are accepted.
+fn parse_xmp(data: &[u8]) -> Option<DescriptiveMetadata> {
+    let text = std::str::from_utf8(data).ok()?;
+    // Sidecars may start with an <?xpacket?> processing instruction or a BOM
+    let text = text.trim_start_matches('\u{feff}');
+    let document = Document::parse(text).ok()?;
+
+    let rating = xmp_property(&document, NS_XMP, "Rating")
+        .and_then(|rating| rating.parse::<f32>().ok())
+        .filter(|rating| *rating >= 1.0)
+        .map(|rating| rating.round().min(5.0) as u8);
+
+    Some(DescriptiveMetadata {
+        title: xmp_property(&document, NS_DC, "title"),
+        caption: xmp_property(&document, NS_DC, "description"),
+        keywords: xmp_list(&document, NS_DC, "subject"),
+        rating,
+        sublocation: xmp_property(&document, NS_IPTC_CORE, "Location"),
+        city: xmp_property(&document, NS_PHOTOSHOP, "City"),
+        state: xmp_property(&document, NS_PHOTOSHOP, "State"),
+        country: xmp_property(&document, NS_PHOTOSHOP, "Country"),
+    })
+}
+
+/// Reads a simple or language-alternative XMP property
+///
+/// For language alternatives (`rdf:Alt`), the `x-default` entry is
+/// preferred, then the first one.
+fn xmp_property(document: &Document, namespace: &str, name: &str) -> Option<String> {
+    let descriptions = document.descendants().filter(|node| node.has_tag_name((NS_RDF, "Description")));
+    for description in descriptions {
+        if let Some(value) = description.attribute((namespace, name)) {
+            return non_empty(value);
+        }
+    }
+
+    let element = document.descendants().find(|node| node.has_tag_name((namespace, name)))?;
+    let items: Vec<Node> = list_items(element).collect();
+    if items.is_empty() {
+        return element.text().and_then(non_empty);
+    }
+
+    items.iter()
+        .find(|item| item.attribute((NS_XML, "lang")) == Some("x-default"))
+        .or_else(|| items.first())
+        .and_then(|item| item.text())
+        .and_then(non_empty)
+}
+
+/// Reads an XMP list property (`rdf:Bag` or `rdf:Seq`)
+fn xmp_list(document: &Document, namespace: &str, name: &str) -> Vec<String> {
+    document.descendants()
+        .find(|node| node.has_tag_name((namespace, name)))
+        .map(|element| {
+            list_items(element)
+                .filter_map(|item| item.text().and_then(non_empty))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `rdf:li` items of an XMP property
+fn list_items<'a, 'input>(element: Node<'a, 'input>) -> impl Iterator<Item = Node<'a, 'input>> {
+    element.descendants().filter(|node| node.has_tag_name((NS_RDF, "li")))
+}
+
+// ============================================================================
+// IPTC-IIM
+// ============================================================================
+
+/// Reads the IPTC-IIM block of a JPEG file
+fn read_jpeg_iptc(data: &[u8]) -> Option<DescriptiveMetadata> {
+    let jpeg = Jpeg::from_bytes(Bytes::copy_from_slice(data)).ok()?;
+    let resources = jpeg.segments_by_marker(markers::APP13)
+        .map(|segment| segment.contents())
+        .find(|contents| contents.starts_with(PHOTOSHOP_HEADER))?;
+
+    let iptc = photoshop_resource(&resources[PHOTOSHOP_HEADER.len()..], IPTC_RESOURCE_ID)?;
+    Some(parse_iim(iptc))
+}
+
+/// Finds an image resource in a Photoshop "8BIM" resource list
+///
+/// Each resource is: "8BIM", 2-byte ID, Pascal name padded to an even
+/// length, 4-byte size, data padded to an even length.
+fn photoshop_resource(mut data: &[u8], id: u16) -> Option<&[u8]> {
+    while data.len() >= 12 && data.starts_with(b"8BIM") {
+        let resource_id = u16::from_be_bytes([data[4], data[5]]);
+        let name_length = *data.get(6)? as usize;
+        let name_size = (name_length + 2) & !1;
+        let size_pos = 6 + name_size;
+        let size = u32::from_be_bytes(data.get(size_pos..size_pos + 4)?.try_into().ok()?) as usize;
+        let start = size_pos + 4;
+        let content = data.get(start..start + size)?;
+
+        if resource_id == id {
+            return Some(content);
+        }
+        data = data.get(start + ((size + 1) & !1)..)?;
+    }
+    None
+}
+
+/// Parses IPTC-IIM datasets (record 2, application record)
+///
+/// Each dataset is: 0x1C marker, record number, dataset number,
+/// 2-byte length, data. Text is read as UTF-8, falling back to Latin-1.
+fn parse_iim(mut data: &[u8]) -> DescriptiveMetadata {
+    let mut metadata = DescriptiveMetadata::default();
+
+    while data.len() >= 5 && data[0] == 0x1C {
+        let (record, dataset) = (data[1], data[2]);
+        let length = u16::from_be_bytes([data[3], data[4]]) as usize;
+        // Extended lengths (high bit set) are only used for binary data
+        if length & 0x8000 != 0 {
+            break;
+        }
+        let Some(value) = data.get(5..5 + length) else {
+            break;
+        };
+        data = &data[5 + length..];
+
+        if record != 2 {
+            continue;
+        }
+        let text = decode_text(value);
+        match dataset {
+            5 => metadata.title = non_empty(&text),
+            25 => metadata.keywords.extend(non_empty(&text)),
+            90 => metadata.city = non_empty(&text),
+            92 => metadata.sublocation = non_empty(&text),
+            95 => metadata.state = non_empty(&text),
+            101 => metadata.country = non_empty(&text),
+            120 => metadata.caption = non_empty(&text),
+            _ => {}
+        }
+    }
+
+    metadata
+}
+
+fn decode_text(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    if text.is_empty() { None } else { Some(text.to_string()) }
+}
+
+/// Position of `needle` in `haystack`
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}